@@ -3,6 +3,7 @@
 //! This module defines the fundamental data structures (nodes) that make up
 //! the Abstract Syntax Tree (AST) of the Nebulang language.
 
+use crate::compiler::span::Span;
 use std::vec::Vec; // Vec is used implicitly, but good practice to show context.
 
 /// Represents the root of a Nebulang program's Abstract Syntax Tree (AST).
@@ -17,17 +18,76 @@ pub struct Program {
 /// Defines the supported assignment operators in Nebulang.
 #[derive(Debug, Clone)]
 pub enum AssignmentOperator {
+    /// Plain reassignment of an existing variable (e.g., `x 5` after `x 1`).
+    Assign,
     /// Compound multiplication assignment (e.g., `x *= y`).
     Multiply,
     /// Compound addition assignment (e.g., `x += y`).
     Plus,
+    /// Compound subtraction assignment (e.g., `x -= y`).
+    Minus,
+    /// Compound division assignment (e.g., `x /= y`).
+    Divide,
+    /// Compound modulo assignment (e.g., `x %= y`).
+    Modulo,
+    /// Compound exponentiation assignment (e.g., `x ^= y`).
+    Power,
+}
+
+impl AssignmentOperator {
+    /// Returns the binary operation performed by a compound assignment.
+    ///
+    /// `x op= y` stores the result of `x op y`; plain reassignment has no operation.
+    pub fn binary_operator(&self) -> Option<BinaryOperator> {
+        match self {
+            Self::Assign => None,
+            Self::Multiply => Some(BinaryOperator::Multiply),
+            Self::Plus => Some(BinaryOperator::Add),
+            Self::Minus => Some(BinaryOperator::Subtract),
+            Self::Divide => Some(BinaryOperator::Divide),
+            Self::Modulo => Some(BinaryOperator::Modulo),
+            Self::Power => Some(BinaryOperator::Power),
+        }
+    }
+
+    /// Returns the operator as written in the source (e.g., `+=`).
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Assign => "assignment",
+            Self::Multiply => "*=",
+            Self::Plus => "+=",
+            Self::Minus => "-=",
+            Self::Divide => "/=",
+            Self::Modulo => "%=",
+            Self::Power => "^=",
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------
 
+/// A statement together with its location in the source.
+///
+/// For statements that open a block (loops, conditionals, functions), the span
+/// covers the header line only.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    /// The kind of statement and its contents.
+    pub kind: StatementKind,
+    /// The source span of the statement.
+    pub span: Span,
+}
+
+impl Statement {
+    /// Creates a statement node covering the given span.
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
 /// Represents all possible statement types in the Nebulang language.
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub enum StatementKind {
     /// A declaration for a mutable variable.
     VariableDeclaration {
         /// The name (identifier) of the variable.
@@ -47,17 +107,35 @@ pub enum Statement {
         /// The parts to be printed, which can be strings or expressions.
         parts: Vec<PrintPart>,
     },
-    /// A loop construct (e.g., a count-controlled loop).
+    /// A count-controlled loop over a range (e.g., `@ i, 1..10` or `@ i, 10..<0 step -2`).
+    ///
+    /// Without a step, the loop counts up by one; a negative step counts down. `end` and
+    /// `step` are evaluated once, before the first iteration.
     Loop {
         /// The loop variable identifier.
         variable: String,
         /// The starting expression for the loop range.
         start: Box<Expression>,
-        /// The ending expression for the loop range (exclusive or inclusive, defined by semantics).
+        /// The ending expression for the loop range.
         end: Box<Expression>,
+        /// `true` if `end` itself is part of the range (`..`), `false` for `..<`.
+        inclusive: bool,
+        /// The optional amount added to the loop variable after each iteration (`step n`).
+        step: Option<Box<Expression>>,
+        /// The list of statements within the loop body.
+        body: Vec<Statement>,
+    },
+    /// A loop that runs its body as long as a condition holds (e.g., `@ (guess != secret)`).
+    While {
+        /// The condition, tested before every iteration.
+        condition: Box<Expression>,
         /// The list of statements within the loop body.
         body: Vec<Statement>,
     },
+    /// Leaves the innermost enclosing loop (`break`).
+    Break,
+    /// Skips to the next iteration of the innermost enclosing loop (`continue`).
+    Continue,
     /// A conditional execution construct.
     If {
         /// The condition expression that determines execution.
@@ -65,6 +143,8 @@ pub enum Statement {
         /// The list of statements executed if the condition is true.
         then_branch: Vec<Statement>,
         /// Optional list of statements executed if the condition is false.
+        ///
+        /// An else-if (`!? (cond)`) is stored as an else branch holding a single nested `If`.
         else_branch: Option<Vec<Statement>>,
     },
     /// An assignment operation to update the value of an existing variable.
@@ -76,15 +156,146 @@ pub enum Statement {
         /// The specific assignment operator used (e.g., simple or compound).
         operator: AssignmentOperator,
     },
+    /// A declaration of a named collection, possibly nested (e.g., `[] cars as c`).
+    CollectionDeclaration {
+        /// The root collection and its sub-collections.
+        collection: Collection,
+    },
+    /// An assignment to a single array element (e.g., `scores{2} 95`).
+    ArrayAssignment {
+        /// The name of the array being modified.
+        array: String,
+        /// The index expression selecting the element.
+        index: Box<Expression>,
+        /// The new element value.
+        value: Box<Expression>,
+    },
+    /// A loop over the elements of an array (e.g., `@ score in scores`).
+    ForEach {
+        /// The loop variable, bound to each element in turn.
+        variable: String,
+        /// The array expression being iterated.
+        iterable: Box<Expression>,
+        /// The list of statements within the loop body.
+        body: Vec<Statement>,
+    },
+    /// A user-defined function (e.g., `# add(a, b)` followed by an indented body).
+    Function {
+        /// The name (identifier) of the function.
+        name: String,
+        /// The parameter names, in declaration order.
+        parameters: Vec<String>,
+        /// The list of statements forming the function body.
+        body: Vec<Statement>,
+    },
+    /// A return from the enclosing function (`=> value`).
+    Return {
+        /// The returned value, or `None` for a bare `=>`.
+        value: Option<Box<Expression>>,
+    },
+    /// A function call used as a statement, discarding any returned value.
+    Call {
+        /// The name of the called function.
+        function: String,
+        /// The argument expressions, in call order.
+        arguments: Vec<Expression>,
+    },
 }
 
 // -----------------------------------------------------------------------------
 
+/// An expression together with its location in the source.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    /// The kind of expression and its operands.
+    pub kind: ExpressionKind,
+    /// The source span of the expression.
+    pub span: Span,
+}
+
+impl Expression {
+    /// Creates an expression node covering the given span.
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Returns the value of an integer literal, possibly negated (e.g., `5` or `-(5)`).
+    pub fn integer_literal(&self) -> Option<i64> {
+        match &self.kind {
+            ExpressionKind::Integer(n) => Some(*n),
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Negate,
+                operand,
+            } => operand.integer_literal()?.checked_neg(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a float literal, possibly negated (e.g., `2.5` or `-(2.5)`).
+    pub fn float_literal(&self) -> Option<f64> {
+        match &self.kind {
+            ExpressionKind::Float(x) => Some(*x),
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Negate,
+                operand,
+            } => operand.float_literal().map(|x| -x),
+            _ => None,
+        }
+    }
+}
+
+/// Raises an integer to an integer power, as `^` does.
+///
+/// # Arguments
+///
+/// * `base` - The base.
+/// * `exponent` - The exponent.
+///
+/// # Returns
+///
+/// The power, or `None` if the exponent is negative or the power does not fit in 64 bits.
+pub fn checked_power(base: i64, exponent: i64) -> Option<i64> {
+    match (base, u32::try_from(exponent)) {
+        (_, Ok(exponent)) => base.checked_pow(exponent),
+        // Exponents beyond `u32::MAX` only fit for the bases 0, 1 and -1.
+        (0 | 1, Err(_)) if exponent > 0 => Some(base),
+        (-1, Err(_)) if exponent > 0 => Some(if exponent % 2 == 0 { 1 } else { -1 }),
+        _ => None,
+    }
+}
+
+/// Raises an integer to an integer power, wrapping around in two's complement like the
+/// multiplications of the runtime square-and-multiply do.
+///
+/// # Arguments
+///
+/// * `base` - The base.
+/// * `exponent` - The exponent.
+///
+/// # Returns
+///
+/// The power, wrapped to 64 bits.
+pub fn wrapping_power(base: i64, exponent: u64) -> i64 {
+    let mut result: i64 = 1;
+    let mut square = base;
+    let mut bits = exponent;
+    while bits > 0 {
+        if bits & 1 == 1 {
+            result = result.wrapping_mul(square);
+        }
+        square = square.wrapping_mul(square);
+        bits >>= 1;
+    }
+    result
+}
+
 /// Represents all possible expression types in the Nebulang language.
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub enum ExpressionKind {
     /// A literal integer value.
     Integer(i64),
+    /// A literal floating-point value.
+    Float(f64),
     /// A literal string value.
     String(String),
     /// A literal boolean value (`true` or `false`).
@@ -98,6 +309,22 @@ pub enum Expression {
         /// The index expression used to select the element.
         index: Box<Expression>,
     },
+    /// A substring of a string variable, from `start` to `end` inclusive (e.g., `name{0..2}`).
+    Slice {
+        /// The name of the sliced string variable.
+        string: String,
+        /// The index of the first character.
+        start: Box<Expression>,
+        /// The index of the last character.
+        end: Box<Expression>,
+    },
+    /// A unary operation applied to a single operand (e.g., `-x` or `NOT done`).
+    Unary {
+        /// The unary operator.
+        operator: UnaryOperator,
+        /// The operand expression.
+        operand: Box<Expression>,
+    },
     /// A binary operation involving two operands and an operator.
     Binary {
         /// The expression on the left-hand side of the operator.
@@ -107,6 +334,133 @@ pub enum Expression {
         /// The expression on the right-hand side of the operator.
         right: Box<Expression>,
     },
+    /// Access into a named collection by path (e.g., `c:col:0`, `[]cars:b:2` or `c:b`).
+    ///
+    /// Without an index, the expression denotes the whole (leaf) sub-collection.
+    CollectionAccess {
+        /// The first path segment: an alias, or a collection name.
+        root: String,
+        /// `true` if the root was written as `[]name`, which only matches collection names.
+        by_name: bool,
+        /// The remaining sub-collection names or aliases.
+        path: Vec<String>,
+        /// The optional element index (`:0` or `{i}`).
+        index: Option<Box<Expression>>,
+    },
+    /// The number of elements in an array or characters in a string (e.g., `#scores`).
+    Length(Box<Expression>),
+    /// A call to a user-defined function (e.g., `add(1, 2)`).
+    Call {
+        /// The name of the called function.
+        function: String,
+        /// The argument expressions, in call order.
+        arguments: Vec<Expression>,
+    },
+}
+
+// -----------------------------------------------------------------------------
+
+/// A named collection declared with `[] name as alias`.
+///
+/// A collection either holds elements directly (a leaf) or groups nested sub-collections.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    /// The name of the collection.
+    pub name: String,
+    /// The optional short alias introduced with `as`.
+    pub alias: Option<String>,
+    /// The elements of a leaf collection.
+    pub elements: Vec<Expression>,
+    /// The nested sub-collections.
+    pub children: Vec<Collection>,
+    /// The source span of the `[] name as alias` header.
+    pub span: Span,
+}
+
+impl Collection {
+    /// Checks whether a path segment refers to this collection (by name or alias).
+    pub fn matches(&self, segment: &str) -> bool {
+        self.name == segment || self.alias.as_deref() == Some(segment)
+    }
+
+    /// Resolves a path of names or aliases below this collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The sub-collection segments following this collection.
+    ///
+    /// # Returns
+    ///
+    /// The canonical storage name (names joined by `.`, e.g. `"cars.brands"`) and the
+    /// resolved collection, or `None` if a segment matches no sub-collection.
+    pub fn resolve(&self, path: &[String]) -> Option<(String, &Collection)> {
+        let mut canonical = self.name.clone();
+        let mut current = self;
+        for segment in path {
+            current = current
+                .children
+                .iter()
+                .find(|child| child.matches(segment))?;
+            canonical.push('.');
+            canonical.push_str(&current.name);
+        }
+        Some((canonical, current))
+    }
+
+    /// Visits every leaf collection together with its canonical storage name.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The canonical name of the parent (empty for a root collection).
+    /// * `visit` - Called with each leaf's canonical name and the leaf itself.
+    pub fn for_each_leaf(&self, prefix: &str, visit: &mut impl FnMut(String, &Collection)) {
+        let canonical = if prefix.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", prefix, self.name)
+        };
+
+        if self.children.is_empty() {
+            visit(canonical, self);
+        } else {
+            for child in &self.children {
+                child.for_each_leaf(&canonical, visit);
+            }
+        }
+    }
+}
+
+/// Resolves a collection path against the collections declared so far.
+///
+/// A `[]name` root only matches collection names; a bare root matches an alias first,
+/// then a name. Later declarations shadow earlier ones.
+///
+/// # Arguments
+///
+/// * `collections` - The declared root collections, in declaration order.
+/// * `root` - The first path segment.
+/// * `by_name` - Whether the root was written as `[]name`.
+/// * `path` - The remaining segments.
+///
+/// # Returns
+///
+/// The canonical storage name and the resolved collection, if the path is valid.
+pub fn resolve_collection_path<'a>(
+    collections: &'a [Collection],
+    root: &str,
+    by_name: bool,
+    path: &[String],
+) -> Option<(String, &'a Collection)> {
+    let root_collection = if by_name {
+        collections.iter().rev().find(|c| c.name == root)
+    } else {
+        collections
+            .iter()
+            .rev()
+            .find(|c| c.alias.as_deref() == Some(root))
+            .or_else(|| collections.iter().rev().find(|c| c.name == root))
+    }?;
+    root_collection.resolve(path)
 }
 
 // -----------------------------------------------------------------------------
@@ -118,6 +472,13 @@ pub enum PrintPart {
     String(String),
     /// An expression whose resulting value is to be printed.
     Expression(Box<Expression>),
+    /// A number printed with a fixed number of decimal places (e.g., `{rate:.2}`).
+    Formatted {
+        /// The numeric expression to print.
+        expression: Box<Expression>,
+        /// The number of digits after the decimal point.
+        precision: usize,
+    },
 }
 
 // -----------------------------------------------------------------------------
@@ -135,7 +496,7 @@ pub enum BinaryOperator {
     Divide,
     /// Modulo (remainder) operator (`%`).
     Modulo,
-    /// Exponentiation operator (`^` or similar).
+    /// Exponentiation operator (`^`).
     Power,
     /// Equality comparison operator (`==`).
     Equal,
@@ -153,4 +514,86 @@ pub enum BinaryOperator {
     And,
     /// Logical OR operator (`OR`).
     Or,
+    /// Bitwise AND operator (`&`).
+    BitAnd,
+    /// Bitwise OR operator (`|`).
+    BitOr,
+    /// Bitwise exclusive OR operator (`~`).
+    BitXor,
+    /// Left shift operator (`<<`).
+    ShiftLeft,
+    /// Arithmetic (sign-preserving) right shift operator (`>>`).
+    ShiftRight,
+}
+
+impl BinaryOperator {
+    /// Returns the operator as written in the source (e.g., `<<`).
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Modulo => "%",
+            Self::Power => "^",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::Greater => ">",
+            Self::LessEqual => "<=",
+            Self::GreaterEqual => ">=",
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "~",
+            Self::ShiftLeft => "<<",
+            Self::ShiftRight => ">>",
+        }
+    }
+}
+
+/// Defines all supported unary (prefix) operators in Nebulang.
+#[derive(Debug, Clone)]
+pub enum UnaryOperator {
+    /// Arithmetic negation (`-x`).
+    Negate,
+    /// Logical negation (`NOT x`).
+    Not,
+    /// Bitwise complement (`~x`).
+    BitNot,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_power_rejects_negative_exponents_and_overflow() {
+        assert_eq!(checked_power(2, 10), Some(1024));
+        assert_eq!(checked_power(-2, 63), Some(i64::MIN));
+        assert_eq!(checked_power(2, 63), None);
+        assert_eq!(checked_power(2, -1), None);
+        assert_eq!(checked_power(7, 0), Some(1));
+        // Huge exponents still fold for the bases whose powers stay small.
+        assert_eq!(checked_power(1, i64::MAX), Some(1));
+        assert_eq!(checked_power(-1, 1 << 40), Some(1));
+        assert_eq!(checked_power(-1, (1 << 40) + 1), Some(-1));
+        assert_eq!(checked_power(2, 1 << 40), None);
+    }
+
+    #[test]
+    fn wrapping_power_matches_twos_complement_multiplication() {
+        for (base, exponent) in [(3, 41), (2, 64), (-7, 33), (10, 19), (5, 0)] {
+            assert_eq!(
+                wrapping_power(base, exponent),
+                base.wrapping_pow(exponent as u32),
+                "{} ^ {}",
+                base,
+                exponent
+            );
+        }
+        assert_eq!(wrapping_power(-1, u64::MAX), -1);
+        assert_eq!(wrapping_power(2, u64::MAX), 0);
+    }
 }
@@ -4,7 +4,7 @@
 //! and implements logic for type compatibility checks.
 
 /// Represents the fundamental data types in Nebulang.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum Type {
     /// A whole number type, typically a 64-bit signed integer.
     Integer,
@@ -14,7 +14,12 @@ pub enum Type {
     String,
     /// A boolean type, representing `true` or `false`.
     Boolean,
+    /// A fixed-size array whose elements all share the given type.
+    Array(Box<Type>),
+    /// The absence of a value (e.g., the result of a function without `=> value`).
+    Void,
     /// A type that is currently unknown (e.g., during initial parsing or type inference).
+    #[default]
     Unknown,
 }
 
@@ -36,8 +41,50 @@ impl Type {
             (Self::Unknown, _) | (_, Self::Unknown) => true,
             // Integer and Float are compatible with each other.
             (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => true,
+            // Arrays are compatible when their element types are.
+            (Self::Array(a), Self::Array(b)) => a.is_compatible_with(b),
             // All other types must be strictly equal.
             (a, b) => a == b,
         }
     }
+
+    /// Checks if a value of type `value` can be stored in a slot of this type.
+    ///
+    /// Unlike [`Type::is_compatible_with`], this is directional: an Integer is promoted
+    /// when stored as a Float, but a Float is never truncated implicitly.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The type of the value being stored.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value can be stored without losing information.
+    pub fn accepts(&self, value: &Self) -> bool {
+        match (self, value) {
+            (Self::Integer, Self::Float) => false,
+            (Self::Array(a), Self::Array(b)) => a.accepts(b),
+            (target, value) => target.is_compatible_with(value),
+        }
+    }
+
+    /// Checks if this type is a number (or unknown, and thus possibly a number).
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::Integer | Self::Float | Self::Unknown)
+    }
+
+    /// Returns the type of an arithmetic operation on operands of these types.
+    ///
+    /// Integers are promoted to Float when mixed with floats.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The type of the other operand.
+    pub fn promote(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Float, Self::Integer | Self::Float | Self::Unknown)
+            | (Self::Integer | Self::Unknown, Self::Float) => Self::Float,
+            _ => self.clone(),
+        }
+    }
 }
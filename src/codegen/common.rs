@@ -9,8 +9,35 @@
 //! - Generating unique **labels** for control flow.
 //! - Creating the necessary assembly **data** and **BSS** sections.
 
+use crate::ast::nodes::{
+    AssignmentOperator, Collection, ExpressionKind, Statement, StatementKind, checked_power,
+    resolve_collection_path, wrapping_power,
+};
 use crate::ast::types::Type;
+use crate::compiler::analyzer::FunctionTypes;
+use crate::compiler::span::{SourceMap, Span};
 use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The System V AMD64 integer argument registers, in argument order.
+///
+/// Arguments beyond the sixth are passed on the stack.
+pub const ARGUMENT_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+/// Exit code of a program terminated by an out-of-bounds array access.
+pub const EXIT_BOUNDS_ERROR: i32 = 3;
+
+/// Exit code of a program that could not allocate memory for its strings.
+pub const EXIT_OUT_OF_MEMORY: i32 = 4;
+
+/// Exit code of a program terminated by a division by zero or a trapped integer overflow.
+pub const EXIT_ARITHMETIC_ERROR: i32 = 5;
+
+/// The minimum number of bytes by which the string heap grows at a time.
+const HEAP_CHUNK: usize = 64 * 1024;
+
+/// The number of bytes read from stdin at a time.
+const STDIN_CHUNK: usize = 4096;
 
 /// A central struct for managing shared state and utilities during the code generation process.
 ///
@@ -26,9 +53,96 @@ pub struct CodeGenCommon {
     /// Stores the inferred data type for each declared variable.
     /// Key: Variable name, Value: [`Type`] enum.
     pub variable_types: HashMap<String, Type>,
+    /// The types of every user-defined function, as inferred by the analyzer.
+    pub function_types: HashMap<String, FunctionTypes>,
     /// Stores the assembly memory address/label for each declared variable.
     /// Key: Variable name, Value: Assembly label name (e.g., "var_my_var").
+    ///
+    /// Inside a function body, entries are stack-frame slots instead (e.g., "rbp - 8").
     pub variable_addresses: HashMap<String, String>,
+    /// The named collections declared so far in the current scope, used to resolve
+    /// collection paths (e.g., `c:col`) to the storage of their leaves.
+    pub collections: Vec<Collection>,
+    /// The epilogue label of the function currently being generated, if any.
+    /// Return statements jump to this label.
+    pub return_label: Option<String>,
+    /// The return type of the function currently being generated, so that integers
+    /// returned by a Float function are converted.
+    pub return_type: Type,
+    /// Whether array accesses are checked against the array length at runtime.
+    pub bounds_checks: bool,
+    /// The jump targets of the loops enclosing the current statement, innermost last.
+    /// `break` and `continue` jump to the labels of the innermost loop.
+    pub loop_labels: Vec<LoopLabels>,
+    /// What integer addition, subtraction, multiplication and negation do when they overflow.
+    pub overflow: OverflowMode,
+    /// The name of the source file, shown in runtime error messages.
+    pub source_name: String,
+    /// The line index of the source file, used to turn spans into line numbers.
+    /// Without it, runtime error messages carry no location.
+    pub source_map: Option<SourceMap>,
+}
+
+/// The jump targets of a loop being generated.
+#[derive(Debug, Clone)]
+pub struct LoopLabels {
+    /// Where `continue` jumps: the loop step, or the condition check of a condition loop.
+    pub continue_label: String,
+    /// Where `break` jumps: the first instruction after the loop.
+    pub break_label: String,
+}
+
+/// Selects what happens when integer arithmetic overflows (`--overflow`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Wrap around in two's complement, like the hardware does.
+    Wrap,
+    /// Stop the program with a runtime error.
+    #[default]
+    Trap,
+    /// Clamp the result to the largest or smallest integer.
+    Saturate,
+}
+
+impl OverflowMode {
+    /// Raises an integer to an integer power, as `^` does at runtime in this mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The base.
+    /// * `exponent` - The exponent.
+    ///
+    /// # Returns
+    ///
+    /// The power, or `None` if the exponent is negative or the power overflows in trap mode.
+    pub fn power(self, base: i64, exponent: i64) -> Option<i64> {
+        let unsigned_exponent = u64::try_from(exponent).ok()?;
+        match self {
+            Self::Trap => checked_power(base, exponent),
+            Self::Wrap => Some(wrapping_power(base, unsigned_exponent)),
+            // The exact power is negative only for a negative base and an odd exponent.
+            Self::Saturate => {
+                checked_power(base, exponent).or(Some(if base < 0 && exponent % 2 == 1 {
+                    i64::MIN
+                } else {
+                    i64::MAX
+                }))
+            }
+        }
+    }
+}
+
+impl FromStr for OverflowMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "wrap" => Ok(Self::Wrap),
+            "trap" => Ok(Self::Trap),
+            "saturate" => Ok(Self::Saturate),
+            other => Err(format!("invalid overflow mode '{}'", other)),
+        }
+    }
 }
 
 impl CodeGenCommon {
@@ -42,7 +156,16 @@ impl CodeGenCommon {
             string_pool: HashMap::new(),
             label_counter: 0,
             variable_types: HashMap::new(),
+            function_types: HashMap::new(),
             variable_addresses: HashMap::new(),
+            collections: Vec::new(),
+            return_label: None,
+            return_type: Type::Void,
+            bounds_checks: true,
+            loop_labels: Vec::new(),
+            overflow: OverflowMode::Trap,
+            source_name: String::new(),
+            source_map: None,
         }
     }
 
@@ -79,7 +202,8 @@ impl CodeGenCommon {
 
     /// Registers a new variable in the code generation context.
     ///
-    /// This assigns a unique assembly address (label) and records its type.
+    /// This assigns a unique assembly address (label) and records its type. Variables
+    /// that already have an address (e.g., stack-frame slots of a function) keep it.
     ///
     /// # Arguments
     ///
@@ -90,11 +214,22 @@ impl CodeGenCommon {
     ///
     /// The assembly label assigned to the variable (e.g., `"var_my_counter"`).
     pub fn register_variable(&mut self, name: &str, var_type: Type) -> String {
-        let address = format!("var_{}", name);
         self.variable_types.insert(name.to_string(), var_type);
         self.variable_addresses
-            .insert(name.to_string(), address.clone());
-        address
+            .entry(name.to_string())
+            .or_insert_with(|| format!("var_{}", name))
+            .clone()
+    }
+
+    /// Returns the assembly label of a user-defined function.
+    ///
+    /// The `fn_` prefix keeps user functions apart from runtime helpers.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The identifier of the function.
+    pub fn function_label(name: &str) -> String {
+        format!("fn_{}", name)
     }
 
     /// Retrieves the assembly address (label) for a given variable name.
@@ -123,6 +258,37 @@ impl CodeGenCommon {
         self.variable_types.get(name)
     }
 
+    /// Spells out `text` as the operands of a `db` directive.
+    ///
+    /// NASM copies double-quoted strings byte for byte, without escapes, so quotes and
+    /// control characters are written as numbers to keep the bytes equal to the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The non-empty string to emit.
+    ///
+    /// # Returns
+    ///
+    /// The comma-separated quoted runs and byte values (e.g., `"p", 9, "q"`).
+    fn data_bytes(text: &str) -> String {
+        let mut operands = Vec::new();
+        let mut run = String::new();
+        for c in text.chars() {
+            if c == '"' || c.is_ascii_control() {
+                if !run.is_empty() {
+                    operands.push(format!("\"{}\"", std::mem::take(&mut run)));
+                }
+                operands.push((c as u8).to_string());
+            } else {
+                run.push(c);
+            }
+        }
+        if !run.is_empty() {
+            operands.push(format!("\"{}\"", run));
+        }
+        operands.join(", ")
+    }
+
     /// Generates the `.data` section of the assembly code, including all pooled strings.
     ///
     /// # Returns
@@ -142,20 +308,34 @@ impl CodeGenCommon {
                 .iter()
                 .find_map(|(k, v)| if v == label { Some(k) } else { None })
             {
-                // Escape the string for assembly, handling characters like quotes and newlines.
-                let escaped_string = string_value
-                    .replace('\\', "\\\\")
-                    .replace('"', "\\\"")
-                    .replace('\n', "\\n")
-                    .replace('\t', "\\t");
-                asm.push_str(&format!("{}: db \"{}\", 0\n", label, escaped_string));
+                // Strings are length-prefixed: the byte length sits just before the text.
+                asm.push_str(&format!("    dq {}\n", string_value.len()));
+                asm.push_str(&format!(
+                    "{}: db {}, 0\n",
+                    label,
+                    Self::data_bytes(string_value)
+                ));
             }
         }
 
         // Add standard static data elements.
         asm.push_str("newline: db 10, 0\n");
+        asm.push_str("    dq 0\n");
         asm.push_str("empty_str: db 0\n");
         asm.push_str("minus_sign: db \"-\", 0\n"); // Moved from generate_print_functions for better data organization
+        asm.push_str("runtime_error_prefix: db \"runtime error: \", 0\n");
+        asm.push_str("bounds_error_msg: db \"array index out of bounds\", 0\n");
+        asm.push_str("string_bounds_error_msg: db \"string index out of bounds\", 0\n");
+        asm.push_str("out_of_memory_msg: db \"out of memory\", 0\n");
+        asm.push_str("argument_bounds_error_msg: db \"argument index out of bounds\", 0\n");
+        asm.push_str("list_separator: db \", \", 0\n");
+        asm.push_str("quote_char: db '\"', 0\n");
+        asm.push_str("decimal_point: db \".\", 0\n");
+        asm.push_str("float_exponent: db \"e\", 0\n");
+        asm.push_str("float_inf: db \"inf\", 0\n");
+        asm.push_str("float_nan: db \"nan\", 0\n");
+        asm.push_str("bool_true: db \"TRUE\", 0\n");
+        asm.push_str("bool_false: db \"FALSE\", 0\n");
 
         asm
     }
@@ -179,11 +359,25 @@ impl CodeGenCommon {
         asm.push_str("    quantum_seed: resq 1\n");
         asm.push_str("    critical_section_1: resq 1\n");
         asm.push_str("    critical_section_2: resq 1\n");
+        // The string heap: the next free byte and the current program break.
+        asm.push_str("    heap_top: resq 1\n");
+        asm.push_str("    heap_end: resq 1\n");
+        // The process arguments and environment, captured from the initial stack in `_start`.
+        asm.push_str("    arg_count: resq 1\n");
+        asm.push_str("    arg_values: resq 1\n");
+        asm.push_str("    env_values: resq 1\n");
+        // Buffered stdin: the bytes read, the next unread one, and whether the last read
+        // found the end of input.
+        asm.push_str(&format!("    stdin_buffer: resb {}\n", STDIN_CHUNK));
+        asm.push_str("    stdin_pos: resq 1\n");
+        asm.push_str("    stdin_len: resq 1\n");
+        asm.push_str("    stdin_eof: resq 1\n");
 
         let variables = self.collect_variables(program);
-        // Reserve 8 bytes (resq 1) for each variable, assuming 64-bit architecture.
-        for var in &variables {
-            asm.push_str(&format!("    var_{}: resq 1\n", var));
+        // Reserve 8 bytes per slot for each variable, assuming 64-bit architecture.
+        // Arrays hold their length in the first slot, followed by the elements.
+        for (var, slots) in &variables {
+            asm.push_str(&format!("    var_{}: resq {}\n", var, slots));
         }
 
         asm
@@ -235,8 +429,31 @@ impl CodeGenCommon {
         label
     }
 
+    /// Generates assembly that stops the program with an arithmetic runtime error.
+    ///
+    /// The message is followed by the source location of the failing expression
+    /// (e.g., `division by zero at main.neb:3`).
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - What went wrong.
+    /// * `span` - The span of the failing expression.
+    pub fn generate_arithmetic_error(&mut self, message: &str, span: Span) -> String {
+        let message = match &self.source_map {
+            Some(source_map) => format!(
+                "{} at {}:{}",
+                message,
+                self.source_name,
+                source_map.location(span.start).line
+            ),
+            None => message.to_string(),
+        };
+        let label = self.add_string_to_pool(&message);
+        format!("    mov rsi, {}\n    jmp _nebula_arithmetic_error\n", label)
+    }
+
     /// Traverses the Abstract Syntax Tree (AST) to collect all unique variable names declared
-    /// in the program.
+    /// in the program, together with the number of 64-bit slots each one needs.
     ///
     /// # Arguments
     ///
@@ -244,8 +461,8 @@ impl CodeGenCommon {
     ///
     /// # Returns
     ///
-    /// A `Vec` of unique variable identifiers (names).
-    pub fn collect_variables(&self, program: &crate::ast::nodes::Program) -> Vec<String> {
+    /// A `Vec` of unique variable identifiers (names) and their slot counts.
+    pub fn collect_variables(&self, program: &crate::ast::nodes::Program) -> Vec<(String, usize)> {
         let mut variables = Vec::new();
         Self::collect_variables_from_statements(&program.statements, &mut variables);
         variables
@@ -253,46 +470,138 @@ impl CodeGenCommon {
 
     /// Recursively collects variable names from a slice of statements.
     ///
-    /// Variables are collected from `VariableDeclaration` and `Loop` statements.
+    /// Variables are collected from `VariableDeclaration`, `ArrayDeclaration`, collection
+    /// and loop statements, and from reassignments, which may copy a larger array. Scalars need one slot; an array needs one slot for its length
+    /// plus one per element. Each leaf of a collection is stored as an array under its
+    /// canonical name (e.g., `cars.brands`), and a variable declared from an array
+    /// (e.g., `myCars c:b`) receives as many slots as the copied array.
+    /// Function bodies are skipped, since their variables live in the function's stack frame.
     ///
     /// # Arguments
     ///
     /// * `statements` - A slice of [`Statement`] nodes to process.
     /// * `variables` - A mutable reference to the vector where unique variable names are accumulated.
-    fn collect_variables_from_statements(
-        statements: &[crate::ast::nodes::Statement],
-        variables: &mut Vec<String>,
+    pub fn collect_variables_from_statements(
+        statements: &[Statement],
+        variables: &mut Vec<(String, usize)>,
+    ) {
+        Self::collect_scope_variables(statements, variables, &mut Vec::new());
+    }
+
+    /// Collects variables from statements, tracking the collections declared so far so
+    /// that array copies from collection paths can be sized.
+    fn collect_scope_variables(
+        statements: &[Statement],
+        variables: &mut Vec<(String, usize)>,
+        collections: &mut Vec<Collection>,
     ) {
         for statement in statements {
-            match statement {
-                crate::ast::nodes::Statement::VariableDeclaration { name, .. } => {
-                    if !variables.contains(name) {
-                        variables.push(name.clone());
-                    }
+            match &statement.kind {
+                StatementKind::VariableDeclaration { name, value }
+                | StatementKind::Assignment {
+                    name,
+                    value,
+                    operator: AssignmentOperator::Assign,
+                } => {
+                    // Copies of arrays take the size of their source; scalars take one slot.
+                    let source = match &value.kind {
+                        ExpressionKind::Variable(source) => Some(source.clone()),
+                        ExpressionKind::CollectionAccess {
+                            root,
+                            by_name,
+                            path,
+                            index: None,
+                        } => resolve_collection_path(collections, root, *by_name, path)
+                            .map(|(canonical, _)| canonical),
+                        _ => None,
+                    };
+                    let slots = source
+                        .and_then(|source| {
+                            variables
+                                .iter()
+                                .find(|(existing, _)| *existing == source)
+                                .map(|(_, slots)| *slots)
+                        })
+                        .unwrap_or(1);
+                    Self::add_variable(variables, name, slots);
                 }
-                crate::ast::nodes::Statement::Loop { variable, body, .. } => {
-                    // Loop variable must also be considered declared
-                    if !variables.contains(variable) {
-                        variables.push(variable.clone());
-                    }
-                    Self::collect_variables_from_statements(body, variables);
+                StatementKind::ArrayDeclaration { name, elements } => {
+                    Self::add_variable(variables, name, elements.len() + 1);
+                }
+                StatementKind::CollectionDeclaration { collection } => {
+                    collection.for_each_leaf("", &mut |canonical, leaf| {
+                        Self::add_variable(variables, &canonical, leaf.elements.len() + 1);
+                    });
+                    collections.push(collection.clone());
+                }
+                StatementKind::Loop { variable, body, .. } => {
+                    // Loop variable must also be considered declared, with its hidden slots.
+                    Self::add_variable(variables, variable, 1);
+                    Self::add_variable(variables, &Self::end_slot(variable), 1);
+                    Self::add_variable(variables, &Self::step_slot(variable), 1);
+                    Self::collect_scope_variables(body, variables, collections);
+                }
+                StatementKind::ForEach { variable, body, .. } => {
+                    // The element variable plus a hidden slot for the current index.
+                    Self::add_variable(variables, variable, 1);
+                    Self::add_variable(variables, &Self::index_slot(variable), 1);
+                    Self::collect_scope_variables(body, variables, collections);
+                }
+                StatementKind::While { body, .. } => {
+                    Self::collect_scope_variables(body, variables, collections);
                 }
-                crate::ast::nodes::Statement::If {
+                StatementKind::If {
                     then_branch,
                     else_branch,
                     ..
                 } => {
-                    Self::collect_variables_from_statements(then_branch, variables);
+                    Self::collect_scope_variables(then_branch, variables, collections);
                     if let Some(else_branch) = else_branch {
-                        Self::collect_variables_from_statements(else_branch, variables);
+                        Self::collect_scope_variables(else_branch, variables, collections);
                     }
                 }
-                // ArrayDeclaration is missing from the match, assuming it's an oversight and should be handled if needed.
                 _ => {}
             }
         }
     }
 
+    /// Records a variable, keeping the largest slot count if it is declared several times.
+    fn add_variable(variables: &mut Vec<(String, usize)>, name: &str, slots: usize) {
+        match variables.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, existing_slots)) => *existing_slots = (*existing_slots).max(slots),
+            None => variables.push((name.to_string(), slots)),
+        }
+    }
+
+    /// Returns the name of the hidden slot holding the index of an element loop.
+    ///
+    /// The `.` cannot appear in Nebulang identifiers, so the name never clashes with user variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `variable` - The element loop variable.
+    pub fn index_slot(variable: &str) -> String {
+        format!("{}.index", variable)
+    }
+
+    /// Returns the name of the hidden slot holding the end of a range loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `variable` - The range loop variable.
+    pub fn end_slot(variable: &str) -> String {
+        format!("{}.end", variable)
+    }
+
+    /// Returns the name of the hidden slot holding the step of a range loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `variable` - The range loop variable.
+    pub fn step_slot(variable: &str) -> String {
+        format!("{}.step", variable)
+    }
+
     /// Generates the assembly code for essential runtime printing functions.
     ///
     /// These functions handle system calls for outputting strings and converting/printing numbers.
@@ -394,25 +703,903 @@ _nebula_print_number:
     pop rbp
     ret
 
-; String length function
-; Input: rsi = string pointer
-; Output: rax = length
-_nebula_strlen:
+; Print float function (64-bit IEEE 754 double)
+; Input: xmm0 = number, rdi = digits after the decimal point
+;        (negative: up to 6 digits, with trailing zeros removed)
+; Numbers from 2^63 up print in exponent notation (e.g., 1.5e20)
+_nebula_print_float:
+    push r12
+    push r13
+    push r14
+    push r15
+    push rbp
+    mov rbp, rsp
+    sub rsp, 32         ; Reserve stack space for the fraction digits
+    mov qword [rbp - 16], 0 ; Becomes 1 for exponent notation, [rbp - 8] = exponent
+
+    mov r12, rdi        ; r12 = precision
+    xor r13, r13        ; r13 = 1 if trailing zeros are removed
+    test r12, r12
+    jns .float_sign
+    mov r12, 6
+    mov r13, 1
+
+.float_sign:
+    movq r14, xmm0
+    btr r14, 63         ; r14 = |number|, CF = sign bit
+    jnc .float_special
+    mov rsi, minus_sign
+    mov rdx, 1
+    call _nebula_print
+
+.float_special:
+    ; An all-ones exponent encodes infinity (zero mantissa) or NaN.
+    mov rax, 0x7FF0000000000000
+    cmp r14, rax
+    jb .float_finite
+    mov rsi, float_nan
+    ja .float_special_print
+    mov rsi, float_inf
+.float_special_print:
+    mov rdx, 3
+    call _nebula_print
+    jmp .float_done
+
+.float_finite:
+    ; r15 = 10^precision
+    mov r15, 1
+    mov rcx, r12
+.float_scale:
+    test rcx, rcx
+    jz .float_split
+    imul r15, r15, 10
+    dec rcx
+    jmp .float_scale
+
+.float_split:
+    ; The integer part must fit in 64 bits; larger numbers print their mantissa in
+    ; [1, 10) below, followed by the decimal exponent.
+    mov rax, 0x43E0000000000000 ; 2^63
+    cmp r14, rax
+    jb .float_fixed
+    mov rax, 0x4024000000000000 ; 10.0
+    movq xmm3, rax
+    movq xmm0, r14
+    xor ecx, ecx
+.float_exponent:
+    comisd xmm0, xmm3
+    jb .float_power
+    divsd xmm0, xmm3
+    inc rcx
+    jmp .float_exponent
+.float_power:
+    mov [rbp - 8], rcx
+    mov rax, 0x3FF0000000000000 ; 1.0
+    movq xmm0, rax
+.float_power_step:      ; xmm0 = 10 ^ exponent
+    test rcx, rcx
+    jz .float_power_done
+    mulsd xmm0, xmm3
+    dec rcx
+    jmp .float_power_step
+.float_power_done:
+    movq xmm1, r14
+    divsd xmm1, xmm0    ; xmm1 = mantissa, divided once to limit rounding errors
+    mov rax, 0x4024000000000000 ; 10.0
+    movq xmm3, rax
+    mov rax, 0x3FF0000000000000 ; 1.0
+    movq xmm2, rax
+    comisd xmm1, xmm2
+    jae .float_round_up
+    mulsd xmm1, xmm3    ; The division loop overshot at a power of ten
+    dec qword [rbp - 8]
+.float_round_up:
+    cvtsi2sd xmm2, r15
+    movapd xmm0, xmm1
+    mulsd xmm0, xmm2
+    cvtsd2si rax, xmm0  ; rax = mantissa digits, rounded to nearest
+    imul rcx, r15, 10
+    cmp rax, rcx
+    jl .float_mantissa
+    divsd xmm1, xmm3    ; The mantissa rounds up to 10
+    inc qword [rbp - 8]
+.float_mantissa:
+    movq r14, xmm1
+    mov qword [rbp - 16], 1
+
+.float_fixed:
+    movq xmm0, r14
+    cvttsd2si rax, xmm0 ; rax = integer part (truncated)
+    cvtsi2sd xmm1, rax
+    subsd xmm0, xmm1    ; xmm0 = fractional part
+    cvtsi2sd xmm1, r15
+    mulsd xmm0, xmm1
+    cvtsd2si r14, xmm0  ; r14 = fraction digits, rounded to nearest
+    cmp r14, r15
+    jl .float_integer
+    inc rax             ; The fraction rounded up to a whole number
+    xor r14, r14
+.float_integer:
+    call _nebula_print_number
+
+    test r13, r13
+    jz .float_fraction
+.float_trim:
+    test r12, r12
+    jz .float_trimmed
+    mov rax, r14
+    xor rdx, rdx
+    mov rcx, 10
+    div rcx
+    test rdx, rdx
+    jnz .float_trimmed  ; Stop at the last non-zero digit
+    mov r14, rax
+    dec r12
+    jmp .float_trim
+.float_trimmed:
+    test r12, r12
+    jnz .float_fraction
+    mov r12, 1          ; Whole numbers keep one decimal place (e.g., 2.0)
+
+.float_fraction:
+    test r12, r12
+    jz .float_done
+    mov rsi, decimal_point
+    mov rdx, 1
+    call _nebula_print
+
+    ; Write the fraction digits right to left, padded with leading zeros.
+    mov rax, r14
+    mov rcx, 10
+    lea rdi, [rsp + r12]
+    mov r8, r12
+.float_digits:
+    xor rdx, rdx
+    div rcx
+    add dl, '0'
+    dec rdi
+    mov [rdi], dl
+    dec r8
+    jnz .float_digits
+
+    mov rsi, rsp
+    mov rdx, r12
+    call _nebula_print
+
+.float_done:
+    cmp qword [rbp - 16], 0
+    je .float_return
+    mov rsi, float_exponent
+    mov rdx, 1
+    call _nebula_print
+    mov rax, [rbp - 8]
+    call _nebula_print_number
+
+.float_return:
+    mov rsp, rbp
+    pop rbp
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    ret
+
+; Print string value function
+; Input: rsi = string pointer (the length is stored in the 8 bytes before the text)
+_nebula_print_string:
+    push rdx
+    mov rdx, [rsi - 8]
+    call _nebula_print
+    pop rdx
+    ret
+
+; Print boolean function
+; Input: rax = boolean (0 = FALSE, anything else = TRUE)
+_nebula_print_bool:
+    push rsi
+    push rdx
+    mov rsi, bool_true
+    mov rdx, 4
+    test rax, rax
+    jnz .bool_print
+    mov rsi, bool_false
+    mov rdx, 5
+.bool_print:
+    call _nebula_print
+    pop rdx
+    pop rsi
+    ret
+"#
+        .to_string()
+    }
+
+    /// Generates the assembly code for printing whole arrays.
+    ///
+    /// Elements are separated by `, `; string elements are printed in double quotes
+    /// (e.g., `"Toyota", "Nissan", "Honda"`).
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly function.
+    pub fn generate_array_print_function(&self) -> String {
+        r#"
+; Print array function
+; Input: rsi = array address (length slot),
+;        rdi = element kind (0 = integer, 1 = string, 2 = float, 3 = boolean)
+_nebula_print_array:
+    push r12
+    push r13
+    push r14
+    push r15
+    mov r12, rsi        ; Array address
+    mov r13, rdi        ; Element kind
+    xor r14, r14        ; Element index
+
+.array_loop:
+    cmp r14, [r12]
+    jge .array_done
+    test r14, r14
+    jz .array_element   ; No separator before the first element
+    mov rsi, list_separator
+    mov rdx, 2
+    call _nebula_print
+
+.array_element:
+    mov rax, [r12 + 8 + r14 * 8]
+    cmp r13, 1
+    je .array_string
+    cmp r13, 2
+    je .array_float
+    cmp r13, 3
+    je .array_bool
+    call _nebula_print_number
+    jmp .array_next
+
+.array_bool:
+    call _nebula_print_bool
+    jmp .array_next
+
+.array_float:
+    movq xmm0, rax
+    mov rdi, -1
+    call _nebula_print_float
+    jmp .array_next
+
+.array_string:
+    mov r15, rax        ; String pointer
+    mov rsi, quote_char
+    mov rdx, 1
+    call _nebula_print
+    mov rsi, r15
+    call _nebula_print_string
+    mov rsi, quote_char
+    mov rdx, 1
+    call _nebula_print
+
+.array_next:
+    inc r14
+    jmp .array_loop
+
+.array_done:
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    ret
+"#
+        .to_string()
+    }
+
+    /// Generates the assembly code for runtime string values.
+    ///
+    /// Strings built at runtime live on a simple bump-allocated heap, grown with `brk` and
+    /// never freed. Like pooled literals, they hold their length in the 8 bytes before the
+    /// text and end with a NUL byte.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly functions.
+    pub fn generate_string_functions(&self) -> String {
+        format!(
+            r#"
+; -------------------------------------------------------------------
+; Runtime Strings
+; -------------------------------------------------------------------
+
+; Allocate heap memory (8-byte aligned)
+; Input: rdi = size in bytes
+; Output: rax = pointer to the block
+_nebula_alloc:
+    push rsi
+    push rdi
+    push rdx
+    add rdi, 7
+    and rdi, -8
+    mov rax, [heap_top]
+    test rax, rax
+    jnz .alloc_ready
+    push rdi
+    mov rax, 12         ; sys_brk(0) returns the initial program break
+    xor rdi, rdi
+    syscall
+    pop rdi
+    mov [heap_top], rax
+    mov [heap_end], rax
+.alloc_ready:
+    mov rsi, rax        ; Start of the block
+    add rax, rdi        ; New heap top
+    cmp rax, [heap_end]
+    jbe .alloc_done
+    push rsi
+    push rax
+    lea rdi, [rax + {heap_chunk}]
+    mov rax, 12         ; sys_brk: move the program break past the new top
+    syscall
+    mov rdx, rax        ; The break actually set
+    pop rax
+    pop rsi
+    cmp rdx, rax
+    jb _nebula_out_of_memory
+    mov [heap_end], rdx
+.alloc_done:
+    mov [heap_top], rax
+    mov rax, rsi
+    pop rdx
+    pop rdi
+    pop rsi
+    ret
+
+; Allocate an uninitialized string
+; Input: rdi = length
+; Output: rax = string pointer (length stored, NUL-terminated)
+_nebula_str_new:
+    push rdi
+    add rdi, 9          ; Length slot, text and NUL byte
+    call _nebula_alloc
+    pop rdi
+    mov [rax], rdi
+    add rax, 8
+    mov byte [rax + rdi], 0
+    ret
+
+; Concatenate two strings
+; Input: rsi = left string, rdi = right string
+; Output: rax = new string
+_nebula_str_concat:
+    push rsi
+    push rdi
+    mov rdi, [rsi - 8]
+    mov rax, [rsp]
+    add rdi, [rax - 8]
+    call _nebula_str_new
+    pop rdx             ; Right string
+    pop rsi             ; Left string
+    mov rdi, rax
+    mov rcx, [rsi - 8]
+    rep movsb
+    mov rsi, rdx
+    mov rcx, [rsi - 8]
+    rep movsb
+    ret
+
+; Copy a range of characters into a new string
+; Input: rsi = string, rdi = first index, rdx = last index (inclusive)
+; Output: rax = new string
+_nebula_str_slice:
+    push rsi
+    push rdi
+    lea rcx, [rdx + 1]
+    sub rcx, rdi        ; Length of the slice
+    mov rdi, rcx
+    call _nebula_str_new
+    pop rdi
+    pop rsi
+    add rsi, rdi
+    mov rdi, rax
+    mov rcx, [rax - 8]
+    rep movsb
+    ret
+
+; Compare two strings lexicographically (byte by byte)
+; Input: rsi = left string, rdi = right string
+; Output: rax = -1 (left < right), 0 (equal) or 1 (left > right)
+_nebula_str_compare:
+    mov rcx, [rsi - 8]
+    mov rdx, [rdi - 8]
+    xor rax, rax        ; Byte index
+.compare_loop:
+    cmp rax, rcx
+    je .compare_left_end
+    cmp rax, rdx
+    je .compare_greater ; The right string is a prefix of the left one
+    mov r8b, [rsi + rax]
+    cmp r8b, [rdi + rax]
+    jb .compare_less
+    ja .compare_greater
+    inc rax
+    jmp .compare_loop
+.compare_left_end:
+    cmp rax, rdx
+    je .compare_equal
+.compare_less:
+    mov rax, -1
+    ret
+.compare_greater:
+    mov rax, 1
+    ret
+.compare_equal:
+    xor rax, rax
+    ret
+
+; Convert an integer into its decimal string
+; Input: rdi = number
+; Output: rax = new string
+_nebula_int_to_str:
+    push rbp
+    mov rbp, rsp
+    sub rsp, 32         ; Digit buffer, filled backwards from rbp
+    mov rax, rdi
+    mov rsi, rbp
+    mov rcx, 10
+    test rax, rax
+    jns .to_str_digits
+    neg rax             ; Unsigned division also handles the most negative number
+.to_str_digits:
+    xor rdx, rdx
+    div rcx
+    add dl, '0'
+    dec rsi
+    mov [rsi], dl
+    test rax, rax
+    jnz .to_str_digits
+    test rdi, rdi
+    jns .to_str_copy
+    dec rsi
+    mov byte [rsi], '-'
+.to_str_copy:
+    mov rdi, rbp
+    sub rdi, rsi        ; Length
+    push rsi
+    call _nebula_str_new
+    pop rsi
+    mov rdi, rax
+    mov rcx, [rax - 8]
+    rep movsb
+    mov rsp, rbp
+    pop rbp
+    ret
+"#,
+            heap_chunk = HEAP_CHUNK,
+        )
+    }
+
+    /// Generates the assembly code for the stdin routines behind `input()`, `input_int(...)`
+    /// and `eof()`.
+    ///
+    /// Input is read with `sys_read` into a buffer, a chunk at a time.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly functions.
+    pub fn generate_input_functions(&self) -> String {
+        format!(
+            r#"
+; Refill the stdin buffer
+; Output: rax = number of bytes read (0 at the end of input or after a read error)
+_nebula_stdin_fill:
+    push rdi
+    push rsi
+    push rdx
+    push rcx
+    push r11
+    xor eax, eax        ; sys_read
+    xor edi, edi        ; stdin file descriptor
+    mov rsi, stdin_buffer
+    mov rdx, {stdin_chunk}
+    syscall
+    test rax, rax
+    jns .filled
+    xor eax, eax        ; A read error ends the input
+.filled:
+    mov [stdin_len], rax
+    mov qword [stdin_pos], 0
+    pop r11
+    pop rcx
+    pop rdx
+    pop rsi
+    pop rdi
+    ret
+
+; Read a line from stdin, without its line terminator ("\n" or "\r\n")
+; Output: rax = new string (empty at the end of input)
+;         stdin_eof = 1 if the input had ended, 0 otherwise
+_nebula_read_line:
+    push r12
+    push r13
+    push r14
+    mov r12, empty_str  ; The line read so far
+    xor r14, r14        ; Becomes 1 once a byte of the line was read
+.fill:
+    mov rax, [stdin_pos]
+    cmp rax, [stdin_len]
+    jb .scan
+    call _nebula_stdin_fill
+    test rax, rax
+    jz .ended
+.scan:
+    mov r13, [stdin_pos] ; Start of the segment
+    mov rcx, r13
+.scan_byte:
+    cmp rcx, [stdin_len]
+    je .append          ; The segment runs to the end of the buffer
+    cmp byte [stdin_buffer + rcx], 10
+    je .append          ; The segment ends at the newline
+    inc rcx
+    jmp .scan_byte
+.append:
+    mov r14, 1
+    mov [stdin_pos], rcx
+    mov rdi, rcx
+    sub rdi, r13        ; Segment length
+    call _nebula_str_new
+    mov rdi, rax
+    lea rsi, [stdin_buffer + r13]
+    mov rcx, [rax - 8]
+    rep movsb
+    cmp qword [r12 - 8], 0
+    je .first_segment
+    mov rsi, r12
+    mov rdi, rax
+    call _nebula_str_concat
+.first_segment:
+    mov r12, rax
+    mov rax, [stdin_pos]
+    cmp rax, [stdin_len]
+    je .fill            ; No newline in the buffer yet: the line goes on
+    inc qword [stdin_pos] ; Consume the newline
+    jmp .strip
+.ended:
+    test r14, r14
+    jnz .strip          ; A last line without a newline still counts
+    mov qword [stdin_eof], 1
+    jmp .done
+.strip:
+    mov qword [stdin_eof], 0
+    mov rcx, [r12 - 8]
+    test rcx, rcx
+    jz .done
+    cmp byte [r12 + rcx - 1], 13
+    jne .done
+    dec rcx             ; Drop the carriage return of a "\r\n" terminator
+    mov [r12 - 8], rcx
+    mov byte [r12 + rcx], 0
+.done:
+    mov rax, r12
+    pop r14
+    pop r13
+    pop r12
+    ret
+
+; Read a line from stdin and parse it as a decimal integer
+; Surrounding spaces and tabs are ignored; a sign may precede the digits.
+; Input: rdi = value returned if the line is not an integer in range or the input has ended
+; Output: rax = the integer
+_nebula_read_int:
     push rdi
-    mov rdi, rsi        ; Source pointer
-    xor rcx, rcx
-    not rcx             ; Set rcx to maximum value
-    xor al, al          ; Search byte is null (0)
-    repne scasb         ; Find the null terminator
-    not rcx             ; rcx now holds the index of the null terminator
-    dec rcx             ; Subtract 1 to get the length
-    mov rax, rcx
+    call _nebula_read_line
     pop rdi
+    mov rsi, rax        ; Next character
+    mov rcx, [rax - 8]  ; Characters left
+.leading:
+    test rcx, rcx
+    jz .invalid
+    cmp byte [rsi], ' '
+    je .skip_leading
+    cmp byte [rsi], 9
+    jne .sign
+.skip_leading:
+    inc rsi
+    dec rcx
+    jmp .leading
+.sign:
+    xor r8, r8          ; 1 for a negative number
+    cmp byte [rsi], '+'
+    je .skip_sign
+    cmp byte [rsi], '-'
+    jne .digits
+    mov r8, 1
+.skip_sign:
+    inc rsi
+    dec rcx
+.digits:
+    ; The value is accumulated negated, so that the most negative integer fits.
+    xor rax, rax
+    xor rdx, rdx        ; Number of digits
+.digit:
+    test rcx, rcx
+    jz .trailing
+    movzx r9, byte [rsi]
+    sub r9, '0'
+    cmp r9, 9
+    ja .trailing        ; Not a digit (below '0' wraps around)
+    imul rax, rax, 10
+    jo .invalid
+    sub rax, r9
+    jo .invalid
+    inc rdx
+    inc rsi
+    dec rcx
+    jmp .digit
+.trailing:
+    test rdx, rdx
+    jz .invalid
+.trailing_space:
+    test rcx, rcx
+    jz .apply_sign
+    cmp byte [rsi], ' '
+    je .skip_trailing
+    cmp byte [rsi], 9
+    jne .invalid
+.skip_trailing:
+    inc rsi
+    dec rcx
+    jmp .trailing_space
+.apply_sign:
+    test r8, r8
+    jnz .parsed
+    neg rax
+    jo .invalid         ; The magnitude of the most negative integer has no positive
+.parsed:
+    ret
+.invalid:
+    mov rax, rdi
+    ret
+
+; Tell whether the last read found the end of input
+; Output: rax = 1 if it did, 0 otherwise
+_nebula_stdin_eof:
+    mov rax, [stdin_eof]
+    ret
+"#,
+            stdin_chunk = STDIN_CHUNK,
+        )
+    }
+
+    /// Generates the assembly code for the routines behind `argc()`, `arg(n)` and `env(name)`.
+    ///
+    /// They read the argument and environment vectors saved by `_start`.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly functions.
+    pub fn generate_process_functions(&self) -> String {
+        r#"
+; Copy a NUL-terminated string into a new string
+; Input: rsi = C string
+; Output: rax = new string
+_nebula_str_from_c:
+    push rsi
+    xor rdi, rdi
+.length:
+    cmp byte [rsi + rdi], 0
+    je .copy
+    inc rdi
+    jmp .length
+.copy:
+    call _nebula_str_new
+    pop rsi
+    mov rdi, rax
+    mov rcx, [rax - 8]
+    rep movsb
+    ret
+
+; Get the number of command-line arguments, including the program path
+; Output: rax = argument count
+_nebula_argc:
+    mov rax, [arg_count]
+    ret
+
+; Get a command-line argument
+; Input: rdi = index (0 is the program path)
+; Output: rax = new string
+_nebula_arg:
+    cmp rdi, [arg_count]
+    jae _nebula_argument_bounds_error ; Unsigned, so negative indices fail too
+    mov rax, [arg_values]
+    mov rsi, [rax + rdi * 8]
+    jmp _nebula_str_from_c
+
+; Look up an environment variable
+; Input: rdi = variable name
+; Output: rax = new string with its value, or the empty string if it is not set
+_nebula_env:
+    mov r8, [env_values] ; Next "NAME=value" entry
+    mov rcx, [rdi - 8]  ; Name length
+.entry:
+    mov rsi, [r8]
+    test rsi, rsi
+    jz .unset           ; The vector ends with a null pointer
+    xor rdx, rdx        ; Byte index
+.compare:
+    cmp rdx, rcx
+    je .name_end
+    mov al, [rsi + rdx]
+    cmp al, [rdi + rdx]
+    jne .next           ; Also stops at the NUL of a shorter entry
+    inc rdx
+    jmp .compare
+.name_end:
+    cmp byte [rsi + rdx], '='
+    jne .next
+    lea rsi, [rsi + rdx + 1]
+    jmp _nebula_str_from_c
+.next:
+    add r8, 8
+    jmp .entry
+.unset:
+    mov rax, empty_str
     ret
 "#
         .to_string()
     }
 
+    /// Generates the assembly code for the exponentiation routines behind `^`.
+    ///
+    /// Both use square-and-multiply, so a power takes one step per bit of the exponent.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly functions.
+    pub fn generate_power_functions(&self) -> String {
+        format!(
+            r#"
+; Integer power by square-and-multiply
+; Input: rdi = base, rsi = exponent (not negative); both are preserved
+; Output: rax = base ^ exponent, wrapped to 64 bits
+;         OF is set if the exact power does not fit in 64 bits
+_nebula_int_power:
+    mov rax, 1          ; Result
+    mov r8, rdi         ; The base squared once per exponent bit
+    mov r9, rsi         ; Exponent bits not consumed yet
+    xor edx, edx        ; Becomes 1 once a multiplication overflows
+.next_bit:
+    test r9, 1
+    jz .square
+    imul rax, r8
+    seto cl
+    or dl, cl
+.square:
+    shr r9, 1
+    jz .done            ; No square is taken after the last bit, so it cannot overflow
+    imul r8, r8
+    seto cl
+    or dl, cl
+    jmp .next_bit
+.done:
+    mov rcx, {integer_max}
+    add rcx, rdx        ; Overflows exactly when a multiplication did
+    ret
+
+; Float power by square-and-multiply
+; Input: xmm0 = base, rdi = exponent
+; Output: xmm0 = base ^ exponent (for a negative exponent, 1 / base ^ -exponent)
+_nebula_float_power:
+    movapd xmm1, xmm0   ; The base squared once per exponent bit
+    mov rax, {one:#x}
+    movq xmm0, rax      ; Result (start at 1.0)
+    mov rcx, rdi
+    test rcx, rcx
+    jns .next_bit
+    neg rcx             ; The most negative exponent still reads right as unsigned
+.next_bit:
+    test rcx, 1
+    jz .square
+    mulsd xmm0, xmm1
+.square:
+    shr rcx, 1
+    jz .sign
+    mulsd xmm1, xmm1
+    jmp .next_bit
+.sign:
+    test rdi, rdi
+    jns .done
+    movq xmm1, rax      ; 1.0
+    divsd xmm1, xmm0    ; result = 1.0 / result
+    movapd xmm0, xmm1
+.done:
+    ret
+"#,
+            integer_max = i64::MAX,
+            one = 1.0f64.to_bits(),
+        )
+    }
+
+    /// Generates the assembly code for the runtime error routines.
+    ///
+    /// Runtime errors print `runtime error: <message>` to stderr and terminate the program
+    /// with a dedicated exit code, so they can be told apart from normal failures.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the assembly functions.
+    pub fn generate_runtime_error_functions(&self) -> String {
+        format!(
+            r#"
+; -------------------------------------------------------------------
+; Runtime Error Handling
+; -------------------------------------------------------------------
+
+; Report a fatal runtime error and exit
+; Input: rsi = message pointer, rdx = message length, rdi = exit code
+_nebula_panic:
+    mov r12, rdi        ; Preserve exit code
+    mov r13, rsi        ; Preserve message
+    mov r14, rdx
+    mov rax, 1          ; sys_write
+    mov rdi, 2          ; stderr file descriptor
+    mov rsi, runtime_error_prefix
+    mov rdx, {prefix_len}
+    syscall
+    mov rax, 1
+    mov rdi, 2
+    mov rsi, r13
+    mov rdx, r14
+    syscall
+    mov rax, 1
+    mov rdi, 2
+    mov rsi, newline
+    mov rdx, 1
+    syscall
+    mov rax, 60         ; sys_exit
+    mov rdi, r12
+    syscall
+
+; Array index out of bounds (jumped to from checked accesses)
+_nebula_bounds_error:
+    mov rsi, bounds_error_msg
+    mov rdx, {bounds_len}
+    mov rdi, {bounds_code}
+    jmp _nebula_panic
+
+; String index or slice out of bounds (jumped to from checked accesses)
+_nebula_string_bounds_error:
+    mov rsi, string_bounds_error_msg
+    mov rdx, {string_bounds_len}
+    mov rdi, {bounds_code}
+    jmp _nebula_panic
+
+; Division by zero or trapped integer overflow
+; Input: rsi = length-prefixed message, including the source location
+_nebula_arithmetic_error:
+    mov rdx, [rsi - 8]
+    mov rdi, {arithmetic_code}
+    jmp _nebula_panic
+
+; Command-line argument index out of bounds (jumped to from `arg(n)`)
+_nebula_argument_bounds_error:
+    mov rsi, argument_bounds_error_msg
+    mov rdx, {argument_bounds_len}
+    mov rdi, {bounds_code}
+    jmp _nebula_panic
+
+; The string heap cannot grow any further
+_nebula_out_of_memory:
+    mov rsi, out_of_memory_msg
+    mov rdx, {memory_len}
+    mov rdi, {memory_code}
+    jmp _nebula_panic
+"#,
+            prefix_len = "runtime error: ".len(),
+            bounds_len = "array index out of bounds".len(),
+            string_bounds_len = "string index out of bounds".len(),
+            argument_bounds_len = "argument index out of bounds".len(),
+            bounds_code = EXIT_BOUNDS_ERROR,
+            memory_len = "out of memory".len(),
+            memory_code = EXIT_OUT_OF_MEMORY,
+            arithmetic_code = EXIT_ARITHMETIC_ERROR,
+        )
+    }
+
     /// Generates assembly code for "Quantum Protection" runtime security features.
     ///
     /// This includes functions for runtime initialization, temporal scrambling (obfuscation),
@@ -527,3 +1714,41 @@ impl Default for CodeGenCommon {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_modes_parse_from_their_flag_values() {
+        assert_eq!("wrap".parse(), Ok(OverflowMode::Wrap));
+        assert_eq!("trap".parse(), Ok(OverflowMode::Trap));
+        assert_eq!("saturate".parse(), Ok(OverflowMode::Saturate));
+        assert_eq!(
+            "panic".parse::<OverflowMode>(),
+            Err("invalid overflow mode 'panic'".to_string())
+        );
+        assert_eq!(OverflowMode::default(), OverflowMode::Trap);
+    }
+
+    #[test]
+    fn literal_powers_follow_the_overflow_mode() {
+        assert_eq!(OverflowMode::Trap.power(2, 10), Some(1024));
+        assert_eq!(OverflowMode::Trap.power(2, 64), None);
+        assert_eq!(OverflowMode::Wrap.power(2, 64), Some(0));
+        assert_eq!(
+            OverflowMode::Wrap.power(3, 41),
+            Some(3_i64.wrapping_pow(41))
+        );
+        assert_eq!(OverflowMode::Saturate.power(2, 64), Some(i64::MAX));
+        assert_eq!(OverflowMode::Saturate.power(-2, 65), Some(i64::MIN));
+        assert_eq!(OverflowMode::Saturate.power(-2, 64), Some(i64::MAX));
+        for mode in [
+            OverflowMode::Wrap,
+            OverflowMode::Trap,
+            OverflowMode::Saturate,
+        ] {
+            assert_eq!(mode.power(2, -1), None, "{:?}", mode);
+        }
+    }
+}
@@ -5,9 +5,18 @@
 //!
 //! All expression results are pushed onto the stack, following a stack-based expression evaluation model.
 
-use super::common::CodeGenCommon;
-use crate::ast::nodes::{BinaryOperator, Expression};
+use super::common::{ARGUMENT_REGISTERS, CodeGenCommon, OverflowMode};
+use crate::ast::nodes::{
+    BinaryOperator, Expression, ExpressionKind, UnaryOperator, resolve_collection_path,
+};
+use crate::ast::types::Type;
+use crate::compiler::builtins::Builtin;
 use crate::compiler::error::CompileError;
+use crate::compiler::span::Span;
+
+/// The largest 64-bit integer, as an assembly operand; saturated results are clamped to it
+/// (or to its bitwise not, the smallest integer).
+const INTEGER_MAX: &str = "0x7FFFFFFFFFFFFFFF";
 
 /// A static utility struct for generating assembly code from Nebulang expressions.
 pub struct ExpressionGenerator;
@@ -29,20 +38,159 @@ impl ExpressionGenerator {
         common: &mut CodeGenCommon,
         expr: &Expression,
     ) -> Result<String, CompileError> {
-        match expr {
-            Expression::Binary {
+        let asm = match &expr.kind {
+            ExpressionKind::Binary {
                 left,
                 operator,
                 right,
-            } => Self::generate_binary_expression(common, left, operator, right),
-            Expression::Variable(name) => Self::generate_variable_expression(common, name),
-            Expression::Integer(n) => Self::generate_integer_expression(*n),
-            Expression::String(s) => Self::generate_string_expression(common, s),
-            Expression::Boolean(b) => Self::generate_boolean_expression(*b),
-            Expression::ArrayAccess { array, index } => {
-                Self::generate_array_access(common, array, index)
+            } => Self::generate_binary_expression(common, left, operator, right, expr.span),
+            ExpressionKind::Unary { operator, operand } => {
+                Self::generate_unary_expression(common, operator, operand, expr.span)
+            }
+            ExpressionKind::Variable(name) => Self::generate_variable_expression(common, name),
+            ExpressionKind::Integer(n) => Self::generate_integer_expression(*n),
+            ExpressionKind::Float(x) => Self::generate_float_expression(*x),
+            ExpressionKind::String(s) => Self::generate_string_expression(common, s),
+            ExpressionKind::Boolean(b) => Self::generate_boolean_expression(*b),
+            ExpressionKind::ArrayAccess { array, index } => {
+                if common.get_variable_type(array) == Some(&Type::String) {
+                    Self::generate_string_slice(common, array, index, None)
+                } else {
+                    Self::generate_array_access(common, array, index)
+                }
+            }
+            ExpressionKind::Slice { string, start, end } => {
+                Self::generate_string_slice(common, string, start, Some(end))
+            }
+            ExpressionKind::CollectionAccess {
+                root,
+                by_name,
+                path,
+                index,
+            } => {
+                let canonical = Self::collection_storage(common, root, *by_name, path)?;
+                match index {
+                    Some(index) => Self::generate_array_access(common, &canonical, index),
+                    None => Err(CompileError::type_mismatch(format!(
+                        "Collection '{}' must be indexed or copied into a variable",
+                        canonical
+                    ))),
+                }
+            }
+            ExpressionKind::Length(operand)
+                if Self::static_type(common, operand) == Type::String =>
+            {
+                // The length of a string is stored just before its text.
+                let mut asm = Self::generate_expression(common, operand)?;
+                asm.push_str("    pop rax\n");
+                asm.push_str("    mov rax, [rax - 8]\n");
+                asm.push_str("    push rax\n");
+                Ok(asm)
+            }
+            ExpressionKind::Length(operand) => {
+                // The length is stored in the array's first slot.
+                let base = Self::array_base(common, operand)?;
+                Ok(format!("    mov rax, [{}]\n    push rax\n", base))
+            }
+            ExpressionKind::Call {
+                function,
+                arguments,
+            } => Self::generate_call(common, function, arguments),
+        };
+        // Errors without a more precise location point at this expression.
+        asm.map_err(|error| error.with_span(expr.span))
+    }
+
+    /// Generates assembly for a call to a user-defined function and pushes its result.
+    ///
+    /// Calls follow the System V AMD64 convention: the first six arguments are passed in
+    /// `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`, the rest on the stack, the result comes
+    /// back in RAX and the stack is 16-byte aligned at the `call` instruction.
+    /// Arguments are evaluated right to left so that the seventh argument ends up on top.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `function` - The name of the called function.
+    /// * `arguments` - The argument expressions.
+    pub fn generate_call(
+        common: &mut CodeGenCommon,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<String, CompileError> {
+        if let Some(builtin) = Builtin::from_name(function) {
+            return Self::generate_builtin_call(common, builtin, arguments);
+        }
+
+        let mut asm = String::new();
+        let register_count = arguments.len().min(ARGUMENT_REGISTERS.len());
+        let stack_count = arguments.len() - register_count;
+
+        // 1. Save the caller's stack pointer and align the stack. The padding keeps RSP
+        //    16-byte aligned once the saved pointer and the stack arguments are pushed.
+        asm.push_str("    mov rax, rsp\n");
+        asm.push_str("    and rsp, -16\n");
+        if stack_count.is_multiple_of(2) {
+            asm.push_str("    sub rsp, 8\n");
+        }
+        asm.push_str("    push rax\n");
+
+        // 2. Evaluate the arguments right to left, pushing each result. Floats are passed as
+        //    their raw bits in the integer registers; integers passed to a Float parameter
+        //    are converted first.
+        let parameters = common
+            .function_types
+            .get(function)
+            .map(|function| function.parameters.clone())
+            .unwrap_or_default();
+        for (index, argument) in arguments.iter().enumerate().rev() {
+            if parameters.get(index) == Some(&Type::Float) {
+                asm.push_str(&Self::generate_float_operand(common, argument)?);
+            } else {
+                asm.push_str(&Self::generate_expression(common, argument)?);
             }
         }
+
+        // 3. Move the first six arguments into their registers; the rest stay on the stack.
+        for register in &ARGUMENT_REGISTERS[..register_count] {
+            asm.push_str(&format!("    pop {}\n", register));
+        }
+
+        // 4. Call and restore the saved stack pointer (stored just above the stack arguments).
+        asm.push_str(&format!(
+            "    call {}\n",
+            CodeGenCommon::function_label(function)
+        ));
+        asm.push_str(&format!("    mov rsp, [rsp + {}]\n", stack_count * 8));
+        asm.push_str("    push rax\n");
+        Ok(asm)
+    }
+
+    /// Generates assembly for a call to a built-in function and pushes its result.
+    ///
+    /// Built-ins are runtime routines: the arguments are passed in the argument registers
+    /// and the routines do not depend on the stack alignment.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `builtin` - The called built-in.
+    /// * `arguments` - The argument expressions.
+    fn generate_builtin_call(
+        common: &mut CodeGenCommon,
+        builtin: Builtin,
+        arguments: &[Expression],
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        for argument in arguments.iter().rev() {
+            asm.push_str(&Self::generate_expression(common, argument)?);
+        }
+        for register in &ARGUMENT_REGISTERS[..arguments.len()] {
+            asm.push_str(&format!("    pop {}\n", register));
+        }
+        asm.push_str(&format!("    call {}\n", builtin.routine()));
+        asm.push_str("    push rax\n");
+        Ok(asm)
     }
 
     /// Generates assembly to push a literal 64-bit integer value onto the stack.
@@ -51,7 +199,45 @@ impl ExpressionGenerator {
     ///
     /// * `n` - The integer value.
     fn generate_integer_expression(n: i64) -> Result<String, CompileError> {
-        Ok(format!("    push {}\n", n))
+        // `push` only takes a 32-bit immediate; wider literals go through RAX.
+        if i32::try_from(n).is_ok() {
+            Ok(format!("    push {}\n", n))
+        } else {
+            Ok(format!("    mov rax, {}\n    push rax\n", n))
+        }
+    }
+
+    /// Generates assembly to push a literal float onto the stack.
+    ///
+    /// Floats travel through the stack as the raw bits of an IEEE 754 double.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The float value.
+    fn generate_float_expression(x: f64) -> Result<String, CompileError> {
+        Ok(format!("    mov rax, {:#x}\n    push rax\n", x.to_bits()))
+    }
+
+    /// Generates assembly that pushes an expression's value as a float.
+    ///
+    /// Integer values are converted (`cvtsi2sd`), so integers can be mixed with floats.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `expr` - The numeric expression.
+    pub fn generate_float_operand(
+        common: &mut CodeGenCommon,
+        expr: &Expression,
+    ) -> Result<String, CompileError> {
+        let mut asm = Self::generate_expression(common, expr)?;
+        if Self::static_type(common, expr) != Type::Float {
+            asm.push_str("    pop rax\n");
+            asm.push_str("    cvtsi2sd xmm0, rax\n");
+            asm.push_str("    movq rax, xmm0\n");
+            asm.push_str("    push rax\n");
+        }
+        Ok(asm)
     }
 
     /// Generates assembly to push a boolean value onto the stack (1 for true, 0 for false).
@@ -101,7 +287,8 @@ impl ExpressionGenerator {
 
     /// Generates assembly for accessing an element of an array.
     ///
-    /// This calculates the memory address: `[array_base_address + index * element_size (8)]`.
+    /// Arrays store their length in the first slot, so the element lives at
+    /// `[array_base_address + 8 + index * element_size (8)]`.
     ///
     /// # Arguments
     ///
@@ -122,15 +309,228 @@ impl ExpressionGenerator {
         // 1. Evaluate the index expression and push it onto the stack.
         let index_asm = Self::generate_expression(common, index)?;
         asm.push_str(&index_asm);
-        // 2. Pop index into RBX.
+        // 2. Pop index into RBX and check it against the length.
         asm.push_str("    pop rbx\n");
-        // 3. Calculate address and load value: array_base + index * 8 (assuming 64-bit/8-byte elements).
-        asm.push_str(&format!("    mov rax, [{} + rbx * 8]\n", address));
+        asm.push_str(&Self::generate_bounds_check(common, &address));
+        // 3. Calculate address and load value: array_base + 8 + index * 8 (assuming 64-bit/8-byte elements).
+        asm.push_str(&format!("    mov rax, [{} + 8 + rbx * 8]\n", address));
         // 4. Push the array element value onto the stack.
         asm.push_str("    push rax\n");
         Ok(asm)
     }
 
+    /// Generates assembly for indexing (`name{i}`) or slicing (`name{a..b}`) a string variable.
+    ///
+    /// Both produce a new string: indexing yields the single character at `i`, slicing the
+    /// characters from `a` to `b` inclusive (empty if `b` is `a - 1`).
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `string` - The identifier of the string variable.
+    /// * `start` - The index (of the first character).
+    /// * `end` - The index of the last character, or `None` when indexing.
+    fn generate_string_slice(
+        common: &mut CodeGenCommon,
+        string: &str,
+        start: &Expression,
+        end: Option<&Expression>,
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let address = common
+            .get_variable_address(string)
+            .ok_or_else(|| CompileError::undefined_variable(string))?
+            .clone();
+
+        // 1. Evaluate the bounds: RDI = first index, RDX = last index.
+        if let Some(end) = end {
+            asm.push_str(&Self::generate_expression(common, end)?);
+        }
+        asm.push_str(&Self::generate_expression(common, start)?);
+        asm.push_str("    pop rdi\n");
+        match end {
+            Some(_) => asm.push_str("    pop rdx\n"),
+            None => asm.push_str("    mov rdx, rdi\n"),
+        }
+        asm.push_str(&format!("    mov rsi, [{}]\n", address));
+
+        // 2. Check `start <= end + 1 <= length`; the unsigned comparisons also reject
+        //    negative indices.
+        if common.bounds_checks {
+            asm.push_str("    lea rcx, [rdx + 1]\n");
+            asm.push_str("    cmp rcx, [rsi - 8]\n");
+            asm.push_str("    ja _nebula_string_bounds_error\n");
+            asm.push_str("    cmp rdi, rcx\n");
+            asm.push_str("    ja _nebula_string_bounds_error\n");
+        }
+
+        // 3. Copy the characters into a new string.
+        asm.push_str("    call _nebula_str_slice\n");
+        asm.push_str("    push rax\n");
+        Ok(asm)
+    }
+
+    /// Generates the runtime bounds check for an index held in RBX.
+    ///
+    /// The unsigned comparison also rejects negative indices. Nothing is emitted when
+    /// bounds checks are disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The code generation context.
+    /// * `base` - The address of the array (its length slot).
+    pub fn generate_bounds_check(common: &CodeGenCommon, base: &str) -> String {
+        if common.bounds_checks {
+            format!("    cmp rbx, [{}]\n    jae _nebula_bounds_error\n", base)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Resolves an array-valued expression to the address of the array's storage.
+    ///
+    /// Arrays are not first-class values, so only named arrays and collection paths
+    /// (e.g., `c:b`) can be resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The code generation context.
+    /// * `expr` - The array expression (e.g., `Variable("scores")`).
+    pub fn array_base(common: &CodeGenCommon, expr: &Expression) -> Result<String, CompileError> {
+        let name = match &expr.kind {
+            ExpressionKind::Variable(name) => name.clone(),
+            ExpressionKind::CollectionAccess {
+                root,
+                by_name,
+                path,
+                index: None,
+            } => Self::collection_storage(common, root, *by_name, path)?,
+            _ => {
+                return Err(CompileError::type_mismatch("Expected the name of an array"));
+            }
+        };
+        common
+            .get_variable_address(&name)
+            .cloned()
+            .ok_or_else(|| CompileError::undefined_variable(&name))
+    }
+
+    /// Resolves a collection path to the canonical name under which its leaf is stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The code generation context.
+    /// * `root` - The first path segment (alias or name).
+    /// * `by_name` - Whether the root was written as `[]name`.
+    /// * `path` - The remaining segments.
+    fn collection_storage(
+        common: &CodeGenCommon,
+        root: &str,
+        by_name: bool,
+        path: &[String],
+    ) -> Result<String, CompileError> {
+        resolve_collection_path(&common.collections, root, by_name, path)
+            .map(|(canonical, _)| canonical)
+            .ok_or_else(|| CompileError::analysis(format!("Unknown collection '{}'", root)))
+    }
+
+    /// Determines the type of an expression as far as code generation needs to know it.
+    ///
+    /// Variables and function results take the types inferred by the analyzer; anything
+    /// of unknown type is treated as an integer.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The code generation context.
+    /// * `expr` - The expression to inspect.
+    pub fn static_type(common: &CodeGenCommon, expr: &Expression) -> Type {
+        let variable_type = |name: &str| {
+            common
+                .get_variable_type(name)
+                .cloned()
+                .unwrap_or(Type::Integer)
+        };
+
+        match &expr.kind {
+            ExpressionKind::String(_) => Type::String,
+            ExpressionKind::Float(_) => Type::Float,
+            ExpressionKind::Boolean(_) => Type::Boolean,
+            ExpressionKind::Variable(name) => variable_type(name),
+            ExpressionKind::Binary {
+                left,
+                operator: BinaryOperator::Add,
+                ..
+            } if Self::static_type(common, left) == Type::String => Type::String,
+            ExpressionKind::Binary {
+                left,
+                operator:
+                    BinaryOperator::Add
+                    | BinaryOperator::Subtract
+                    | BinaryOperator::Multiply
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Power,
+                right,
+            } => match (
+                Self::static_type(common, left),
+                Self::static_type(common, right),
+            ) {
+                (Type::Float, _) | (_, Type::Float) => Type::Float,
+                _ => Type::Integer,
+            },
+            ExpressionKind::Binary {
+                operator:
+                    BinaryOperator::Equal
+                    | BinaryOperator::NotEqual
+                    | BinaryOperator::Less
+                    | BinaryOperator::Greater
+                    | BinaryOperator::LessEqual
+                    | BinaryOperator::GreaterEqual
+                    | BinaryOperator::And
+                    | BinaryOperator::Or,
+                ..
+            } => Type::Boolean,
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Negate,
+                operand,
+            } => Self::static_type(common, operand),
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Not,
+                ..
+            } => Type::Boolean,
+            ExpressionKind::Call { function, .. } => match Builtin::from_name(function) {
+                Some(builtin) => builtin.return_type(),
+                None => common
+                    .function_types
+                    .get(function)
+                    .map(|function| function.return_type.clone())
+                    .filter(|return_type| !matches!(return_type, Type::Unknown | Type::Void))
+                    .unwrap_or(Type::Integer),
+            },
+            ExpressionKind::ArrayAccess { array, .. } => match variable_type(array) {
+                Type::Array(element_type) => *element_type,
+                Type::String => Type::String,
+                _ => Type::Integer,
+            },
+            ExpressionKind::Slice { .. } => Type::String,
+            ExpressionKind::CollectionAccess {
+                root,
+                by_name,
+                path,
+                index,
+            } => {
+                let Ok(canonical) = Self::collection_storage(common, root, *by_name, path) else {
+                    return Type::Integer;
+                };
+                match (variable_type(&canonical), index) {
+                    (Type::Array(element_type), Some(_)) => *element_type,
+                    (array, None) => array,
+                    _ => Type::Integer,
+                }
+            }
+            _ => Type::Integer,
+        }
+    }
+
     /// Generates assembly for a binary operation (e.g., arithmetic, comparison, logic).
     ///
     /// The operands are evaluated first, popped from the stack, the operation is performed,
@@ -142,12 +542,35 @@ impl ExpressionGenerator {
     /// * `left` - The left-hand side expression.
     /// * `operator` - The binary operator.
     /// * `right` - The right-hand side expression.
+    /// * `span` - The span of the operation, for runtime error messages.
     fn generate_binary_expression(
         common: &mut CodeGenCommon,
         left: &Expression,
         operator: &BinaryOperator,
         right: &Expression,
+        span: Span,
     ) -> Result<String, CompileError> {
+        if matches!(operator, BinaryOperator::And | BinaryOperator::Or) {
+            return Self::generate_logical_expression(common, left, operator, right);
+        }
+        if Self::is_float_operation(common, left, operator, right) {
+            return Self::generate_float_binary_expression(common, left, operator, right);
+        }
+        if Self::static_type(common, left) == Type::String
+            || Self::static_type(common, right) == Type::String
+        {
+            return Self::generate_string_binary_expression(common, left, operator, right);
+        }
+
+        // A power of two literals is computed at compile time, overflowing as the runtime
+        // would; the analyzer has rejected the ones that trap or have a negative exponent.
+        if let (BinaryOperator::Power, Some(base), Some(exponent)) =
+            (operator, left.integer_literal(), right.integer_literal())
+            && let Some(power) = common.overflow.power(base, exponent)
+        {
+            return Self::generate_integer_expression(power);
+        }
+
         let mut asm = String::new();
 
         // Evaluate right operand first (pushed second, popped first).
@@ -162,41 +585,58 @@ impl ExpressionGenerator {
         asm.push_str("    pop rax\n"); // RAX = Left Operand
         asm.push_str("    pop rbx\n"); // RBX = Right Operand
 
+        // An overflowing sum or difference has the wrong sign, so it saturates towards the
+        // opposite of the sign it got.
+        let saturate_sum = format!(
+            "    sar rax, 63\n    not rax\n    mov rcx, {}\n    xor rax, rcx\n",
+            INTEGER_MAX
+        );
         match operator {
             BinaryOperator::Add => {
                 asm.push_str("    add rax, rbx\n");
+                asm.push_str(&Self::generate_overflow_check(common, span, &saturate_sum));
                 asm.push_str("    push rax\n");
             }
             BinaryOperator::Subtract => {
                 asm.push_str("    sub rax, rbx\n"); // RAX - RBX
+                asm.push_str(&Self::generate_overflow_check(common, span, &saturate_sum));
                 asm.push_str("    push rax\n");
             }
             BinaryOperator::Multiply => {
+                // The sign of the exact product is the sign of RAX xor RBX.
+                if common.overflow == OverflowMode::Saturate {
+                    asm.push_str("    mov rdx, rax\n");
+                    asm.push_str("    xor rdx, rbx\n");
+                }
                 asm.push_str("    imul rax, rbx\n"); // RAX = RAX * RBX
+                let saturate = format!(
+                    "    mov rax, rdx\n    sar rax, 63\n    mov rcx, {}\n    xor rax, rcx\n",
+                    INTEGER_MAX
+                );
+                asm.push_str(&Self::generate_overflow_check(common, span, &saturate));
                 asm.push_str("    push rax\n");
             }
-            BinaryOperator::Divide => {
-                asm.push_str("    xor rdx, rdx\n"); // Clear RDX for 64-bit division
-                asm.push_str("    idiv rbx\n"); // Signed division: RAX / RBX -> Quotient in RAX
-                asm.push_str("    push rax\n");
-            }
-            BinaryOperator::Modulo => {
-                asm.push_str("    xor rdx, rdx\n"); // Clear RDX
-                asm.push_str("    idiv rbx\n"); // Signed division
-                asm.push_str("    push rdx\n"); // Remainder is in RDX
+            BinaryOperator::Divide | BinaryOperator::Modulo => {
+                asm.push_str(&Self::generate_division(common, operator, right, span));
             }
             BinaryOperator::Power => {
-                // Implementation of a simple integer power loop (RAX ^ RBX)
-                asm.push_str("    mov rcx, rbx\n"); // RCX = exponent
-                asm.push_str("    mov rbx, rax\n"); // RBX = base
-                asm.push_str("    mov rax, 1\n"); // RAX = result (start at 1)
-                asm.push_str("    test rcx, rcx\n");
-                asm.push_str("    jz .power_done\n"); // If exponent is 0, result is 1
-                asm.push_str(".power_loop:\n");
-                asm.push_str("    imul rax, rbx\n"); // result = result * base
-                asm.push_str("    dec rcx\n");
-                asm.push_str("    jnz .power_loop\n");
-                asm.push_str(".power_done:\n");
+                let exponent_label = common.next_label();
+                asm.push_str("    test rbx, rbx\n");
+                asm.push_str(&format!("    jns {}\n", exponent_label));
+                asm.push_str(
+                    &common
+                        .generate_arithmetic_error("negative exponent in an integer power", span),
+                );
+                asm.push_str(&format!("{}:\n", exponent_label));
+                asm.push_str("    mov rdi, rax\n"); // RDI = base
+                asm.push_str("    mov rsi, rbx\n"); // RSI = exponent
+                asm.push_str("    call _nebula_int_power\n");
+                // The exact power is negative only for a negative base and an odd exponent.
+                let saturate = format!(
+                    "    mov rax, rsi\n    and rax, 1\n    neg rax\n    and rax, rdi\n    sar rax, 63\n    mov rcx, {}\n    xor rax, rcx\n",
+                    INTEGER_MAX
+                );
+                asm.push_str(&Self::generate_overflow_check(common, span, &saturate));
                 asm.push_str("    push rax\n");
             }
             // --- Comparison Operators ---
@@ -237,25 +677,529 @@ impl ExpressionGenerator {
                 asm.push_str("    movzx rax, al\n");
                 asm.push_str("    push rax\n");
             }
-            // --- Logical Operators ---
-            // Operates on the 64-bit boolean values (1 or 0) on the stack.
-            BinaryOperator::And => {
+            // --- Bitwise Operators ---
+            // `AND` and `OR` short-circuit above; on booleans (0 or 1) they would agree anyway.
+            BinaryOperator::BitAnd | BinaryOperator::And => {
                 asm.push_str("    and rax, rbx\n");
                 asm.push_str("    push rax\n");
             }
-            BinaryOperator::Or => {
+            BinaryOperator::BitOr | BinaryOperator::Or => {
                 asm.push_str("    or rax, rbx\n");
                 asm.push_str("    push rax\n");
             }
+            BinaryOperator::BitXor => {
+                asm.push_str("    xor rax, rbx\n");
+                asm.push_str("    push rax\n");
+            }
+            // Shift counts are taken modulo 64 by the hardware.
+            BinaryOperator::ShiftLeft => {
+                asm.push_str("    mov rcx, rbx\n");
+                asm.push_str("    shl rax, cl\n");
+                asm.push_str("    push rax\n");
+            }
+            BinaryOperator::ShiftRight => {
+                asm.push_str("    mov rcx, rbx\n");
+                asm.push_str("    sar rax, cl\n"); // Arithmetic shift keeps the sign
+                asm.push_str("    push rax\n");
+            }
+        }
+
+        Ok(asm)
+    }
+
+    /// Generates assembly for `AND` or `OR` and pushes the result (0 or 1).
+    ///
+    /// The right operand is only evaluated when the left one does not decide the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `left` - The left-hand side condition.
+    /// * `operator` - Either [`BinaryOperator::And`] or [`BinaryOperator::Or`].
+    /// * `right` - The right-hand side condition.
+    fn generate_logical_expression(
+        common: &mut CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+    ) -> Result<String, CompileError> {
+        let false_label = common.next_label();
+        let end_label = common.next_label();
+        let mut asm =
+            Self::generate_logical_jump(common, left, operator, right, &false_label, false)?;
+        asm.push_str("    push 1\n");
+        asm.push_str(&format!("    jmp {}\n", end_label));
+        asm.push_str(&format!("{}:\n", false_label));
+        asm.push_str("    push 0\n");
+        asm.push_str(&format!("{}:\n", end_label));
+        Ok(asm)
+    }
+
+    /// Generates a jump taken when a condition has the given value; otherwise execution
+    /// falls through, and nothing is left on the stack either way.
+    ///
+    /// Conditions are branched on directly where possible: `AND` and `OR` short-circuit,
+    /// `NOT` inverts the jump, and comparisons of integers or booleans jump on the flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `condition` - The boolean expression.
+    /// * `target` - The label to jump to.
+    /// * `jump_if` - The value of the condition for which the jump is taken.
+    pub fn generate_condition_jump(
+        common: &mut CodeGenCommon,
+        condition: &Expression,
+        target: &str,
+        jump_if: bool,
+    ) -> Result<String, CompileError> {
+        if let ExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } = &condition.kind
+            && let Some((if_true, if_false)) =
+                Self::integer_comparison_jumps(common, left, operator, right)
+        {
+            let mut asm = Self::generate_expression(common, right)?;
+            asm.push_str(&Self::generate_expression(common, left)?);
+            asm.push_str("    pop rax\n"); // RAX = Left Operand
+            asm.push_str("    pop rbx\n"); // RBX = Right Operand
+            asm.push_str("    cmp rax, rbx\n");
+            let jump = if jump_if { if_true } else { if_false };
+            asm.push_str(&format!("    {} {}\n", jump, target));
+            return Ok(asm);
+        }
+
+        match &condition.kind {
+            ExpressionKind::Boolean(value) if *value == jump_if => {
+                Ok(format!("    jmp {}\n", target))
+            }
+            ExpressionKind::Boolean(_) => Ok(String::new()),
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Not,
+                operand,
+            } => Self::generate_condition_jump(common, operand, target, !jump_if),
+            ExpressionKind::Binary {
+                left,
+                operator: operator @ (BinaryOperator::And | BinaryOperator::Or),
+                right,
+            } => Self::generate_logical_jump(common, left, operator, right, target, jump_if),
+            _ => {
+                let mut asm = Self::generate_expression(common, condition)?;
+                asm.push_str("    pop rax\n");
+                asm.push_str("    test rax, rax\n");
+                let jump = if jump_if { "jnz" } else { "jz" };
+                asm.push_str(&format!("    {} {}\n", jump, target));
+                Ok(asm)
+            }
+        }
+    }
+
+    /// Generates the short-circuiting jump of an `AND` or `OR` condition (see
+    /// [`Self::generate_condition_jump`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `left` - The left-hand side condition, always evaluated.
+    /// * `operator` - Either [`BinaryOperator::And`] or [`BinaryOperator::Or`].
+    /// * `right` - The right-hand side condition, evaluated only if `left` does not decide.
+    /// * `target` - The label to jump to.
+    /// * `jump_if` - The value of the condition for which the jump is taken.
+    fn generate_logical_jump(
+        common: &mut CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+        target: &str,
+        jump_if: bool,
+    ) -> Result<String, CompileError> {
+        // `OR` is decided by a true left operand, `AND` by a false one.
+        let decided_by = matches!(operator, BinaryOperator::Or);
+        if decided_by == jump_if {
+            // The deciding value takes the jump right away.
+            let mut asm = Self::generate_condition_jump(common, left, target, jump_if)?;
+            asm.push_str(&Self::generate_condition_jump(
+                common, right, target, jump_if,
+            )?);
+            Ok(asm)
+        } else {
+            // The deciding value falls through without testing the right operand.
+            let skip_label = common.next_label();
+            let mut asm = Self::generate_condition_jump(common, left, &skip_label, decided_by)?;
+            asm.push_str(&Self::generate_condition_jump(
+                common, right, target, jump_if,
+            )?);
+            asm.push_str(&format!("{}:\n", skip_label));
+            Ok(asm)
+        }
+    }
+
+    /// Returns the jumps taken when a comparison of integers (or booleans) is true and when
+    /// it is false.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The code generation context.
+    /// * `left` - The left-hand side expression.
+    /// * `operator` - The binary operator.
+    /// * `right` - The right-hand side expression.
+    ///
+    /// # Returns
+    ///
+    /// The two jump instructions, or `None` if the operation is not such a comparison.
+    fn integer_comparison_jumps(
+        common: &CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+    ) -> Option<(&'static str, &'static str)> {
+        let jumps = match operator {
+            BinaryOperator::Equal => ("je", "jne"),
+            BinaryOperator::NotEqual => ("jne", "je"),
+            BinaryOperator::Less => ("jl", "jge"),
+            BinaryOperator::Greater => ("jg", "jle"),
+            BinaryOperator::LessEqual => ("jle", "jg"),
+            BinaryOperator::GreaterEqual => ("jge", "jl"),
+            _ => return None,
+        };
+        // Floats and strings are compared by their own routines.
+        let scalar = |operand| {
+            !matches!(
+                Self::static_type(common, operand),
+                Type::Float | Type::String
+            )
+        };
+        (scalar(left) && scalar(right)).then_some(jumps)
+    }
+
+    /// Generates assembly for an integer division or remainder of RAX by RBX and pushes the result.
+    ///
+    /// A zero divisor stops the program with a runtime error. Dividing the smallest integer
+    /// by -1 overflows like a negation; its remainder is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `operator` - Either [`BinaryOperator::Divide`] or [`BinaryOperator::Modulo`].
+    /// * `right` - The divisor expression.
+    /// * `span` - The span of the division, for runtime error messages.
+    fn generate_division(
+        common: &mut CodeGenCommon,
+        operator: &BinaryOperator,
+        right: &Expression,
+        span: Span,
+    ) -> String {
+        let mut asm = String::new();
+        let quotient = matches!(operator, BinaryOperator::Divide);
+        let result = if quotient {
+            "rax" // Quotient
+        } else {
+            "rdx" // Remainder
+        };
+
+        // A literal divisor other than 0 and -1 needs no checks.
+        if !matches!(right.kind, ExpressionKind::Integer(n) if n != 0 && n != -1) {
+            let nonzero_label = common.next_label();
+            let divide_label = common.next_label();
+            let done_label = common.next_label();
+            asm.push_str("    test rbx, rbx\n");
+            asm.push_str(&format!("    jnz {}\n", nonzero_label));
+            asm.push_str(&common.generate_arithmetic_error("division by zero", span));
+            asm.push_str(&format!("{}:\n", nonzero_label));
+            asm.push_str("    cmp rbx, -1\n");
+            asm.push_str(&format!("    jne {}\n", divide_label));
+            if quotient {
+                asm.push_str("    neg rax\n");
+                asm.push_str(&Self::generate_overflow_check(
+                    common,
+                    span,
+                    "    not rax\n",
+                ));
+            } else {
+                asm.push_str("    xor rdx, rdx\n");
+            }
+            asm.push_str(&format!("    jmp {}\n", done_label));
+            asm.push_str(&format!("{}:\n", divide_label));
+            asm.push_str("    cqo\n"); // Sign-extend RAX into RDX:RAX
+            asm.push_str("    idiv rbx\n"); // Signed division: quotient in RAX, remainder in RDX
+            asm.push_str(&format!("{}:\n", done_label));
+        } else {
+            asm.push_str("    cqo\n");
+            asm.push_str("    idiv rbx\n");
+        }
+        asm.push_str(&format!("    push {}\n", result));
+        asm
+    }
+
+    /// Generates the handling of an overflow flagged by the preceding integer instruction,
+    /// according to the overflow mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `span` - The span of the operation, for runtime error messages.
+    /// * `saturate` - Assembly that replaces the wrapped result in RAX with the clamped one.
+    ///
+    /// # Returns
+    ///
+    /// Nothing when overflow wraps; otherwise a `jno` around the trap or the clamping code.
+    fn generate_overflow_check(common: &mut CodeGenCommon, span: Span, saturate: &str) -> String {
+        let handler = match common.overflow {
+            OverflowMode::Wrap => return String::new(),
+            OverflowMode::Trap => common.generate_arithmetic_error("integer overflow", span),
+            OverflowMode::Saturate => saturate.to_string(),
+        };
+        let ok_label = common.next_label();
+        format!("    jno {}\n{}{}:\n", ok_label, handler, ok_label)
+    }
+
+    /// Generates assembly for a unary operation and pushes its result.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `operator` - The unary operator.
+    /// * `operand` - The operand expression.
+    /// * `span` - The span of the operation, for runtime error messages.
+    fn generate_unary_expression(
+        common: &mut CodeGenCommon,
+        operator: &UnaryOperator,
+        operand: &Expression,
+        span: Span,
+    ) -> Result<String, CompileError> {
+        let mut asm = Self::generate_expression(common, operand)?;
+        asm.push_str("    pop rax\n");
+        match operator {
+            // A float is negated by flipping its sign bit.
+            UnaryOperator::Negate if Self::static_type(common, operand) == Type::Float => {
+                asm.push_str("    btc rax, 63\n");
+            }
+            // Only the smallest integer overflows; its saturated negation is the largest.
+            UnaryOperator::Negate => {
+                asm.push_str("    neg rax\n");
+                asm.push_str(&Self::generate_overflow_check(
+                    common,
+                    span,
+                    "    not rax\n",
+                ));
+            }
+            // Booleans are 0 or 1, so flipping the lowest bit negates them.
+            UnaryOperator::Not => asm.push_str("    xor rax, 1\n"),
+            UnaryOperator::BitNot => asm.push_str("    not rax\n"),
+        }
+        asm.push_str("    push rax\n");
+        Ok(asm)
+    }
+
+    /// Generates assembly for a concatenation (`+`) or comparison of two strings.
+    ///
+    /// Comparisons are lexicographic, byte by byte (`"apple" < "banana"`, `"ab" < "abc"`).
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `left` - The left-hand side string.
+    /// * `operator` - The binary operator.
+    /// * `right` - The right-hand side string.
+    fn generate_string_binary_expression(
+        common: &mut CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+    ) -> Result<String, CompileError> {
+        let mut asm = Self::generate_expression(common, right)?;
+        asm.push_str(&Self::generate_expression(common, left)?);
+        asm.push_str("    pop rsi\n"); // RSI = Left string
+        asm.push_str("    pop rdi\n"); // RDI = Right string
+
+        let condition = match operator {
+            BinaryOperator::Add => {
+                asm.push_str("    call _nebula_str_concat\n");
+                asm.push_str("    push rax\n");
+                return Ok(asm);
+            }
+            BinaryOperator::Equal => "e",
+            BinaryOperator::NotEqual => "ne",
+            BinaryOperator::Less => "l",
+            BinaryOperator::Greater => "g",
+            BinaryOperator::LessEqual => "le",
+            BinaryOperator::GreaterEqual => "ge",
+            other => {
+                return Err(CompileError::type_mismatch(format!(
+                    "Operator {:?} is not defined for strings",
+                    other
+                )));
+            }
+        };
+
+        // The comparison routine returns -1, 0 or 1, which is compared against zero.
+        asm.push_str("    call _nebula_str_compare\n");
+        asm.push_str("    cmp rax, 0\n");
+        asm.push_str(&format!("    set{} al\n", condition));
+        asm.push_str("    movzx rax, al\n");
+        asm.push_str("    push rax\n");
+        Ok(asm)
+    }
+
+    /// Checks whether a binary operation must be computed in floating point, i.e. whether
+    /// it is arithmetic or a comparison with at least one Float operand.
+    fn is_float_operation(
+        common: &CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+    ) -> bool {
+        !matches!(operator, BinaryOperator::And | BinaryOperator::Or)
+            && (Self::static_type(common, left) == Type::Float
+                || Self::static_type(common, right) == Type::Float)
+    }
+
+    /// Generates assembly for an arithmetic operation or comparison on floats (SSE2).
+    ///
+    /// Both operands are converted to floats if needed and loaded into XMM0 (left) and
+    /// XMM1 (right). Arithmetic pushes the resulting float; comparisons push 0 or 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `left` - The left-hand side expression.
+    /// * `operator` - The binary operator.
+    /// * `right` - The right-hand side expression.
+    fn generate_float_binary_expression(
+        common: &mut CodeGenCommon,
+        left: &Expression,
+        operator: &BinaryOperator,
+        right: &Expression,
+    ) -> Result<String, CompileError> {
+        if matches!(operator, BinaryOperator::Power) {
+            return Self::generate_float_power(common, left, right);
+        }
+
+        let mut asm = String::new();
+        asm.push_str(&Self::generate_float_operand(common, right)?);
+        asm.push_str(&Self::generate_float_operand(common, left)?);
+        asm.push_str("    pop rax\n");
+        asm.push_str("    movq xmm0, rax\n"); // XMM0 = Left Operand
+        asm.push_str("    pop rbx\n");
+        asm.push_str("    movq xmm1, rbx\n"); // XMM1 = Right Operand
+
+        let arithmetic = match operator {
+            BinaryOperator::Add => Some("addsd"),
+            BinaryOperator::Subtract => Some("subsd"),
+            BinaryOperator::Multiply => Some("mulsd"),
+            BinaryOperator::Divide => Some("divsd"),
+            _ => None,
+        };
+        if let Some(instruction) = arithmetic {
+            asm.push_str(&format!("    {} xmm0, xmm1\n", instruction));
+            asm.push_str("    movq rax, xmm0\n");
+            asm.push_str("    push rax\n");
+            return Ok(asm);
+        }
+
+        // `ucomisd` sets the flags like an unsigned comparison; an unordered result (NaN)
+        // sets ZF, PF and CF. Less-than tests swap the operands so that NaN compares false.
+        match operator {
+            BinaryOperator::Equal => {
+                asm.push_str("    ucomisd xmm0, xmm1\n");
+                asm.push_str("    sete al\n");
+                asm.push_str("    setnp cl\n"); // Not unordered
+                asm.push_str("    and al, cl\n");
+            }
+            BinaryOperator::NotEqual => {
+                asm.push_str("    ucomisd xmm0, xmm1\n");
+                asm.push_str("    setne al\n");
+                asm.push_str("    setp cl\n"); // Unordered
+                asm.push_str("    or al, cl\n");
+            }
+            BinaryOperator::Greater => {
+                asm.push_str("    ucomisd xmm0, xmm1\n");
+                asm.push_str("    seta al\n");
+            }
+            BinaryOperator::GreaterEqual => {
+                asm.push_str("    ucomisd xmm0, xmm1\n");
+                asm.push_str("    setae al\n");
+            }
+            BinaryOperator::Less => {
+                asm.push_str("    ucomisd xmm1, xmm0\n");
+                asm.push_str("    seta al\n");
+            }
+            BinaryOperator::LessEqual => {
+                asm.push_str("    ucomisd xmm1, xmm0\n");
+                asm.push_str("    setae al\n");
+            }
+            _ => {
+                return Err(CompileError::type_mismatch(format!(
+                    "Operator {:?} is not defined for floats",
+                    operator
+                )));
+            }
         }
+        asm.push_str("    movzx rax, al\n");
+        asm.push_str("    push rax\n");
+        Ok(asm)
+    }
 
+    /// Generates assembly for a float raised to an integer power (`base ^ exponent`).
+    ///
+    /// The power is computed by `_nebula_float_power`, or at compile time for literal operands;
+    /// a negative exponent takes the reciprocal.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `base` - The float base.
+    /// * `exponent` - The integer exponent.
+    fn generate_float_power(
+        common: &mut CodeGenCommon,
+        base: &Expression,
+        exponent: &Expression,
+    ) -> Result<String, CompileError> {
+        if let (Some(base), Some(exponent)) = (base.float_literal(), exponent.integer_literal()) {
+            return Self::generate_float_expression(Self::fold_float_power(base, exponent));
+        }
+
+        let mut asm = String::new();
+        asm.push_str(&Self::generate_expression(common, exponent)?);
+        asm.push_str(&Self::generate_float_operand(common, base)?);
+        asm.push_str("    pop rax\n");
+        asm.push_str("    movq xmm0, rax\n"); // XMM0 = base
+        asm.push_str("    pop rdi\n"); // RDI = exponent
+        asm.push_str("    call _nebula_float_power\n");
+        asm.push_str("    movq rax, xmm0\n");
+        asm.push_str("    push rax\n");
         Ok(asm)
     }
 
+    /// Computes a float power the way `_nebula_float_power` does, for constant folding.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The base.
+    /// * `exponent` - The exponent; a negative one gives the reciprocal.
+    fn fold_float_power(base: f64, exponent: i64) -> f64 {
+        let mut result = 1.0;
+        let mut square = base;
+        let mut bits = exponent.unsigned_abs();
+        loop {
+            if bits & 1 == 1 {
+                result *= square;
+            }
+            bits >>= 1;
+            if bits == 0 {
+                break;
+            }
+            square *= square;
+        }
+        if exponent < 0 { 1.0 / result } else { result }
+    }
+
     /// Generates assembly code specifically for printing an expression's value.
     ///
-    /// This function handles the printing logic based on the expression's type (number vs. string/boolean),
-    /// invoking the appropriate runtime print helper (`_nebula_print_number` or `_nebula_print`).
+    /// This function dispatches on the expression's static type, invoking the matching runtime
+    /// print helper (`_nebula_print_number`, `_nebula_print_string`, `_nebula_print_bool`, ...).
     ///
     /// # Arguments
     ///
@@ -267,9 +1211,50 @@ impl ExpressionGenerator {
     ) -> Result<String, CompileError> {
         let mut asm = String::new();
 
-        match expr {
+        match Self::static_type(common, expr) {
+            // Whole arrays print as a comma-separated list.
+            Type::Array(element_type) => {
+                let base = Self::array_base(common, expr)?;
+                let kind = match *element_type {
+                    Type::String => 1,
+                    Type::Float => 2,
+                    Type::Boolean => 3,
+                    _ => 0,
+                };
+                asm.push_str(&format!("    lea rsi, [{}]\n", base));
+                asm.push_str(&format!("    mov rdi, {}\n", kind));
+                asm.push_str("    call _nebula_print_array\n");
+                return Ok(asm);
+            }
+            // String values (e.g., `c:col:0`) are pointers to length-prefixed text.
+            Type::String if !matches!(expr.kind, ExpressionKind::String(_)) => {
+                asm.push_str(&Self::generate_expression(common, expr)?);
+                asm.push_str("    pop rsi\n");
+                asm.push_str("    call _nebula_print_string\n");
+                return Ok(asm);
+            }
+            // Boolean values (variables, comparisons, ...) print as `TRUE` or `FALSE`.
+            Type::Boolean if !matches!(expr.kind, ExpressionKind::Boolean(_)) => {
+                asm.push_str(&Self::generate_expression(common, expr)?);
+                asm.push_str("    pop rax\n");
+                asm.push_str("    call _nebula_print_bool\n");
+                return Ok(asm);
+            }
+            // Floats print with up to six decimal places, without trailing zeros.
+            Type::Float => {
+                asm.push_str(&Self::generate_expression(common, expr)?);
+                asm.push_str("    pop rax\n");
+                asm.push_str("    movq xmm0, rax\n");
+                asm.push_str("    mov rdi, -1\n");
+                asm.push_str("    call _nebula_print_float\n");
+                return Ok(asm);
+            }
+            _ => {}
+        }
+
+        match &expr.kind {
             // Direct handling for simple types to avoid stack push/pop overhead.
-            Expression::Variable(name) => {
+            ExpressionKind::Variable(name) => {
                 let address = common
                     .get_variable_address(name)
                     .ok_or_else(|| CompileError::undefined_variable(name))?
@@ -278,19 +1263,19 @@ impl ExpressionGenerator {
                 asm.push_str(&format!("    mov rax, [{}]\n", address));
                 asm.push_str("    call _nebula_print_number\n");
             }
-            Expression::Integer(n) => {
+            ExpressionKind::Integer(n) => {
                 // Load value into RAX directly
                 asm.push_str(&format!("    mov rax, {}\n", n));
                 asm.push_str("    call _nebula_print_number\n");
             }
-            Expression::String(s) => {
+            ExpressionKind::String(s) => {
                 let label = common.add_string_to_pool(s);
                 // Load address (RSI) and length (RDX) for string printing function
                 asm.push_str(&format!("    mov rsi, {}\n", label));
                 asm.push_str(&format!("    mov rdx, {}\n", s.len()));
                 asm.push_str("    call _nebula_print\n");
             }
-            Expression::Boolean(b) => {
+            ExpressionKind::Boolean(b) => {
                 let s_val = if *b { "TRUE" } else { "FALSE" };
                 let len = s_val.len();
                 let label = common.add_string_to_pool(s_val);
@@ -313,4 +1298,25 @@ impl ExpressionGenerator {
 
         Ok(asm)
     }
+
+    /// Generates assembly for printing a number with a fixed number of decimal places
+    /// (e.g., `{rate:.2}`). Integers are converted to floats first.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `expr` - The numeric expression.
+    /// * `precision` - The number of digits after the decimal point.
+    pub fn generate_formatted_print(
+        common: &mut CodeGenCommon,
+        expr: &Expression,
+        precision: usize,
+    ) -> Result<String, CompileError> {
+        let mut asm = Self::generate_float_operand(common, expr)?;
+        asm.push_str("    pop rax\n");
+        asm.push_str("    movq xmm0, rax\n");
+        asm.push_str(&format!("    mov rdi, {}\n", precision));
+        asm.push_str("    call _nebula_print_float\n");
+        Ok(asm)
+    }
 }
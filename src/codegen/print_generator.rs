@@ -10,7 +10,7 @@
 
 use super::common::CodeGenCommon;
 use super::expression_generator::ExpressionGenerator;
-use crate::ast::nodes::{Expression, PrintPart};
+use crate::ast::nodes::{ExpressionKind, PrintPart};
 use crate::compiler::error::CompileError;
 
 /// A static utility struct dedicated to generating assembly code for `Print` statements.
@@ -62,9 +62,9 @@ impl PrintGenerator {
                 }
                 PrintPart::Expression(expr) => {
                     // Handle Expression parts, which require evaluation before printing.
-                    match expr.as_ref() {
+                    match &expr.kind {
                         // Special handling for boolean literals to print "TRUE" or "FALSE" strings.
-                        Expression::Boolean(b) => {
+                        ExpressionKind::Boolean(b) => {
                             let s_val = if *b { "TRUE" } else { "FALSE" };
                             let len = s_val.len();
                             let label = common.add_string_to_pool(s_val);
@@ -82,6 +82,14 @@ impl PrintGenerator {
                         }
                     }
                 }
+                PrintPart::Formatted {
+                    expression,
+                    precision,
+                } => {
+                    asm.push_str(&ExpressionGenerator::generate_formatted_print(
+                        common, expression, *precision,
+                    )?);
+                }
             }
         }
 
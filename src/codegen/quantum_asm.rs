@@ -7,10 +7,12 @@
 //!
 //! The generated assembly targets standard Linux environments (using syscalls like `exit`=60).
 
-use super::common::CodeGenCommon;
+use super::common::{CodeGenCommon, OverflowMode};
 use super::statement_generator::StatementGenerator;
-use crate::ast::nodes::Program;
+use crate::ast::nodes::{Program, StatementKind};
+use crate::compiler::analyzer::ProgramTypes;
 use crate::compiler::error::CompileError;
+use crate::compiler::span::SourceMap;
 
 /// The main entry point for generating the final executable assembly code.
 pub struct QuantumAssemblyGenerator {
@@ -26,10 +28,53 @@ impl QuantumAssemblyGenerator {
         }
     }
 
+    /// Enables or disables runtime bounds checks on array accesses (enabled by default).
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether out-of-bounds accesses should raise a runtime error.
+    pub fn set_bounds_checks(&mut self, enabled: bool) {
+        self.common.bounds_checks = enabled;
+    }
+
+    /// Selects what integer arithmetic does when it overflows (trapping by default).
+    ///
+    /// Division by zero is always a runtime error, whatever the mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Whether overflowing results wrap, trap or saturate.
+    pub fn set_overflow(&mut self, mode: OverflowMode) {
+        self.common.overflow = mode;
+    }
+
+    /// Supplies the source file, so that runtime errors can name the line that failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name shown in runtime error messages.
+    /// * `source_map` - The line index of the source file.
+    pub fn set_source(&mut self, name: &str, source_map: SourceMap) {
+        self.common.source_name = name.to_string();
+        self.common.source_map = Some(source_map);
+    }
+
+    /// Supplies the types inferred by the analyzer.
+    ///
+    /// Without them, function parameters and results are treated as integers.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - The variable and function types of the program.
+    pub fn set_types(&mut self, types: ProgramTypes) {
+        self.common.variable_types = types.variables;
+        self.common.function_types = types.functions;
+    }
+
     /// Generates the complete x86-64 assembly code for the given Nebulang program AST.
     ///
     /// The process involves:
-    /// 1. Generating code for all program statements.
+    /// 1. Generating code for all program statements and user-defined functions.
     /// 2. Constructing the static data (`.data`) and uninitialized data (`.bss`) sections.
     /// 3. Assembling the `.text` section, including the program entry point (`_start`)
     ///    and necessary runtime functions.
@@ -49,10 +94,16 @@ impl QuantumAssemblyGenerator {
         asm.push_str("; Generated by NEBC Quantum Compiler\n\n");
 
         // --- 1. Program Statement Generation (Intermediate Code) ---
+        // Function definitions are emitted separately, after the program exit.
         let mut temp_asm = String::new();
+        let mut functions_asm = String::new();
         for statement in &program.statements {
             let stmt_asm = StatementGenerator::generate_statement(&mut self.common, statement)?;
-            temp_asm.push_str(&stmt_asm);
+            if matches!(statement.kind, StatementKind::Function { .. }) {
+                functions_asm.push_str(&stmt_asm);
+            } else {
+                temp_asm.push_str(&stmt_asm);
+            }
         }
 
         // --- 2. Data and BSS Sections ---
@@ -67,6 +118,14 @@ impl QuantumAssemblyGenerator {
 
         // Standard function prologue: stack alignment and setup.
         asm.push_str("    mov rbp, rsp\n");
+        // Save argc, argv and envp from the initial stack: `[rsp]` holds argc, followed by the
+        // argv pointers, a null pointer, then the envp pointers.
+        asm.push_str("    mov rax, [rbp]\n");
+        asm.push_str("    mov [arg_count], rax\n");
+        asm.push_str("    lea rcx, [rbp + 8]\n");
+        asm.push_str("    mov [arg_values], rcx\n");
+        asm.push_str("    lea rcx, [rcx + rax * 8 + 8]\n");
+        asm.push_str("    mov [env_values], rcx\n");
         asm.push_str("    and rsp, 0xFFFFFFFFFFFFFFF0 ; Align stack to 16 bytes\n");
 
         // Runtime/Protection Initialization
@@ -80,10 +139,560 @@ impl QuantumAssemblyGenerator {
         asm.push_str("    xor rdi, rdi\n"); // Exit code 0 (success)
         asm.push_str("    syscall\n");
 
+        // --- User-Defined Functions ---
+        asm.push_str(&functions_asm);
+
         // --- 4. Runtime Helpers and Security Modules ---
         asm.push_str(&self.common.generate_print_functions());
+        asm.push_str(&self.common.generate_array_print_function());
+        asm.push_str(&self.common.generate_string_functions());
+        asm.push_str(&self.common.generate_power_functions());
+        asm.push_str(&self.common.generate_input_functions());
+        asm.push_str(&self.common.generate_process_functions());
+        asm.push_str(&self.common.generate_runtime_error_functions());
         asm.push_str(&self.common.generate_quantum_protection());
 
         Ok(asm)
     }
 }
+
+impl Default for QuantumAssemblyGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::common::EXIT_ARITHMETIC_ERROR;
+    use crate::compiler::analyzer::analyze_with_overflow;
+    use crate::compiler::lexer::tokenize;
+    use crate::compiler::parser::parse;
+
+    /// Generates the assembly for `source` (as `nebc build --show-asm` prints it), with
+    /// integer overflow handled as `overflow` says and optional bounds checks.
+    fn generate(source: &str, overflow: OverflowMode, bounds_checks: bool) -> String {
+        let program =
+            parse(tokenize(source).expect("source should tokenize")).expect("source should parse");
+        let types = analyze_with_overflow(&program, overflow).expect("source should analyze");
+        let mut generator = QuantumAssemblyGenerator::new();
+        generator.set_bounds_checks(bounds_checks);
+        generator.set_overflow(overflow);
+        generator.set_source("test.neb", SourceMap::new(source));
+        generator.set_types(types);
+        generator
+            .generate(&program)
+            .expect("assembly should generate")
+    }
+
+    /// Generates the assembly for `source` with bounds checks, and integer overflow handled
+    /// as `overflow` says.
+    fn assembly_with(source: &str, overflow: OverflowMode) -> String {
+        generate(source, overflow, true)
+    }
+
+    /// Generates the assembly for `source` with the default (trapping) overflow mode.
+    fn assembly(source: &str) -> String {
+        assembly_with(source, OverflowMode::Trap)
+    }
+
+    /// Asserts that `expected` appear in `asm` as lines in this order, possibly with other
+    /// lines in between. Trailing `;` comments are ignored.
+    fn assert_lines_in_order(asm: &str, expected: &[&str]) {
+        let mut lines = asm
+            .lines()
+            .map(|line| line.split(';').next().unwrap_or_default().trim());
+        for line in expected {
+            assert!(
+                lines.any(|candidate| candidate == *line),
+                "missing `{}` (in order) in:\n{}",
+                line,
+                asm
+            );
+        }
+    }
+
+    #[test]
+    fn function_call_and_definition() {
+        let asm = assembly("# add(a, b)\n    => a + b\n\nx add(1, 2)\n");
+        assert_lines_in_order(
+            &asm,
+            &["pop rdi", "pop rsi", "call fn_add", "push rax", "fn_add:"],
+        );
+        assert_lines_in_order(&asm, &["fn_add:", "push rbp", "mov rbp, rsp"]);
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_the_value_first() {
+        let asm = assembly("# one()\n    => 1\n\nx 1\nx += one()\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "call fn_one",
+                "mov rax, [var_x]",
+                "pop rax",
+                "pop rbx",
+                "add rax, rbx",
+            ],
+        );
+    }
+
+    #[test]
+    fn array_accesses_are_bounds_checked_unless_disabled() {
+        let source = "scores [72, 95, 58]\ni 1\nx scores{i}\n";
+        assert_lines_in_order(
+            &assembly(source),
+            &["cmp rbx, [var_scores]", "jae _nebula_bounds_error"],
+        );
+        let unchecked = generate(source, OverflowMode::Trap, false);
+        assert!(!unchecked.contains("jae _nebula_bounds_error"));
+    }
+
+    #[test]
+    fn collection_copied_into_a_variable() {
+        let asm = assembly("[] cars as c\n    [] brands as b\n        1, 2\nall c:b\nx all{1}\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "lea rsi, [var_cars.brands]",
+                "lea rdi, [var_all]",
+                "rep movsq",
+                "cmp rbx, [var_all]",
+                "mov rax, [var_all + 8 + rbx * 8]",
+            ],
+        );
+    }
+
+    #[test]
+    fn integers_are_converted_before_float_arithmetic() {
+        let asm = assembly("x 2\nf x + 0.5\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov rax, 0x3fe0000000000000",
+                "mov rax, [var_x]",
+                "cvtsi2sd xmm0, rax",
+                "addsd xmm0, xmm1",
+                "mov [var_f], rax",
+            ],
+        );
+    }
+
+    #[test]
+    fn floats_pass_to_and_from_functions_as_raw_bits() {
+        let asm = assembly("# half(x)\n    => x / 2\n\nh half(3.0)\n! \"{h:.2}\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov rax, 0x4008000000000000",
+                "pop rdi",
+                "call fn_half",
+                "mov [var_h], rax",
+                "mov rdi, 2",
+                "call _nebula_print_float",
+            ],
+        );
+        // The integer divisor is converted, and the quotient is returned in rax.
+        assert_lines_in_order(
+            &asm,
+            &[
+                "fn_half:",
+                "cvtsi2sd xmm0, rax",
+                "divsd xmm0, xmm1",
+                "jmp fn_half_return",
+            ],
+        );
+    }
+
+    #[test]
+    fn large_floats_print_with_an_exponent() {
+        let asm = assembly("! \"{1.5}\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "_nebula_print_float:",
+                "mov rax, 0x43E0000000000000",
+                "mulsd xmm0, xmm3",
+                "mov rsi, float_exponent",
+            ],
+        );
+    }
+
+    #[test]
+    fn values_print_by_their_static_type() {
+        let asm = assembly("s \"hi\"\nb 3 > 2\nn 7\n! \"{s}{b}{n}\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov rax, [var_s]",
+                "pop rsi",
+                "call _nebula_print_string",
+                "mov rax, [var_b]",
+                "call _nebula_print_bool",
+                "mov rax, [var_n]",
+                "call _nebula_print_number",
+            ],
+        );
+    }
+
+    #[test]
+    fn string_literals_are_length_prefixed() {
+        let asm = assembly("s \"héllo\"\n! \"{s}\"\n");
+        // The prefix counts bytes, not characters.
+        assert_lines_in_order(&asm, &["section .data", "dq 6", "str_0: db \"héllo\", 0"]);
+    }
+
+    #[test]
+    fn string_bytes_match_their_length_prefix() {
+        let asm = assembly("s \"p\tq\\r\"\n! \"{s}\"\n");
+        // Backslashes are copied as they are; the tab is written as a byte value.
+        assert_lines_in_order(&asm, &["dq 5", "str_0: db \"p\", 9, \"q\\r\", 0"]);
+    }
+
+    #[test]
+    fn string_operations_call_the_runtime() {
+        let asm = assembly(
+            "a \"ab\"\nb a + \"c\"\nl a < b\nn #b\nu b{0..1}\nv str(42)\n! \"{l}{n}{u}{v}\"\n",
+        );
+        assert_lines_in_order(
+            &asm,
+            &[
+                "call _nebula_str_concat",
+                "mov [var_b], rax",
+                "call _nebula_str_compare",
+                "setl al",
+                "mov [var_l], rax",
+                // The length is stored just before the characters.
+                "mov rax, [rax - 8]",
+                "mov [var_n], rax",
+                "cmp rcx, [rsi - 8]",
+                "ja _nebula_string_bounds_error",
+                "call _nebula_str_slice",
+                "mov [var_u], rax",
+                "call _nebula_int_to_str",
+                "mov [var_v], rax",
+            ],
+        );
+    }
+
+    #[test]
+    fn compound_division_modulo_and_power() {
+        let asm = assembly_with("x 17\ny 3\nx /= y\nx %= y\nx ^= y\n", OverflowMode::Wrap);
+        assert_lines_in_order(
+            &asm,
+            &[
+                // x /= y: signed division, quotient in rax.
+                "cqo",
+                "idiv rbx",
+                "push rax",
+                "mov [var_x], rax",
+                // x %= y: signed division, remainder in rdx.
+                "cqo",
+                "idiv rbx",
+                "push rdx",
+                "mov [var_x], rax",
+                // x ^= y: integer power.
+                "call _nebula_int_power",
+                "mov [var_x], rax",
+            ],
+        );
+    }
+
+    #[test]
+    fn else_if_chains_share_one_end_label() {
+        let asm =
+            assembly("x 5\n? (x > 3)\n    ! \"a\"\n!? (x > 1)\n    ! \"b\"\n!?\n    ! \"c\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "jle L_1", "jmp L_0", "L_1:", "jle L_2", "jmp L_0", "L_2:", "L_0:",
+            ],
+        );
+    }
+
+    #[test]
+    fn break_and_continue_target_the_innermost_loop() {
+        let asm = assembly_with(
+            "i 0\n@ (i < 10)\n    i += 1\n    @ j, 1..3\n        ? (j == 2)\n            break\n        continue\n    ? (i == 5)\n        break\n",
+            OverflowMode::Wrap,
+        );
+        assert_lines_in_order(
+            &asm,
+            &[
+                // Outer loop: L_0 tests the condition, L_1 ends it.
+                "L_0:", "jge L_1", // Inner loop: L_2 tests, L_3 steps, L_4 ends.
+                "L_2:", "jg L_4", "jmp L_4", "jmp L_3", "L_3:", "jmp L_2", "L_4:", "jmp L_1",
+                "jmp L_0", "L_1:",
+            ],
+        );
+    }
+
+    #[test]
+    fn literal_steps_pick_the_exit_comparison() {
+        // Counting down to an exclusive end leaves once the variable reaches it.
+        let asm = assembly("@ i, 10..<0 step -2\n    ! \"{i}\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov qword [var_i], 10",
+                "L_0:",
+                "cmp rax, 0",
+                "jle L_2",
+                "L_1:",
+                "mov rax, -2",
+                "add [var_i], rax",
+                "jmp L_0",
+                "L_2:",
+            ],
+        );
+    }
+
+    #[test]
+    fn computed_ends_and_steps_are_evaluated_once() {
+        let asm = assembly("n 3\n@ k, 0..n step n - 3\n    ! \"{k}\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov [var_k.end], rax",
+                "mov [var_k.step], rax",
+                // A computed step of zero is a runtime error rather than an endless loop.
+                "test rax, rax",
+                "jnz L_4",
+                "mov rsi, str_1",
+                "jmp _nebula_arithmetic_error",
+                "L_4:",
+                "L_0:",
+                "cmp qword [var_k.step], 0",
+                "cmp rax, qword [var_k.end]",
+                "mov rax, [var_k.step]",
+                "add [var_k], rax",
+                "jmp L_0",
+            ],
+        );
+        assert!(asm.contains("str_1: db \"loop step is zero at test.neb:2\", 0"));
+    }
+
+    #[test]
+    fn unary_and_bitwise_operators_use_single_instructions() {
+        let asm = assembly("x 5\nb x > 1\na -x\nn NOT b\nc ~x\nd x ~ 3\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "neg rax",
+                "jno L_0",
+                "mov [var_a], rax",
+                "xor rax, 1",
+                "mov [var_n], rax",
+                "not rax",
+                "mov [var_c], rax",
+                "xor rax, rbx",
+                "mov [var_d], rax",
+            ],
+        );
+    }
+
+    #[test]
+    fn trap_mode_checks_overflow_and_division() {
+        let asm = assembly("x 5\ny x * 3\nz x / y\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "imul rax, rbx",
+                "jno L_0",
+                "mov rsi, str_0",
+                "jmp _nebula_arithmetic_error",
+                "L_0:",
+                "test rbx, rbx",
+                "jnz L_1",
+                "mov rsi, str_1",
+                "jmp _nebula_arithmetic_error",
+                "L_1:",
+                // i64::MIN / -1 overflows, so -1 is handled by negation.
+                "cmp rbx, -1",
+                "neg rax",
+                "jno L_4",
+                "cqo",
+                "idiv rbx",
+            ],
+        );
+        assert!(asm.contains("str_0: db \"integer overflow at test.neb:2\", 0"));
+        assert!(asm.contains("str_1: db \"division by zero at test.neb:3\", 0"));
+        assert_lines_in_order(
+            &asm,
+            &[
+                "_nebula_arithmetic_error:",
+                &format!("mov rdi, {}", EXIT_ARITHMETIC_ERROR),
+                "jmp _nebula_panic",
+            ],
+        );
+    }
+
+    #[test]
+    fn wrap_mode_only_checks_division() {
+        let asm = assembly_with("x 5\ny x * 3\nz x / y\n", OverflowMode::Wrap);
+        assert!(!asm.contains("jno"));
+        assert_lines_in_order(
+            &asm,
+            &[
+                "imul rax, rbx",
+                "mov [var_y], rax",
+                "test rbx, rbx",
+                "jnz L_0",
+                "jmp _nebula_arithmetic_error",
+                "cqo",
+                "idiv rbx",
+            ],
+        );
+    }
+
+    #[test]
+    fn saturate_mode_clamps_to_the_sign_of_the_exact_result() {
+        let asm = assembly_with("x 5\ny x * 3\n", OverflowMode::Saturate);
+        assert_lines_in_order(
+            &asm,
+            &[
+                "mov rdx, rax",
+                "xor rdx, rbx",
+                "imul rax, rbx",
+                "jno L_0",
+                "mov rax, rdx",
+                "sar rax, 63",
+                "mov rcx, 0x7FFFFFFFFFFFFFFF",
+                "xor rax, rcx",
+                "L_0:",
+            ],
+        );
+        assert!(!asm.contains("integer overflow at"));
+    }
+
+    #[test]
+    fn literal_powers_fold_and_others_call_the_runtime() {
+        let source = "x 2 ^ 10\ny 2 ^ 64\nw x ^ 2\nv w ^ x\n";
+        let asm = assembly_with(source, OverflowMode::Wrap);
+        assert_lines_in_order(
+            &asm,
+            &[
+                "push 1024",
+                "mov [var_x], rax",
+                "push 0",
+                "mov [var_y], rax",
+                "jns L_0",
+                "call _nebula_int_power",
+                "mov [var_w], rax",
+                "jns L_1",
+                "call _nebula_int_power",
+                "mov [var_v], rax",
+            ],
+        );
+        // Every power shares one runtime routine, so labels are never duplicated.
+        assert_eq!(asm.matches("\n_nebula_int_power:").count(), 1);
+
+        let saturated = assembly_with(source, OverflowMode::Saturate);
+        assert_lines_in_order(
+            &saturated,
+            &["mov rax, 9223372036854775807", "mov [var_y], rax"],
+        );
+    }
+
+    #[test]
+    fn and_or_values_short_circuit() {
+        let asm = assembly("x 5\na x > 1 AND x < 9\nb x > 1 OR x < 9\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                // AND: a false left operand skips the right one.
+                "jle L_0",
+                "jge L_0",
+                "push 1",
+                "jmp L_1",
+                "L_0:",
+                "push 0",
+                "L_1:",
+                "mov [var_a], rax",
+                // OR: a true left operand skips the right one.
+                "jg L_4",
+                "jge L_2",
+                "L_4:",
+                "push 1",
+                "jmp L_3",
+                "L_2:",
+                "push 0",
+                "L_3:",
+                "mov [var_b], rax",
+            ],
+        );
+    }
+
+    #[test]
+    fn conditions_branch_directly() {
+        let asm = assembly("x 5\n? (x > 1 AND x < 9)\n    ! \"in\"\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "jle L_0",
+                "jge L_0",
+                "mov rsi, str_0",
+                "call _nebula_print",
+                "L_0:",
+            ],
+        );
+        // The condition is never materialised as 0/1.
+        let body = &asm[asm.find("call _nebula_quantum_init").unwrap()..asm.find("L_0:").unwrap()];
+        assert!(!body.contains("push 0"), "{}", body);
+    }
+
+    #[test]
+    fn input_builtins_call_the_stdin_routines() {
+        let asm = assembly("n input_int(-1)\nl input()\ne eof()\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "push -1",
+                "pop rdi",
+                "call _nebula_read_int",
+                "mov [var_n], rax",
+                "call _nebula_read_line",
+                "mov [var_l], rax",
+                "call _nebula_stdin_eof",
+                "mov [var_e], rax",
+                "_nebula_read_line:",
+                "_nebula_read_int:",
+                "_nebula_stdin_eof:",
+            ],
+        );
+    }
+
+    #[test]
+    fn start_saves_arguments_and_environment_before_aligning() {
+        let asm = assembly("c argc()\na arg(1)\nh env(\"HOME\")\n");
+        assert_lines_in_order(
+            &asm,
+            &[
+                "_start:",
+                "mov rbp, rsp",
+                "mov rax, [rbp]",
+                "mov [arg_count], rax",
+                "lea rcx, [rbp + 8]",
+                "mov [arg_values], rcx",
+                "lea rcx, [rcx + rax * 8 + 8]",
+                "mov [env_values], rcx",
+                "and rsp, 0xFFFFFFFFFFFFFFF0",
+                "call _nebula_argc",
+                "mov [var_c], rax",
+                "call _nebula_arg",
+                "mov [var_a], rax",
+                "call _nebula_env",
+                "mov [var_h], rax",
+            ],
+        );
+        assert_lines_in_order(
+            &asm,
+            &[
+                "_nebula_arg:",
+                "cmp rdi, [arg_count]",
+                "jae _nebula_argument_bounds_error",
+            ],
+        );
+    }
+}
@@ -6,7 +6,7 @@
 //!
 //! It manages control flow labels and variable storage based on the shared code generation context.
 
-use super::common::CodeGenCommon;
+use super::common::{ARGUMENT_REGISTERS, CodeGenCommon, LoopLabels};
 use super::expression_generator::ExpressionGenerator;
 use super::print_generator::PrintGenerator;
 use crate::ast::nodes::*;
@@ -31,31 +31,210 @@ impl StatementGenerator {
         common: &mut CodeGenCommon,
         statement: &Statement,
     ) -> Result<String, CompileError> {
-        match statement {
-            Statement::VariableDeclaration { name, value } => {
+        let asm = match &statement.kind {
+            StatementKind::VariableDeclaration { name, value } => {
                 Self::generate_variable_declaration(common, name, value)
             }
-            Statement::ArrayDeclaration { name, elements } => {
+            StatementKind::ArrayDeclaration { name, elements } => {
                 Self::generate_array_declaration(common, name, elements)
             }
-            Statement::Print { parts } => Self::generate_print_statement(common, parts),
-            Statement::Loop {
+            StatementKind::CollectionDeclaration { collection } => {
+                Self::generate_collection_declaration(common, collection)
+            }
+            StatementKind::ArrayAssignment {
+                array,
+                index,
+                value,
+            } => Self::generate_array_assignment(common, array, index, value),
+            StatementKind::ForEach {
+                variable,
+                iterable,
+                body,
+            } => Self::generate_for_each(common, variable, iterable, body),
+            StatementKind::Print { parts } => Self::generate_print_statement(common, parts),
+            StatementKind::Loop {
                 variable,
                 start,
                 end,
+                inclusive,
+                step,
                 body,
-            } => Self::generate_loop(common, variable, start, end, body),
-            Statement::If {
+            } => Self::generate_loop(common, variable, start, end, *inclusive, step, body),
+            StatementKind::While { condition, body } => {
+                Self::generate_while(common, condition, body)
+            }
+            StatementKind::Break => Self::generate_loop_jump(common, true),
+            StatementKind::Continue => Self::generate_loop_jump(common, false),
+            StatementKind::If {
                 condition,
                 then_branch,
                 else_branch,
             } => Self::generate_conditional(common, condition, then_branch, else_branch),
-            Statement::Assignment {
+            StatementKind::Assignment {
                 name,
                 value,
                 operator,
             } => Self::generate_assignment(common, name, value, operator),
+            StatementKind::Function {
+                name,
+                parameters,
+                body,
+            } => Self::generate_function_definition(common, name, parameters, body),
+            StatementKind::Return { value } => Self::generate_return(common, value),
+            StatementKind::Call {
+                function,
+                arguments,
+            } => {
+                let mut asm = ExpressionGenerator::generate_call(common, function, arguments)?;
+                asm.push_str("    add rsp, 8\n"); // Discard the returned value
+                Ok(asm)
+            }
+        };
+        asm.map_err(|error| error.with_span(statement.span))
+    }
+
+    /// Generates assembly for a user-defined function with its own stack frame.
+    ///
+    /// Parameters and local variables live in the frame (`[rbp - 8]`, `[rbp - 16]`, ...).
+    /// The first six parameters arrive in the System V argument registers and are spilled
+    /// into their slots; the remaining ones are read from the caller's stack (`[rbp + 16]`, ...).
+    /// Because every call gets a fresh frame, recursion works naturally.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `name` - The function identifier.
+    /// * `parameters` - The parameter names, in declaration order.
+    /// * `body` - The statements of the function body.
+    fn generate_function_definition(
+        common: &mut CodeGenCommon,
+        name: &str,
+        parameters: &[String],
+        body: &[Statement],
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let label = CodeGenCommon::function_label(name);
+        let return_label = format!("{}_return", label);
+
+        // Parameters come first so that register parameters occupy the first slots.
+        let mut locals: Vec<(String, usize)> = parameters
+            .iter()
+            .map(|parameter| (parameter.clone(), 1))
+            .collect();
+        CodeGenCommon::collect_variables_from_statements(body, &mut locals);
+
+        // Swap in a fresh variable scope for the function body, typed from the analysis.
+        let function_types = common.function_types.get(name).cloned().unwrap_or_default();
+        let outer_addresses = std::mem::take(&mut common.variable_addresses);
+        let outer_types = std::mem::take(&mut common.variable_types);
+        let outer_collections = std::mem::take(&mut common.collections);
+        let outer_return = common.return_label.replace(return_label.clone());
+        let outer_return_type =
+            std::mem::replace(&mut common.return_type, function_types.return_type.clone());
+        let outer_loops = std::mem::take(&mut common.loop_labels);
+
+        let mut slots = 0;
+        for (index, (local, size)) in locals.iter().enumerate() {
+            let address = if index >= ARGUMENT_REGISTERS.len() && index < parameters.len() {
+                // Stack parameter: above the return address and the saved RBP.
+                format!("rbp + {}", 16 + (index - ARGUMENT_REGISTERS.len()) * 8)
+            } else {
+                // Arrays take `size` slots; their address is the lowest one (the length slot).
+                slots += size;
+                format!("rbp - {}", slots * 8)
+            };
+            let local_type = function_types
+                .parameters
+                .get(index)
+                .filter(|_| index < parameters.len())
+                .or_else(|| function_types.variables.get(local))
+                .filter(|local_type| **local_type != Type::Unknown)
+                .cloned()
+                .unwrap_or(Type::Integer);
+            common.variable_addresses.insert(local.clone(), address);
+            common.variable_types.insert(local.clone(), local_type);
+        }
+        // Keep RSP 16-byte aligned inside the function.
+        let frame_size = (slots * 8 + 15) & !15;
+
+        // --- Prologue ---
+        asm.push_str(&format!("\n{}:\n", label));
+        asm.push_str("    push rbp\n");
+        asm.push_str("    mov rbp, rsp\n");
+        if frame_size > 0 {
+            asm.push_str(&format!("    sub rsp, {}\n", frame_size));
+        }
+        for (index, register) in ARGUMENT_REGISTERS.iter().take(parameters.len()).enumerate() {
+            asm.push_str(&format!(
+                "    mov [rbp - {}], {}\n",
+                (index + 1) * 8,
+                register
+            ));
         }
+
+        // --- Body ---
+        let body_result = body
+            .iter()
+            .map(|stmt| Self::generate_statement(common, stmt))
+            .collect::<Result<Vec<_>, _>>();
+
+        // Restore the outer scope before propagating any error.
+        common.variable_addresses = outer_addresses;
+        common.variable_types = outer_types;
+        common.collections = outer_collections;
+        common.return_label = outer_return;
+        common.return_type = outer_return_type;
+        common.loop_labels = outer_loops;
+
+        for stmt_asm in body_result? {
+            asm.push_str(&stmt_asm);
+        }
+
+        // --- Epilogue --- (falling off the end returns 0)
+        asm.push_str("    xor eax, eax\n");
+        asm.push_str(&format!("{}:\n", return_label));
+        asm.push_str("    mov rsp, rbp\n");
+        asm.push_str("    pop rbp\n");
+        asm.push_str("    ret\n");
+
+        Ok(asm)
+    }
+
+    /// Generates assembly for a return statement: the value goes into RAX and control
+    /// jumps to the enclosing function's epilogue.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `value` - The optional returned expression.
+    fn generate_return(
+        common: &mut CodeGenCommon,
+        value: &Option<Box<Expression>>,
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let return_label = common
+            .return_label
+            .clone()
+            .ok_or_else(|| CompileError::analysis("Return ('=>') outside of a function"))?;
+
+        match value {
+            Some(value) => {
+                // Floats are returned as their raw bits, like any other value.
+                let expr_asm = if common.return_type == Type::Float {
+                    ExpressionGenerator::generate_float_operand(common, value)?
+                } else {
+                    ExpressionGenerator::generate_expression(common, value)?
+                };
+                asm.push_str(&expr_asm);
+                asm.push_str("    pop rax\n");
+            }
+            None => {
+                asm.push_str("    xor eax, eax\n");
+            }
+        }
+        asm.push_str(&format!("    jmp {}\n", return_label));
+
+        Ok(asm)
     }
 
     /// Generates assembly for a variable declaration.
@@ -73,21 +252,36 @@ impl StatementGenerator {
         value: &Expression,
     ) -> Result<String, CompileError> {
         let mut asm = String::new();
-        // Register variable and get its assembly address. Default type is assumed to be Integer/Pointer.
-        let address = common.register_variable(name, Type::Integer);
 
-        match value {
-            Expression::Integer(n) => {
-                // Direct assignment of a 64-bit integer literal.
+        // Arrays (e.g., `myCars c:b`) are copied element by element, length slot included.
+        if let array_type @ Type::Array(_) = ExpressionGenerator::static_type(common, value) {
+            let source = ExpressionGenerator::array_base(common, value)?;
+            let address = common.register_variable(name, array_type);
+            asm.push_str(&format!("    lea rsi, [{}]\n", source));
+            asm.push_str(&format!("    lea rdi, [{}]\n", address));
+            asm.push_str("    mov rcx, [rsi]\n");
+            asm.push_str("    inc rcx\n");
+            asm.push_str("    rep movsq\n");
+            return Ok(asm);
+        }
+
+        // Register variable and get its assembly address. The type decides how the value is
+        // computed and printed later on.
+        let scalar_type = ExpressionGenerator::static_type(common, value);
+        let address = common.register_variable(name, scalar_type);
+
+        match &value.kind {
+            // Direct assignment of an integer literal that fits a 32-bit immediate.
+            ExpressionKind::Integer(n) if i32::try_from(*n).is_ok() => {
                 asm.push_str(&format!("    mov qword [{}], {}\n", address, n));
             }
-            Expression::String(s) => {
+            ExpressionKind::String(s) => {
                 // Get string label, load address into RAX, then store RAX into variable address.
                 let label = common.add_string_to_pool(s);
                 asm.push_str(&format!("    mov rax, {}\n", label));
                 asm.push_str(&format!("    mov [{}], rax\n", address));
             }
-            Expression::Variable(src_name) => {
+            ExpressionKind::Variable(src_name) => {
                 // Copy value from another variable.
                 let src_address = common
                     .get_variable_address(src_name)
@@ -108,8 +302,8 @@ impl StatementGenerator {
 
     /// Generates assembly for an array declaration.
     ///
-    /// Note: This simplified implementation only reserves the first element's space in BSS.
-    /// A full implementation would require memory allocation and element storage.
+    /// The array's storage (reserved by `collect_variables`) holds the length in its first
+    /// slot, followed by one 8-byte slot per element. Every element is initialized.
     ///
     /// # Arguments
     ///
@@ -122,30 +316,186 @@ impl StatementGenerator {
         elements: &[Expression],
     ) -> Result<String, CompileError> {
         let mut asm = String::new();
-        // Register array identifier. The `var_<name>` label will point to the first element's space.
-        let address = common.register_variable(name, Type::Integer);
-
-        // Simple initialization of the first element (for demonstration/basic use).
-        if let Some(first_element) = elements.first() {
-            match first_element {
-                Expression::Integer(n) => {
-                    asm.push_str(&format!("    mov qword [{}], {}\n", address, n));
-                }
-                Expression::String(s) => {
-                    let label = common.add_string_to_pool(s);
-                    asm.push_str(&format!("    mov rax, {}\n", label));
-                    asm.push_str(&format!("    mov [{}], rax\n", address));
+        // Register array identifier. The `var_<name>` label points to the length slot.
+        // The element type follows the elements, which the analyzer checked; integers
+        // mixed with floats are stored as floats.
+        let element_type = elements
+            .iter()
+            .map(|element| ExpressionGenerator::static_type(common, element))
+            .reduce(|element_type, current| element_type.promote(&current))
+            .unwrap_or(Type::Integer);
+        let address = common.register_variable(name, Type::Array(Box::new(element_type.clone())));
+
+        asm.push_str(&format!(
+            "    mov qword [{}], {}\n",
+            address,
+            elements.len()
+        ));
+
+        for (index, element) in elements.iter().enumerate() {
+            let offset = (index + 1) * 8;
+            match &element.kind {
+                ExpressionKind::Integer(n)
+                    if element_type != Type::Float && i32::try_from(*n).is_ok() =>
+                {
+                    asm.push_str(&format!(
+                        "    mov qword [{} + {}], {}\n",
+                        address, offset, n
+                    ));
                 }
                 _ => {
-                    // Complex expression initialization (e.g., array = [1+1, 2+2]) is not fully implemented here
+                    let expr_asm = if element_type == Type::Float {
+                        ExpressionGenerator::generate_float_operand(common, element)?
+                    } else {
+                        ExpressionGenerator::generate_expression(common, element)?
+                    };
+                    asm.push_str(&expr_asm);
+                    asm.push_str("    pop rax\n");
+                    asm.push_str(&format!("    mov [{} + {}], rax\n", address, offset));
                 }
             }
         }
         Ok(asm)
     }
 
+    /// Generates assembly for a named collection declaration.
+    ///
+    /// Every leaf collection is stored like an array under its canonical name
+    /// (e.g., `var_cars.brands`); the collection is then registered so that paths
+    /// such as `c:b` can be resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `collection` - The declared root collection.
+    fn generate_collection_declaration(
+        common: &mut CodeGenCommon,
+        collection: &Collection,
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+
+        let mut leaves = Vec::new();
+        collection.for_each_leaf("", &mut |canonical, leaf| {
+            leaves.push((canonical, leaf.elements.clone()));
+        });
+        for (canonical, elements) in leaves {
+            asm.push_str(&Self::generate_array_declaration(
+                common, &canonical, &elements,
+            )?);
+        }
+
+        common.collections.push(collection.clone());
+        Ok(asm)
+    }
+
+    /// Generates assembly for storing a value into a single array element.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `array` - The array identifier.
+    /// * `index` - The index expression.
+    /// * `value` - The new element value.
+    fn generate_array_assignment(
+        common: &mut CodeGenCommon,
+        array: &str,
+        index: &Expression,
+        value: &Expression,
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let address = common
+            .get_variable_address(array)
+            .ok_or_else(|| CompileError::undefined_variable(array))?
+            .clone();
+
+        // 1. Evaluate the value (converted for float arrays), then the index.
+        let value_asm = match common.get_variable_type(array) {
+            Some(Type::Array(element_type)) if **element_type == Type::Float => {
+                ExpressionGenerator::generate_float_operand(common, value)?
+            }
+            _ => ExpressionGenerator::generate_expression(common, value)?,
+        };
+        asm.push_str(&value_asm);
+        asm.push_str(&ExpressionGenerator::generate_expression(common, index)?);
+
+        // 2. Pop the index into RBX and check it against the length.
+        asm.push_str("    pop rbx\n");
+        asm.push_str(&ExpressionGenerator::generate_bounds_check(
+            common, &address,
+        ));
+
+        // 3. Store the value into the element slot.
+        asm.push_str("    pop rax\n");
+        asm.push_str(&format!("    mov [{} + 8 + rbx * 8], rax\n", address));
+        Ok(asm)
+    }
+
+    /// Generates assembly code for a loop over the elements of an array.
+    ///
+    /// The loop structure is: `for (index = 0; index < length; index++) variable = array[index]`.
+    /// The index lives in a hidden slot next to the loop variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `variable` - The element variable identifier.
+    /// * `iterable` - The array expression.
+    /// * `body` - The statements inside the loop.
+    fn generate_for_each(
+        common: &mut CodeGenCommon,
+        variable: &str,
+        iterable: &Expression,
+        body: &[Statement],
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let loop_label = common.next_label();
+        let step_label = common.next_label();
+        let end_label = common.next_label();
+        let base = ExpressionGenerator::array_base(common, iterable)?;
+        let element_type = match ExpressionGenerator::static_type(common, iterable) {
+            Type::Array(element_type) => *element_type,
+            _ => Type::Integer,
+        };
+        let address = common.register_variable(variable, element_type);
+        let index_address =
+            common.register_variable(&CodeGenCommon::index_slot(variable), Type::Integer);
+
+        // --- 1. Loop Initialization (index = 0) ---
+        asm.push_str(&format!("    mov qword [{}], 0\n", index_address));
+
+        // --- 2. Loop Condition Check (index < length) ---
+        asm.push_str(&format!("{}:\n", loop_label));
+        asm.push_str(&format!("    mov rbx, [{}]\n", index_address));
+        asm.push_str(&format!("    cmp rbx, [{}]\n", base));
+        asm.push_str(&format!("    jge {}\n", end_label));
+
+        // --- 3. Bind the current element ---
+        asm.push_str(&format!("    mov rax, [{} + 8 + rbx * 8]\n", base));
+        asm.push_str(&format!("    mov [{}], rax\n", address));
+
+        // --- 4. Loop Body ---
+        asm.push_str(&Self::generate_loop_body(
+            common,
+            body,
+            &step_label,
+            &end_label,
+        )?);
+
+        // --- 5. Loop Step (index++) and Re-entry ---
+        asm.push_str(&format!("{}:\n", step_label));
+        asm.push_str(&format!("    inc qword [{}]\n", index_address));
+        asm.push_str(&format!("    jmp {}\n", loop_label));
+        asm.push_str(&format!("{}:\n", end_label));
+
+        Ok(asm)
+    }
+
     /// Generates assembly for an assignment statement (simple or compound).
     ///
+    /// A compound assignment `x op= v` stores the result of the binary expression `x op v`,
+    /// so it follows the same rules as the operator itself: floats use SSE2, division is
+    /// signed and `+=` on a string appends to it.
+    ///
     /// # Arguments
     ///
     /// * `common` - The mutable code generation context.
@@ -158,34 +508,46 @@ impl StatementGenerator {
         value: &Expression,
         operator: &AssignmentOperator,
     ) -> Result<String, CompileError> {
-        let mut asm = String::new();
         let address = common
             .get_variable_address(name)
             .ok_or_else(|| CompileError::undefined_variable(name))?
             .clone();
+        let variable_type = common.get_variable_type(name).cloned();
 
-        // 1. Load the current value of the variable (LHS) into RAX.
-        asm.push_str(&format!("    mov rax, [{}]\n", address));
+        // Reassigning an array copies the new elements, like its declaration.
+        if matches!(variable_type, Some(Type::Array(_))) {
+            return Self::generate_variable_declaration(common, name, value);
+        }
 
-        // 2. Evaluate the RHS expression and push its result onto the stack.
-        let expr_asm = ExpressionGenerator::generate_expression(common, value)?;
-        asm.push_str(&expr_asm);
+        let compound;
+        let result = match operator.binary_operator() {
+            Some(binary_operator) => {
+                compound = Expression::new(
+                    ExpressionKind::Binary {
+                        left: Box::new(Expression::new(
+                            ExpressionKind::Variable(name.to_string()),
+                            value.span,
+                        )),
+                        operator: binary_operator,
+                        right: Box::new(value.clone()),
+                    },
+                    value.span,
+                );
+                &compound
+            }
+            None => value,
+        };
 
-        // 3. Pop the RHS value into RBX.
-        asm.push_str("    pop rbx\n");
+        // 1. Evaluate the new value (converted if an integer is stored in a float variable).
+        let mut asm = if variable_type == Some(Type::Float) {
+            ExpressionGenerator::generate_float_operand(common, result)?
+        } else {
+            ExpressionGenerator::generate_expression(common, result)?
+        };
 
-        // 4. Perform the compound operation (RAX = RAX op RBX).
-        match operator {
-            AssignmentOperator::Multiply => {
-                asm.push_str("    imul rax, rbx\n");
-            }
-            AssignmentOperator::Plus => {
-                asm.push_str("    add rax, rbx\n");
-            }
-        }
-        // 5. Store the final result back into the variable's memory location.
+        // 2. Store it back into the variable's memory location.
+        asm.push_str("    pop rax\n");
         asm.push_str(&format!("    mov [{}], rax\n", address));
-
         Ok(asm)
     }
 
@@ -204,7 +566,10 @@ impl StatementGenerator {
 
     /// Generates assembly code for a count-controlled loop construct.
     ///
-    /// The loop structure is: `for (variable = start; variable <= end; variable++)`.
+    /// The loop structure is: `for (variable = start; variable <= end; variable += step)`,
+    /// with `<` for an exclusive range and `>=` / `>` when the step is negative. `end` and
+    /// `step` are evaluated once, before the first iteration, into hidden slots next to the
+    /// loop variable. Without a step, the loop counts up by one.
     ///
     /// # Arguments
     ///
@@ -212,26 +577,45 @@ impl StatementGenerator {
     /// * `variable` - The loop variable identifier.
     /// * `start` - The starting expression for the loop range.
     /// * `end` - The ending expression for the loop range.
+    /// * `inclusive` - Whether `end` itself is part of the range.
+    /// * `step` - The optional step expression.
     /// * `body` - The statements inside the loop.
     fn generate_loop(
         common: &mut CodeGenCommon,
         variable: &str,
         start: &Expression,
         end: &Expression,
+        inclusive: bool,
+        step: &Option<Box<Expression>>,
         body: &[Statement],
     ) -> Result<String, CompileError> {
         let mut asm = String::new();
         let loop_label = common.next_label();
+        let step_label = common.next_label();
         let end_label = common.next_label();
-        // Register the loop variable.
+        // Register the loop variable and its hidden slots.
         let address = common.register_variable(variable, Type::Integer);
+        let end_address =
+            common.register_variable(&CodeGenCommon::end_slot(variable), Type::Integer);
+        let step_address =
+            common.register_variable(&CodeGenCommon::step_slot(variable), Type::Integer);
+
+        // Without a step, the loop counts up by one; a literal step fixes the direction as well.
+        let constant_step = match step.as_deref() {
+            None => Some(1),
+            Some(Expression {
+                kind: ExpressionKind::Integer(n),
+                ..
+            }) => Some(*n),
+            Some(_) => None,
+        };
 
         // --- 1. Loop Initialization (variable = start) ---
-        match start {
-            Expression::Integer(n) => {
+        match &start.kind {
+            ExpressionKind::Integer(n) if i32::try_from(*n).is_ok() => {
                 asm.push_str(&format!("    mov qword [{}], {}\n", address, n));
             }
-            Expression::Variable(name) => {
+            ExpressionKind::Variable(name) => {
                 let src_address = common
                     .get_variable_address(name)
                     .ok_or_else(|| CompileError::undefined_variable(name))?;
@@ -247,50 +631,180 @@ impl StatementGenerator {
             }
         }
 
-        // --- 2. Loop Condition Check ---
-        asm.push_str(&format!("{}:\n", loop_label));
-        asm.push_str(&format!("    mov rax, [{}]\n", address)); // Load loop variable (i)
-
-        // Compare loop variable (RAX) with the end expression.
-        match end {
-            Expression::Integer(n) => {
-                asm.push_str(&format!("    cmp rax, {}\n", n));
-            }
-            Expression::Variable(name) => {
-                let src_address = common
-                    .get_variable_address(name)
-                    .ok_or_else(|| CompileError::undefined_variable(name))?;
-                asm.push_str(&format!("    mov rbx, [{}]\n", src_address));
-                asm.push_str("    cmp rax, rbx\n");
-            }
+        // The end is evaluated once; a literal end is compared directly.
+        let end_operand = match &end.kind {
+            ExpressionKind::Integer(n) if i32::try_from(*n).is_ok() => n.to_string(),
             _ => {
-                // Evaluate complex end expression.
                 let expr_asm = ExpressionGenerator::generate_expression(common, end)?;
                 asm.push_str(&expr_asm);
-                asm.push_str("    pop rbx\n");
-                asm.push_str("    cmp rax, rbx\n");
+                asm.push_str("    pop rax\n");
+                asm.push_str(&format!("    mov [{}], rax\n", end_address));
+                format!("qword [{}]", end_address)
             }
+        };
+
+        // A computed step is evaluated once as well; its sign is tested on every iteration.
+        // A zero step would never reach the end, so it stops the program instead.
+        if let (Some(step), None) = (step, constant_step) {
+            let expr_asm = ExpressionGenerator::generate_expression(common, step)?;
+            asm.push_str(&expr_asm);
+            asm.push_str("    pop rax\n");
+            asm.push_str(&format!("    mov [{}], rax\n", step_address));
+            let nonzero_label = common.next_label();
+            asm.push_str("    test rax, rax\n");
+            asm.push_str(&format!("    jnz {}\n", nonzero_label));
+            asm.push_str(&common.generate_arithmetic_error("loop step is zero", step.span));
+            asm.push_str(&format!("{}:\n", nonzero_label));
         }
 
-        // Jump Greater (jg) to end if loop variable is past the end limit.
-        asm.push_str(&format!("    jg {}\n", end_label));
+        // --- 2. Loop Condition Check ---
+        // Counting up stops past the end (jg), or at it for an exclusive range (jge);
+        // counting down mirrors this (jl / jle).
+        let (past_end_up, past_end_down) = if inclusive {
+            ("jg", "jl")
+        } else {
+            ("jge", "jle")
+        };
+        asm.push_str(&format!("{}:\n", loop_label));
+        asm.push_str(&format!("    mov rax, [{}]\n", address)); // Load loop variable (i)
+        match constant_step {
+            Some(n) => {
+                asm.push_str(&format!("    cmp rax, {}\n", end_operand));
+                let jump = if n < 0 { past_end_down } else { past_end_up };
+                asm.push_str(&format!("    {} {}\n", jump, end_label));
+            }
+            None => {
+                let down_label = common.next_label();
+                let body_label = common.next_label();
+                asm.push_str(&format!("    cmp qword [{}], 0\n", step_address));
+                asm.push_str(&format!("    jl {}\n", down_label));
+                asm.push_str(&format!("    cmp rax, {}\n", end_operand));
+                asm.push_str(&format!("    {} {}\n", past_end_up, end_label));
+                asm.push_str(&format!("    jmp {}\n", body_label));
+                asm.push_str(&format!("{}:\n", down_label));
+                asm.push_str(&format!("    cmp rax, {}\n", end_operand));
+                asm.push_str(&format!("    {} {}\n", past_end_down, end_label));
+                asm.push_str(&format!("{}:\n", body_label));
+            }
+        }
 
         // --- 3. Loop Body ---
-        for stmt in body {
-            let stmt_asm = Self::generate_statement(common, stmt)?;
-            asm.push_str(&stmt_asm);
-        }
+        asm.push_str(&Self::generate_loop_body(
+            common,
+            body,
+            &step_label,
+            &end_label,
+        )?);
 
-        // --- 4. Loop Step (variable++) and Re-entry ---
-        asm.push_str(&format!("    inc qword [{}]\n", address)); // Increment loop variable
+        // --- 4. Loop Step (variable += step) and Re-entry ---
+        asm.push_str(&format!("{}:\n", step_label));
+        match constant_step {
+            Some(1) => asm.push_str(&format!("    inc qword [{}]\n", address)),
+            Some(-1) => asm.push_str(&format!("    dec qword [{}]\n", address)),
+            Some(n) => {
+                asm.push_str(&format!("    mov rax, {}\n", n));
+                asm.push_str(&format!("    add [{}], rax\n", address));
+            }
+            None => {
+                asm.push_str(&format!("    mov rax, [{}]\n", step_address));
+                asm.push_str(&format!("    add [{}], rax\n", address));
+            }
+        }
         asm.push_str(&format!("    jmp {}\n", loop_label)); // Jump back to condition check
         asm.push_str(&format!("{}:\n", end_label)); // Loop termination label
 
         Ok(asm)
     }
 
+    /// Generates assembly code for a condition loop (`@ (cond)`).
+    ///
+    /// The loop structure is: `while (condition) { body }`.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `condition` - The boolean expression tested before every iteration.
+    /// * `body` - The statements inside the loop.
+    fn generate_while(
+        common: &mut CodeGenCommon,
+        condition: &Expression,
+        body: &[Statement],
+    ) -> Result<String, CompileError> {
+        let mut asm = String::new();
+        let loop_label = common.next_label();
+        let end_label = common.next_label();
+
+        // --- 1. Loop Condition Check (leave the loop when it is false) ---
+        asm.push_str(&format!("{}:\n", loop_label));
+        asm.push_str(&ExpressionGenerator::generate_condition_jump(
+            common, condition, &end_label, false,
+        )?);
+
+        // --- 2. Loop Body and Re-entry ---
+        asm.push_str(&Self::generate_loop_body(
+            common,
+            body,
+            &loop_label,
+            &end_label,
+        )?);
+        asm.push_str(&format!("    jmp {}\n", loop_label));
+        asm.push_str(&format!("{}:\n", end_label));
+
+        Ok(asm)
+    }
+
+    /// Generates the body of a loop, with `break` and `continue` bound to its labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `body` - The statements inside the loop.
+    /// * `continue_label` - The label `continue` jumps to.
+    /// * `break_label` - The label `break` jumps to.
+    fn generate_loop_body(
+        common: &mut CodeGenCommon,
+        body: &[Statement],
+        continue_label: &str,
+        break_label: &str,
+    ) -> Result<String, CompileError> {
+        common.loop_labels.push(LoopLabels {
+            continue_label: continue_label.to_string(),
+            break_label: break_label.to_string(),
+        });
+
+        let body_result = body
+            .iter()
+            .map(|stmt| Self::generate_statement(common, stmt))
+            .collect::<Result<Vec<_>, _>>();
+
+        // Unbind the labels before propagating any error.
+        common.loop_labels.pop();
+        Ok(body_result?.concat())
+    }
+
+    /// Generates the jump of a `break` or `continue` statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `common` - The mutable code generation context.
+    /// * `is_break` - `true` for `break`, `false` for `continue`.
+    fn generate_loop_jump(common: &CodeGenCommon, is_break: bool) -> Result<String, CompileError> {
+        let labels = common
+            .loop_labels
+            .last()
+            .ok_or_else(|| CompileError::analysis("'break' or 'continue' outside of a loop"))?;
+        let target = if is_break {
+            &labels.break_label
+        } else {
+            &labels.continue_label
+        };
+        Ok(format!("    jmp {}\n", target))
+    }
+
     /// Generates assembly code for a conditional (`If` / `If-Else`) statement.
     ///
+    /// An else-if chain is lowered as a flat sequence of tests that all share a single end label.
+    ///
     /// # Arguments
     ///
     /// * `common` - The mutable code generation context.
@@ -304,38 +818,64 @@ impl StatementGenerator {
         else_branch: &Option<Vec<Statement>>,
     ) -> Result<String, CompileError> {
         let mut asm = String::new();
-        let else_label = common.next_label();
         let end_label = common.next_label();
 
-        // 1. Evaluate the condition expression. Result (0 or 1) is pushed to stack.
-        let cond_asm = ExpressionGenerator::generate_expression(common, condition)?;
-        asm.push_str(&cond_asm);
-        asm.push_str("    pop rax\n");
-        // 2. Test RAX (condition result). RAX=0 (false) sets ZF.
-        asm.push_str("    test rax, rax\n");
+        let mut condition = condition;
+        let mut then_branch = then_branch;
+        let mut else_branch = else_branch;
+        loop {
+            let else_label = common.next_label();
 
-        // 3. Jump to the `else` block or `end` if the condition is false (ZF is set).
-        if else_branch.is_some() {
-            asm.push_str(&format!("    jz {}\n", else_label)); // Jump to ELSE
-        } else {
-            asm.push_str(&format!("    jz {}\n", end_label)); // Jump to END (skip THEN)
-        }
+            // 1-3. Branch on the condition: if it is false, jump to the `else` block, or to
+            //      `end` when there is none (skipping THEN).
+            let false_label = if else_branch.is_some() {
+                &else_label
+            } else {
+                &end_label
+            };
+            asm.push_str(&ExpressionGenerator::generate_condition_jump(
+                common,
+                condition,
+                false_label,
+                false,
+            )?);
 
-        // 4. Generate 'Then' Branch
-        for stmt in then_branch {
-            let stmt_asm = Self::generate_statement(common, stmt)?;
-            asm.push_str(&stmt_asm);
-        }
-        // Jump over the 'Else' block to the 'End' label.
-        asm.push_str(&format!("    jmp {}\n", end_label));
+            // 4. Generate 'Then' Branch
+            for stmt in then_branch {
+                let stmt_asm = Self::generate_statement(common, stmt)?;
+                asm.push_str(&stmt_asm);
+            }
 
-        // 5. Generate 'Else' Branch (Optional)
-        if let Some(else_branch) = else_branch {
+            let Some(branch) = else_branch else {
+                break;
+            };
+            // Jump over the rest of the chain to the 'End' label.
+            asm.push_str(&format!("    jmp {}\n", end_label));
             asm.push_str(&format!("{}:\n", else_label));
-            for stmt in else_branch {
+
+            // 5. Continue with the next link of an else-if chain, or generate the final 'Else' Branch.
+            if let [
+                Statement {
+                    kind:
+                        StatementKind::If {
+                            condition: next_condition,
+                            then_branch: next_then,
+                            else_branch: next_else,
+                        },
+                    ..
+                },
+            ] = branch.as_slice()
+            {
+                condition = next_condition;
+                then_branch = next_then;
+                else_branch = next_else;
+                continue;
+            }
+            for stmt in branch {
                 let stmt_asm = Self::generate_statement(common, stmt)?;
                 asm.push_str(&stmt_asm);
             }
+            break;
         }
 
         // 6. End of Conditional
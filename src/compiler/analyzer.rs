@@ -7,13 +7,77 @@
 
 use crate::ast::nodes::*;
 use crate::ast::types::Type;
+use crate::codegen::common::OverflowMode;
+use crate::compiler::builtins::Builtin;
+use crate::compiler::diagnostic::closest_match;
 use crate::compiler::error::CompileError;
+use crate::compiler::span::Span;
 use std::collections::HashMap;
 
+/// The largest number of decimal places a formatted number can be printed with (`{x:.15}`).
+pub const MAX_PRECISION: usize = 15;
+
+/// The signature of a user-defined function, refined as calls are analyzed.
+#[derive(Debug, Clone)]
+struct FunctionSignature {
+    /// Parameter types, fixed by the first call that supplies a known type.
+    parameters: Vec<Type>,
+    /// The type of the value returned by `=> value`, inferred from the body.
+    return_type: Type,
+    /// Whether the body contains at least one `=> value` statement.
+    returns_value: bool,
+    /// The span of the `# name(...)` header, for pointing back at the definition.
+    span: Span,
+}
+
+/// The types inferred for one user-defined function.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionTypes {
+    /// The parameter types, in declaration order ([`Type::Unknown`] if never called).
+    pub parameters: Vec<Type>,
+    /// The types of the variables declared in the function body.
+    pub variables: HashMap<String, Type>,
+    /// The type of the returned value, or [`Type::Void`] if the function returns none.
+    pub return_type: Type,
+}
+
+/// The types inferred by the analyzer, handed to code generation so that every value
+/// is stored, computed and printed according to its type.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramTypes {
+    /// The types of the top-level variables (including loop variables).
+    pub variables: HashMap<String, Type>,
+    /// The inferred types of every user-defined function, by name.
+    pub functions: HashMap<String, FunctionTypes>,
+}
+
 /// The central structure for performing semantic analysis.
 pub struct Analyzer {
     /// Symbol table: Maps variable names (`String`) to their declared [`Type`].
+    ///
+    /// Each function body is analyzed with its own table, so variables never leak
+    /// between functions and the top level.
     symbols: HashMap<String, Type>,
+    /// Where each variable in `symbols` was first declared, to point back at it in errors.
+    declarations: HashMap<String, Span>,
+    /// The named collections declared in the current scope, in declaration order.
+    ///
+    /// Leaf collections are also entered in `symbols` as arrays under their canonical
+    /// names (e.g. `cars.brands`), which cannot clash with user identifiers.
+    collections: Vec<Collection>,
+    /// Function table: Maps function names to their signatures.
+    functions: HashMap<String, FunctionSignature>,
+    /// Signatures inferred by a previous pass, used as the starting point for `functions`.
+    inferred: HashMap<String, FunctionSignature>,
+    /// The types of every variable declared so far, by scope.
+    types: ProgramTypes,
+    /// The name of the function whose body is currently being visited, if any.
+    current_function: Option<String>,
+    /// The number of loops enclosing the statement being visited, for `break` and `continue`.
+    loop_depth: usize,
+    /// What integer overflow does at runtime, which decides whether a literal power
+    /// that does not fit in 64 bits is an error.
+    overflow: OverflowMode,
     /// Accumulates all semantic errors found during the visit phase.
     errors: Vec<CompileError>,
 }
@@ -23,54 +87,384 @@ impl Analyzer {
     pub fn new() -> Self {
         Self {
             symbols: HashMap::new(),
+            declarations: HashMap::new(),
+            collections: Vec::new(),
+            functions: HashMap::new(),
+            inferred: HashMap::new(),
+            types: ProgramTypes::default(),
+            current_function: None,
+            loop_depth: 0,
+            overflow: OverflowMode::Trap,
             errors: Vec::new(),
         }
     }
 
     /// The main entry point for starting the analysis of a program.
     ///
-    /// It consumes the AST and returns an error if any semantic problems are found.
+    /// It consumes the AST and returns every semantic problem found, not just the first.
+    ///
+    /// Parameter types are fixed by calls, which may come after the function body. A first
+    /// pass therefore infers the function signatures, and a second pass checks the whole
+    /// program (function bodies included) with them known from the start.
     ///
     /// # Arguments
     ///
     /// * `ast` - The root [`Program`] AST node.
-    pub fn analyze(ast: &Program) -> Result<(), CompileError> {
+    /// * `overflow` - What integer overflow does at runtime.
+    ///
+    /// # Returns
+    ///
+    /// The inferred [`ProgramTypes`] if the program is valid.
+    pub fn analyze(ast: &Program, overflow: OverflowMode) -> Result<ProgramTypes, CompileError> {
+        let mut inference = Self::new();
+        inference.overflow = overflow;
+        inference.visit_program(ast);
+
         let mut analyzer = Self::new();
+        analyzer.overflow = overflow;
+        analyzer.inferred = inference.functions;
         analyzer.visit_program(ast);
 
         if analyzer.errors.is_empty() {
-            Ok(())
+            Ok(analyzer.into_types())
         } else {
-            // Only return the first error found for simplicity.
-            Err(analyzer.errors.remove(0))
+            Err(CompileError::from_errors(analyzer.errors))
+        }
+    }
+
+    /// Completes the collected variable types with the final function signatures.
+    fn into_types(mut self) -> ProgramTypes {
+        for (name, signature) in self.functions {
+            let function = self.types.functions.entry(name).or_default();
+            function.parameters = signature.parameters;
+            function.return_type = if signature.returns_value {
+                signature.return_type
+            } else {
+                Type::Void
+            };
+        }
+        self.types
+    }
+
+    /// Enters a variable into the symbol table and records its type for code generation.
+    ///
+    /// A name declared several times in one scope keeps its first known type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The variable name.
+    /// * `variable_type` - The type of the variable.
+    fn declare(&mut self, name: &str, variable_type: Type) {
+        let scope = match &self.current_function {
+            Some(function) => {
+                &mut self
+                    .types
+                    .functions
+                    .entry(function.clone())
+                    .or_default()
+                    .variables
+            }
+            None => &mut self.types.variables,
+        };
+        match scope.get_mut(name) {
+            Some(recorded) if *recorded != Type::Unknown => {}
+            Some(recorded) => *recorded = variable_type.clone(),
+            None => {
+                scope.insert(name.to_string(), variable_type.clone());
+            }
         }
+        self.symbols.insert(name.to_string(), variable_type);
     }
 
     /// Recursively visits all statements in the program.
+    ///
+    /// Function signatures are collected first so that functions can be called
+    /// before their definition and can call themselves recursively.
     fn visit_program(&mut self, program: &Program) {
+        self.collect_functions(&program.statements);
+
         for statement in &program.statements {
             self.visit_statement(statement);
         }
     }
 
+    /// Registers the signature of every top-level function definition.
+    fn collect_functions(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            if let StatementKind::Function {
+                name,
+                parameters,
+                body,
+            } = &statement.kind
+            {
+                if Builtin::from_name(name).is_some() {
+                    self.errors.push(
+                        CompileError::analysis(format!(
+                            "'{}' is a built-in function and cannot be redefined",
+                            name
+                        ))
+                        .with_span(statement.span),
+                    );
+                    continue;
+                }
+                if let Some(previous) = self.functions.get(name) {
+                    self.errors.push(
+                        CompileError::analysis(format!(
+                            "Function '{}' is defined more than once",
+                            name
+                        ))
+                        .with_span(statement.span)
+                        .with_label(previous.span, "first defined here"),
+                    );
+                    continue;
+                }
+
+                // Start from the signature inferred by an earlier pass, if any.
+                let inferred = self.inferred.get(name);
+                self.functions.insert(
+                    name.clone(),
+                    FunctionSignature {
+                        parameters: inferred
+                            .map(|signature| signature.parameters.clone())
+                            .unwrap_or_else(|| vec![Type::Unknown; parameters.len()]),
+                        return_type: inferred
+                            .map(|signature| signature.return_type.clone())
+                            .unwrap_or(Type::Unknown),
+                        returns_value: Self::returns_value(body),
+                        span: statement.span,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Checks whether a function body contains a `=> value` statement at any depth.
+    fn returns_value(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| match &statement.kind {
+            StatementKind::Return { value } => value.is_some(),
+            StatementKind::Loop { body, .. }
+            | StatementKind::ForEach { body, .. }
+            | StatementKind::While { body, .. } => Self::returns_value(body),
+            StatementKind::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                Self::returns_value(then_branch)
+                    || else_branch
+                        .as_ref()
+                        .is_some_and(|branch| Self::returns_value(branch))
+            }
+            _ => false,
+        })
+    }
+
+    /// Builds an `UndefinedVariable` error, suggesting a similarly named variable.
+    ///
+    /// Canonical collection names (e.g. `cars.brands`) are not offered, since they
+    /// cannot be written as plain identifiers.
+    fn undefined_variable(&self, name: &str) -> CompileError {
+        let candidates = self
+            .symbols
+            .keys()
+            .map(String::as_str)
+            .filter(|candidate| !candidate.contains('.'));
+
+        let error = CompileError::undefined_variable(name);
+        match closest_match(name, candidates) {
+            Some(similar) => error
+                .with_help(format!(
+                    "a variable with a similar name exists: `{}`",
+                    similar
+                ))
+                .with_replacement(similar),
+            None => error,
+        }
+    }
+
+    /// Attaches a span to every error reported since `first_error` that has none yet.
+    fn attach_span(&mut self, first_error: usize, span: Span) {
+        let located: Vec<_> = self
+            .errors
+            .drain(first_error..)
+            .map(|error| error.with_span(span))
+            .collect();
+        self.errors.extend(located);
+    }
+
     /// Visits a single statement, performing type checks and updating the symbol table.
+    ///
+    /// Errors that are not tied to a more precise location point at the statement.
     fn visit_statement(&mut self, statement: &Statement) {
+        let first_error = self.errors.len();
+        self.visit_statement_kind(&statement.kind, statement.span);
+        self.attach_span(first_error, statement.span);
+    }
+
+    /// Visits the body of a loop, where `break` and `continue` are allowed.
+    fn visit_loop_body(&mut self, body: &[Statement]) {
+        self.loop_depth += 1;
+        for stmt in body {
+            self.visit_statement(stmt);
+        }
+        self.loop_depth -= 1;
+    }
+
+    /// Ends the scope of a loop variable after its body has been visited.
+    ///
+    /// A variable of the same name declared before the loop is visible again afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `variable` - The loop variable.
+    /// * `outer` - The type `variable` had before the loop, if it was declared.
+    fn end_loop_scope(&mut self, variable: &str, outer: Option<Type>) {
+        match outer {
+            Some(outer_type) => {
+                self.symbols.insert(variable.to_string(), outer_type);
+            }
+            None => {
+                self.symbols.remove(variable);
+            }
+        }
+    }
+
+    /// Declares a variable, or checks the new value if the name is already in scope.
+    ///
+    /// `name value` on an existing variable is a reassignment: it keeps the variable's type,
+    /// so the value must be compatible with it (`x 5` cannot be followed by `x "hi"`).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The variable name.
+    /// * `value_type` - The type of the new value.
+    /// * `value_span` - The location of the new value.
+    /// * `span` - The location of the whole declaration.
+    fn declare_or_reassign(&mut self, name: &str, value_type: Type, value_span: Span, span: Span) {
+        match self.symbols.get(name) {
+            Some(variable_type) if *variable_type != Type::Unknown => {
+                let variable_type = variable_type.clone();
+                self.check_reassignment(name, &variable_type, &value_type, value_span);
+            }
+            _ => {
+                self.declare(name, value_type);
+                self.declarations.entry(name.to_string()).or_insert(span);
+            }
+        }
+    }
+
+    /// Checks that a new value can be stored in an existing variable without changing its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The variable name.
+    /// * `variable_type` - The type of the variable.
+    /// * `value_type` - The type of the new value.
+    /// * `value_span` - The location of the new value.
+    fn check_reassignment(
+        &mut self,
+        name: &str,
+        variable_type: &Type,
+        value_type: &Type,
+        value_span: Span,
+    ) {
+        if !variable_type.accepts(value_type) {
+            let error = CompileError::type_mismatch(format!(
+                "Cannot assign {:?} to '{}', which holds {:?}",
+                value_type, name, variable_type
+            ))
+            .with_span(value_span);
+            self.errors.push(self.with_declaration(error, name));
+        }
+    }
+
+    /// Points an error back at the declaration of `name`, if it is known.
+    fn with_declaration(&self, error: CompileError, name: &str) -> CompileError {
+        match (self.declarations.get(name), self.symbols.get(name)) {
+            (Some(span), Some(variable_type)) => error.with_label(
+                *span,
+                format!("'{}' declared as {:?} here", name, variable_type),
+            ),
+            _ => error,
+        }
+    }
+
+    /// Performs the checks specific to each kind of statement.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to check.
+    /// * `span` - The location of the statement.
+    fn visit_statement_kind(&mut self, statement: &StatementKind, span: Span) {
         match statement {
-            Statement::VariableDeclaration { name, value } => {
+            StatementKind::VariableDeclaration { name, value } => {
                 // 1. Determine the type of the value expression.
+                // Array values (e.g., `myCars c:b`) declare a copy of the array.
                 let value_type = self.visit_expression(value);
-                // 2. Register the variable with its inferred type in the symbol table.
-                self.symbols.insert(name.clone(), value_type);
+                // 2. Register the variable, or check a reassignment against its type.
+                self.declare_or_reassign(name, value_type, value.span, span);
             }
-            Statement::ArrayDeclaration { name, elements } => {
-                // Check all element expressions (though a proper check would ensure all are the same type).
-                for element in elements {
-                    self.visit_expression(element);
+            StatementKind::ArrayDeclaration { name, elements } => {
+                let element_type = self.visit_elements(name, elements);
+                self.declare_or_reassign(name, Type::Array(Box::new(element_type)), span, span);
+            }
+            StatementKind::CollectionDeclaration { collection } => {
+                self.visit_collection(collection, "");
+                self.collections.push(collection.clone());
+            }
+            StatementKind::ArrayAssignment {
+                array,
+                index,
+                value,
+            } => {
+                if self.symbols.get(array) == Some(&Type::String) {
+                    self.errors.push(
+                        CompileError::type_mismatch(format!(
+                            "Cannot change a character of string '{}'",
+                            array
+                        ))
+                        .with_help("strings cannot be modified in place; build a new one with `+` and slices"),
+                    );
+                }
+                let element_type = self.visit_array_access(array, index);
+                let value_type = self.visit_expression(value);
+                if !element_type.accepts(&value_type) {
+                    self.errors.push(
+                        CompileError::type_mismatch(format!(
+                            "Cannot store {:?} in array '{}' of {:?}",
+                            value_type, array, element_type
+                        ))
+                        .with_span(value.span),
+                    );
                 }
-                // Array type is simplified to Integer/Pointer for the current code generation.
-                self.symbols.insert(name.clone(), Type::Integer);
             }
-            Statement::Print { parts } => {
+            StatementKind::ForEach {
+                variable,
+                iterable,
+                body,
+            } => {
+                let element_type = match self.visit_expression(iterable) {
+                    Type::Array(element_type) => *element_type,
+                    Type::Unknown => Type::Unknown,
+                    other => {
+                        self.errors.push(
+                            CompileError::r#type(format!(
+                                "Loop 'in' expects an array, found {:?}",
+                                other
+                            ))
+                            .with_span(iterable.span),
+                        );
+                        Type::Unknown
+                    }
+                };
+
+                // Register loop variable (scoped to the loop body).
+                let outer = self.symbols.get(variable).cloned();
+                self.declare(variable, element_type);
+                self.visit_loop_body(body);
+                self.end_loop_scope(variable, outer);
+            }
+            StatementKind::Print { parts } => {
                 // Ensure all expression parts within the print statement are analyzed.
                 for part in parts {
                     match part {
@@ -78,37 +472,128 @@ impl Analyzer {
                         PrintPart::Expression(expr) => {
                             self.visit_expression(expr);
                         }
+                        PrintPart::Formatted {
+                            expression,
+                            precision,
+                        } => {
+                            let value_type = self.visit_expression(expression);
+                            if !value_type.is_numeric() {
+                                self.errors.push(
+                                    CompileError::type_mismatch(format!(
+                                        "Only numbers can be printed with a precision, found {:?}",
+                                        value_type
+                                    ))
+                                    .with_span(expression.span),
+                                );
+                            } else if *precision > MAX_PRECISION {
+                                self.errors.push(
+                                    CompileError::analysis(format!(
+                                        "Precision '.{}' is too large; at most {} decimal places are printed",
+                                        precision, MAX_PRECISION
+                                    ))
+                                    .with_span(expression.span),
+                                );
+                            }
+                        }
                     }
                 }
             }
-            Statement::Loop {
+            StatementKind::Loop {
                 variable,
                 start,
                 end,
+                inclusive,
+                step,
                 body,
             } => {
                 // Check that loop bounds are compatible with Integer type.
                 let start_type = self.visit_expression(start);
                 let end_type = self.visit_expression(end);
 
-                if !start_type.is_compatible_with(&Type::Integer) {
-                    self.errors
-                        .push(CompileError::r#type("Loop start must be integer"));
+                if !Type::Integer.accepts(&start_type) {
+                    self.errors.push(
+                        CompileError::r#type("Loop start must be integer").with_span(start.span),
+                    );
                 }
-                if !end_type.is_compatible_with(&Type::Integer) {
+                if !Type::Integer.accepts(&end_type) {
                     self.errors
-                        .push(CompileError::r#type("Loop end must be integer"));
+                        .push(CompileError::r#type("Loop end must be integer").with_span(end.span));
+                }
+                // A literal range that counts down needs a negative step, or it is empty.
+                if let (None, ExpressionKind::Integer(first), ExpressionKind::Integer(last)) =
+                    (step, &start.kind, &end.kind)
+                    && first > last
+                {
+                    self.errors.push(
+                        CompileError::analysis(format!(
+                            "Range {}{}{} is empty: without a step, loops count up",
+                            first,
+                            if *inclusive { ".." } else { "..<" },
+                            last
+                        ))
+                        .with_span(Span::new(start.span.start, end.span.end))
+                        .with_help("add `step -1` to count down"),
+                    );
+                }
+                if let Some(step) = step {
+                    let step_type = self.visit_expression(step);
+                    if !Type::Integer.accepts(&step_type) {
+                        self.errors.push(
+                            CompileError::r#type("Loop step must be integer").with_span(step.span),
+                        );
+                    } else if matches!(step.kind, ExpressionKind::Integer(0)) {
+                        self.errors.push(
+                            CompileError::analysis("Loop step cannot be zero")
+                                .with_span(step.span)
+                                .with_help(
+                                    "use a positive step to count up, or a negative one to count down",
+                                ),
+                        );
+                    }
+                }
+
+                // An existing variable can only be the counter if it already holds integers.
+                if let Some(variable_type) = self.symbols.get(variable)
+                    && !matches!(variable_type, Type::Integer | Type::Unknown)
+                {
+                    let error = CompileError::type_mismatch(format!(
+                        "Loop counter '{}' would change {:?} variable '{}' to Integer",
+                        variable, variable_type, variable
+                    ))
+                    .with_span(Span::new(span.start, end.span.end));
+                    self.errors.push(self.with_declaration(error, variable));
                 }
 
                 // Register loop variable (scoped to the loop body).
-                self.symbols.insert(variable.clone(), Type::Integer);
-                for stmt in body {
-                    self.visit_statement(stmt);
+                let outer = self.symbols.get(variable).cloned();
+                self.declare(variable, Type::Integer);
+                self.visit_loop_body(body);
+                self.end_loop_scope(variable, outer);
+            }
+            StatementKind::While { condition, body } => {
+                let cond_type = self.visit_expression(condition);
+                if !cond_type.is_compatible_with(&Type::Boolean) {
+                    self.errors.push(
+                        CompileError::r#type("Loop condition must be boolean")
+                            .with_span(condition.span),
+                    );
                 }
-                // Remove variable after loop body traversal (basic scope management).
-                self.symbols.remove(variable);
+                self.visit_loop_body(body);
             }
-            Statement::If {
+            StatementKind::Break | StatementKind::Continue => {
+                if self.loop_depth == 0 {
+                    let keyword = if matches!(statement, StatementKind::Break) {
+                        "break"
+                    } else {
+                        "continue"
+                    };
+                    self.errors.push(CompileError::analysis(format!(
+                        "'{}' is only allowed inside a loop",
+                        keyword
+                    )));
+                }
+            }
+            StatementKind::If {
                 condition,
                 then_branch,
                 else_branch,
@@ -116,8 +601,10 @@ impl Analyzer {
                 // Check that the condition expression evaluates to a Boolean.
                 let cond_type = self.visit_expression(condition);
                 if !cond_type.is_compatible_with(&Type::Boolean) {
-                    self.errors
-                        .push(CompileError::r#type("If condition must be boolean"));
+                    self.errors.push(
+                        CompileError::r#type("If condition must be boolean")
+                            .with_span(condition.span),
+                    );
                 }
 
                 // Visit statement blocks recursively.
@@ -131,24 +618,677 @@ impl Analyzer {
                     }
                 }
             }
-            Statement::Assignment {
+            StatementKind::Assignment {
                 name,
                 value,
-                operator: _,
+                operator,
+            } => {
+                // 1. Analyze the assigned value's type and find the variable.
+                let value_type = self.visit_expression(value);
+                let Some(variable_type) = self.symbols.get(name).cloned() else {
+                    self.errors.push(self.undefined_variable(name));
+                    return;
+                };
+                let Some(binary_operator) = operator.binary_operator() else {
+                    // Plain reassignment keeps the variable's type.
+                    self.check_reassignment(name, &variable_type, &value_type, value.span);
+                    return;
+                };
+
+                // 2. Check that the variable supports the operator. Besides numbers, `+=`
+                //    appends to strings.
+                let appends = matches!(operator, AssignmentOperator::Plus);
+                if !(variable_type.is_numeric() || appends && variable_type == Type::String) {
+                    let error = CompileError::type_mismatch(format!(
+                        "'{}' does not apply to {:?} variable '{}'",
+                        operator.symbol(),
+                        variable_type,
+                        name
+                    ));
+                    self.errors.push(self.with_declaration(error, name));
+                    return;
+                }
+
+                // 3. Check that the variable can hold the value and the result of `x op v`.
+                if !variable_type.accepts(&value_type) {
+                    let error = CompileError::type_mismatch(format!(
+                        "Cannot update {:?} variable '{}' with {:?}",
+                        variable_type, name, value_type
+                    ))
+                    .with_span(value.span);
+                    self.errors.push(self.with_declaration(error, name));
+                    return;
+                }
+                if matches!(operator, AssignmentOperator::Power) && variable_type == Type::Integer {
+                    self.check_integer_power(None, value);
+                }
+                self.binary_type(&variable_type, &binary_operator, &value_type, value.span);
+            }
+            StatementKind::Function {
+                name,
+                parameters,
+                body,
             } => {
-                // 1. Analyze the assigned value's type.
-                self.visit_expression(value);
-                // 2. Check if the assigned variable exists.
-                if !self.symbols.contains_key(name) {
-                    self.errors.push(CompileError::undefined_variable(name));
+                if self.current_function.is_some() {
+                    self.errors.push(CompileError::analysis(format!(
+                        "Function '{}' must be defined at the top level",
+                        name
+                    )));
+                    return;
+                }
+
+                // Parameters start with the types fixed by earlier calls (Unknown otherwise).
+                let parameter_types = self
+                    .functions
+                    .get(name)
+                    .map(|signature| signature.parameters.clone())
+                    .unwrap_or_default();
+
+                // Analyze the body in a fresh scope holding only the parameters.
+                let mut scope = HashMap::new();
+                for (index, parameter) in parameters.iter().enumerate() {
+                    let parameter_type =
+                        parameter_types.get(index).cloned().unwrap_or(Type::Unknown);
+                    scope.insert(parameter.clone(), parameter_type);
+                }
+
+                let outer_symbols = std::mem::replace(&mut self.symbols, scope);
+                let outer_declarations = std::mem::take(&mut self.declarations);
+                let outer_collections = std::mem::take(&mut self.collections);
+                let outer_loop_depth = std::mem::take(&mut self.loop_depth);
+                self.current_function = Some(name.clone());
+                for stmt in body {
+                    self.visit_statement(stmt);
+                }
+                self.current_function = None;
+                self.loop_depth = outer_loop_depth;
+                self.collections = outer_collections;
+                self.symbols = outer_symbols;
+                self.declarations = outer_declarations;
+            }
+            StatementKind::Return { value } => {
+                let Some(function) = self.current_function.clone() else {
+                    self.errors.push(CompileError::analysis(
+                        "Return ('=>') is only allowed inside a function",
+                    ));
+                    if let Some(value) = value {
+                        self.visit_expression(value);
+                    }
+                    return;
+                };
+
+                match value {
+                    Some(value) => {
+                        let value_type = self.visit_expression(value);
+                        if value_type == Type::Void {
+                            self.errors.push(CompileError::type_mismatch(format!(
+                                "Function '{}' cannot return the result of a function without a value",
+                                function
+                            )));
+                        } else if matches!(value_type, Type::Array(_)) {
+                            self.errors.push(CompileError::type_mismatch(format!(
+                                "Function '{}' cannot return an array",
+                                function
+                            )));
+                        } else if let Some(signature) = self.functions.get_mut(&function) {
+                            // The first known return type fixes the function's return type;
+                            // a function returning both integers and floats returns Float.
+                            if signature.return_type == Type::Unknown
+                                || (signature.return_type == Type::Integer
+                                    && value_type == Type::Float)
+                            {
+                                signature.return_type = value_type;
+                            } else if !signature.return_type.accepts(&value_type) {
+                                self.errors.push(CompileError::type_mismatch(format!(
+                                    "Function '{}' returns {:?} but also {:?}",
+                                    function, signature.return_type, value_type
+                                )));
+                            }
+                        }
+                    }
+                    None => {
+                        let returns_value = self
+                            .functions
+                            .get(&function)
+                            .is_some_and(|signature| signature.returns_value);
+                        if returns_value {
+                            self.errors.push(CompileError::analysis(format!(
+                                "Bare '=>' in function '{}', which returns a value elsewhere",
+                                function
+                            )));
+                        }
+                    }
+                }
+            }
+            StatementKind::Call {
+                function,
+                arguments,
+            } => {
+                // The returned value (if any) is discarded.
+                self.visit_call(function, arguments);
+            }
+        }
+    }
+
+    /// Checks the elements of an array or leaf collection and returns their common type.
+    ///
+    /// All elements must share one type; the first known element type wins, except that
+    /// integers and floats mix into a Float array.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The array or collection name, used in error messages.
+    /// * `elements` - The element expressions.
+    fn visit_elements(&mut self, name: &str, elements: &[Expression]) -> Type {
+        let mut element_type = Type::Unknown;
+        for element in elements {
+            let current = self.visit_expression(element);
+            if matches!(current, Type::Array(_) | Type::Void) {
+                self.errors.push(
+                    CompileError::type_mismatch(format!(
+                        "Array '{}' cannot hold {:?} elements",
+                        name, current
+                    ))
+                    .with_span(element.span),
+                );
+            } else if element_type == Type::Unknown {
+                element_type = current;
+            } else if element_type.is_numeric() && current.is_numeric() {
+                // Mixed integer and float elements make a float array.
+                element_type = element_type.promote(&current);
+            } else if !element_type.is_compatible_with(&current) {
+                self.errors.push(
+                    CompileError::type_mismatch(format!(
+                        "Array '{}' mixes {:?} and {:?} elements",
+                        name, element_type, current
+                    ))
+                    .with_span(element.span),
+                );
+            }
+        }
+        element_type
+    }
+
+    /// Checks a collection declaration and registers its leaves as arrays.
+    ///
+    /// # Arguments
+    ///
+    /// * `collection` - The collection being declared.
+    /// * `prefix` - The canonical name of the parent collection (empty for a root).
+    fn visit_collection(&mut self, collection: &Collection, prefix: &str) {
+        let canonical = if prefix.is_empty() {
+            collection.name.clone()
+        } else {
+            format!("{}.{}", prefix, collection.name)
+        };
+
+        // Sibling names and aliases must be unambiguous.
+        let mut seen: Vec<&str> = Vec::new();
+        for child in &collection.children {
+            for segment in std::iter::once(child.name.as_str()).chain(child.alias.as_deref()) {
+                if seen.contains(&segment) {
+                    self.errors.push(
+                        CompileError::analysis(format!(
+                            "'{}' is used by more than one sub-collection of '{}'",
+                            segment, canonical
+                        ))
+                        .with_span(child.span),
+                    );
+                } else {
+                    seen.push(segment);
+                }
+            }
+        }
+
+        if collection.children.is_empty() {
+            let element_type = self.visit_elements(&canonical, &collection.elements);
+            self.declare(&canonical, Type::Array(Box::new(element_type)));
+        } else {
+            for child in &collection.children {
+                self.visit_collection(child, &canonical);
+            }
+        }
+    }
+
+    /// Checks a collection path such as `c:col:0` and returns its type.
+    ///
+    /// # Returns
+    ///
+    /// The element type for an indexed path, or the array type of the whole leaf.
+    fn visit_collection_access(
+        &mut self,
+        root: &str,
+        by_name: bool,
+        path: &[String],
+        index: Option<&Expression>,
+    ) -> Type {
+        let written = std::iter::once(root)
+            .chain(path.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        let Some((canonical, collection)) =
+            resolve_collection_path(&self.collections, root, by_name, path)
+        else {
+            if let Some(index) = index {
+                self.visit_expression(index);
+            }
+            self.errors.push(CompileError::analysis(format!(
+                "Unknown collection path '{}'",
+                written
+            )));
+            return Type::Unknown;
+        };
+
+        if !collection.children.is_empty() {
+            let first_child = &collection.children[0];
+            self.errors.push(CompileError::analysis(format!(
+                "'{}' groups sub-collections; select one (e.g., '{}:{}')",
+                written,
+                written,
+                first_child.alias.as_deref().unwrap_or(&first_child.name)
+            )));
+            return Type::Unknown;
+        }
+
+        match index {
+            Some(index) => self.visit_array_access(&canonical, index),
+            None => self
+                .symbols
+                .get(&canonical)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+        }
+    }
+
+    /// Checks an element access `array{index}` and returns the element type.
+    fn visit_array_access(&mut self, array: &str, index: &Expression) -> Type {
+        // Ensure the index expression is checked.
+        let index_type = self.visit_expression(index);
+        if !Type::Integer.accepts(&index_type) {
+            self.errors.push(
+                CompileError::r#type(format!(
+                    "Array index must be integer, found {:?}",
+                    index_type
+                ))
+                .with_span(index.span),
+            );
+        }
+
+        // Check if the array variable exists and really is an array (or a string, whose
+        // elements are one-character strings).
+        match self.symbols.get(array) {
+            Some(Type::Array(element_type)) => (**element_type).clone(),
+            Some(Type::String) => Type::String,
+            Some(Type::Unknown) => Type::Unknown,
+            Some(other) => {
+                let message = format!("'{}' is {:?}, not an array", array, other);
+                self.errors.push(CompileError::type_mismatch(message));
+                Type::Unknown
+            }
+            None => {
+                self.errors.push(self.undefined_variable(array));
+                Type::Unknown
+            }
+        }
+    }
+
+    /// Determines the type of a binary operation on operands of the given types.
+    ///
+    /// Operators that do not apply to the operands are reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `left_type` - The type of the left operand.
+    /// * `operator` - The binary operator.
+    /// * `right_type` - The type of the right operand.
+    /// * `right_span` - The location of the right operand.
+    fn binary_type(
+        &mut self,
+        left_type: &Type,
+        operator: &BinaryOperator,
+        right_type: &Type,
+        right_span: Span,
+    ) -> Type {
+        match operator {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::Greater
+            | BinaryOperator::LessEqual
+            | BinaryOperator::GreaterEqual => Type::Boolean, // Comparisons yield a boolean
+            // Logical operators take booleans; numbers are not implicitly truthy
+            BinaryOperator::And | BinaryOperator::Or => {
+                for operand_type in [left_type, right_type] {
+                    if !operand_type.is_compatible_with(&Type::Boolean) {
+                        let error = CompileError::type_mismatch(format!(
+                            "{} expects booleans, found {:?}",
+                            operator.symbol(),
+                            operand_type
+                        ));
+                        self.errors.push(if operand_type.is_numeric() {
+                            error.with_help("compare the number explicitly, e.g. `x != 0`")
+                        } else {
+                            error
+                        });
+                        break;
+                    }
+                }
+                Type::Boolean
+            }
+            // Bitwise operators and shifts only apply to integers
+            BinaryOperator::BitAnd
+            | BinaryOperator::BitOr
+            | BinaryOperator::BitXor
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight => {
+                if !Type::Integer.accepts(left_type) || !Type::Integer.accepts(right_type) {
+                    let error = CompileError::type_mismatch(format!(
+                        "Operator '{}' is only defined for integers, found {:?} and {:?}",
+                        operator.symbol(),
+                        left_type,
+                        right_type
+                    ));
+                    let logical = match operator {
+                        BinaryOperator::BitAnd => Some("use `AND` for logical and"),
+                        BinaryOperator::BitOr => Some("use `OR` for logical or"),
+                        _ => None,
+                    };
+                    self.errors.push(match logical {
+                        Some(help) if *left_type == Type::Boolean => error.with_help(help),
+                        _ => error,
+                    });
                 }
-                // A full analyzer would also check if the variable's existing type is compatible with the new value's type.
+                Type::Integer
+            }
+            // Strings are concatenated with `+`; no other arithmetic applies to them.
+            // An operand of unknown type (e.g., a parameter of an uncalled function) may be
+            // a string too.
+            BinaryOperator::Add
+                if *left_type == Type::String
+                    || (*left_type == Type::Unknown && *right_type == Type::String) =>
+            {
+                Type::String
+            }
+            _ if *left_type == Type::String || *right_type == Type::String => {
+                // Mixed operands are already reported as a mismatch by the caller.
+                if left_type.is_compatible_with(right_type) {
+                    self.errors.push(CompileError::type_mismatch(format!(
+                        "Operator '{}' cannot combine {:?} and {:?}",
+                        operator.symbol(),
+                        left_type,
+                        right_type
+                    )));
+                }
+                Type::String
+            }
+            BinaryOperator::Modulo if *left_type == Type::Float || *right_type == Type::Float => {
+                self.errors.push(CompileError::type_mismatch(
+                    "Modulo ('%') is only defined for integers",
+                ));
+                Type::Float
+            }
+            BinaryOperator::Power if *right_type == Type::Float => {
+                self.errors.push(
+                    CompileError::type_mismatch("Exponents ('^') must be integers")
+                        .with_span(right_span),
+                );
+                Type::Float
+            }
+            // Arithmetic operations yield the operand type, promoted to Float if mixed
+            _ => left_type.promote(right_type),
+        }
+    }
+
+    /// Reports integer powers that are known to fail: a literal negative exponent, or
+    /// literal operands whose power does not fit in 64 bits when overflow traps.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The value of the base, if it is a literal.
+    /// * `exponent` - The exponent expression.
+    fn check_integer_power(&mut self, base: Option<i64>, exponent: &Expression) {
+        let Some(exponent_value) = exponent.integer_literal() else {
+            return;
+        };
+        if exponent_value < 0 {
+            self.errors.push(
+                CompileError::analysis(format!(
+                    "Negative exponent {} in an integer power",
+                    exponent_value
+                ))
+                .with_span(exponent.span)
+                .with_help("write the base as a float (e.g., `2.0 ^ -1`) to get a reciprocal"),
+            );
+        } else if let Some(base) = base
+            && self.overflow.power(base, exponent_value).is_none()
+        {
+            self.errors.push(CompileError::analysis(format!(
+                "Integer overflow: {} ^ {} does not fit in 64 bits",
+                base, exponent_value
+            )));
+        }
+    }
+
+    /// Determines the type of a unary operation on an operand of the given type.
+    ///
+    /// Operators that do not apply to the operand are reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `operator` - The unary operator.
+    /// * `operand_type` - The type of the operand.
+    fn unary_type(&mut self, operator: &UnaryOperator, operand_type: &Type) -> Type {
+        match operator {
+            UnaryOperator::Negate => {
+                if !operand_type.is_numeric() {
+                    self.errors.push(CompileError::type_mismatch(format!(
+                        "Negation ('-') expects a number, found {:?}",
+                        operand_type
+                    )));
+                    return Type::Integer;
+                }
+                operand_type.clone()
+            }
+            UnaryOperator::Not => {
+                if !operand_type.is_compatible_with(&Type::Boolean) {
+                    let error = CompileError::type_mismatch(format!(
+                        "NOT expects a boolean, found {:?}",
+                        operand_type
+                    ));
+                    self.errors.push(if *operand_type == Type::Integer {
+                        error.with_help("use `~` for the bitwise complement of an integer")
+                    } else {
+                        error
+                    });
+                }
+                Type::Boolean
+            }
+            UnaryOperator::BitNot => {
+                if !Type::Integer.accepts(operand_type) {
+                    let error = CompileError::type_mismatch(format!(
+                        "Complement ('~') expects an integer, found {:?}",
+                        operand_type
+                    ));
+                    self.errors.push(if *operand_type == Type::Boolean {
+                        error.with_help("use `NOT` to negate a boolean")
+                    } else {
+                        error
+                    });
+                }
+                Type::Integer
+            }
+        }
+    }
+
+    /// Checks a slice of a string variable (e.g., `name{0..2}`) and returns its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `string` - The name of the sliced variable.
+    /// * `start` - The index of the first character.
+    /// * `end` - The index of the last character (inclusive).
+    fn visit_slice(&mut self, string: &str, start: &Expression, end: &Expression) -> Type {
+        for bound in [start, end] {
+            let bound_type = self.visit_expression(bound);
+            if !Type::Integer.accepts(&bound_type) {
+                self.errors.push(
+                    CompileError::r#type(format!(
+                        "Slice bounds must be integers, found {:?}",
+                        bound_type
+                    ))
+                    .with_span(bound.span),
+                );
+            }
+        }
+
+        match self.symbols.get(string) {
+            Some(Type::String | Type::Unknown) => {}
+            Some(other) => {
+                let message = format!("'{}' is {:?}; only strings can be sliced", string, other);
+                self.errors.push(CompileError::type_mismatch(message));
+            }
+            None => self.errors.push(self.undefined_variable(string)),
+        }
+        Type::String
+    }
+
+    /// Checks a function call against the callee's signature and returns the call's type.
+    ///
+    /// Parameter types are inferred from the first call that supplies a known argument
+    /// type; every later call must pass compatible arguments. A parameter that receives
+    /// both integers and floats becomes Float, and integer arguments are converted.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The name of the called function.
+    /// * `arguments` - The argument expressions.
+    ///
+    /// # Returns
+    ///
+    /// The function's return type, or [`Type::Void`] if it never returns a value.
+    fn visit_call(&mut self, function: &str, arguments: &[Expression]) -> Type {
+        let argument_types: Vec<Type> = arguments
+            .iter()
+            .map(|argument| self.visit_expression(argument))
+            .collect();
+
+        if argument_types
+            .iter()
+            .any(|argument_type| matches!(argument_type, Type::Array(_)))
+        {
+            self.errors.push(CompileError::type_mismatch(format!(
+                "Arrays cannot be passed to '{}'; pass individual elements instead",
+                function
+            )));
+        }
+
+        if let Some(builtin) = Builtin::from_name(function) {
+            return self.visit_builtin_call(builtin, arguments, argument_types);
+        }
+
+        let Some(signature) = self.functions.get_mut(function) else {
+            let mut error = CompileError::undefined_function(function);
+            if let Some(similar) =
+                closest_match(function, self.functions.keys().map(String::as_str))
+            {
+                error = error
+                    .with_help(format!(
+                        "a function with a similar name exists: `{}`",
+                        similar
+                    ))
+                    .with_replacement(similar);
+            }
+            self.errors.push(error);
+            return Type::Unknown;
+        };
+
+        if signature.parameters.len() != argument_types.len() {
+            let expected = signature.parameters.len();
+            self.errors.push(
+                CompileError::arity_mismatch(function, expected, argument_types.len())
+                    .with_label(signature.span, format!("'{}' defined here", function)),
+            );
+            return Type::Unknown;
+        }
+
+        for (index, argument_type) in argument_types.into_iter().enumerate() {
+            let parameter_type = &mut signature.parameters[index];
+            if *parameter_type == Type::Unknown
+                || (*parameter_type == Type::Integer && argument_type == Type::Float)
+            {
+                *parameter_type = argument_type;
+            } else if !parameter_type.accepts(&argument_type) {
+                self.errors.push(
+                    CompileError::type_mismatch(format!(
+                        "Argument {} of '{}' expects {:?}, found {:?}",
+                        index + 1,
+                        function,
+                        parameter_type,
+                        argument_type
+                    ))
+                    .with_span(arguments[index].span),
+                );
+            }
+        }
+
+        if signature.returns_value {
+            signature.return_type.clone()
+        } else {
+            Type::Void
+        }
+    }
+
+    /// Checks a call to a built-in function against its fixed signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `builtin` - The called built-in.
+    /// * `arguments` - The argument expressions.
+    /// * `argument_types` - The types of the arguments.
+    ///
+    /// # Returns
+    ///
+    /// The built-in's return type.
+    fn visit_builtin_call(
+        &mut self,
+        builtin: Builtin,
+        arguments: &[Expression],
+        argument_types: Vec<Type>,
+    ) -> Type {
+        let parameters = builtin.parameters();
+        if parameters.len() != argument_types.len() {
+            self.errors.push(CompileError::arity_mismatch(
+                builtin.name(),
+                parameters.len(),
+                argument_types.len(),
+            ));
+            return builtin.return_type();
+        }
+
+        for (index, (parameter_type, argument_type)) in
+            parameters.iter().zip(&argument_types).enumerate()
+        {
+            if !parameter_type.accepts(argument_type) {
+                self.errors.push(
+                    CompileError::type_mismatch(format!(
+                        "Argument {} of '{}' expects {:?}, found {:?}",
+                        index + 1,
+                        builtin.name(),
+                        parameter_type,
+                        argument_type
+                    ))
+                    .with_span(arguments[index].span),
+                );
             }
         }
+        builtin.return_type()
     }
 
     /// Recursively visits an expression, validates compatibility, and returns its resultant type.
     ///
+    /// Errors that are not tied to a more precise location point at the expression.
+    ///
     /// # Arguments
     ///
     /// * `expression` - The expression AST node.
@@ -157,29 +1297,50 @@ impl Analyzer {
     ///
     /// The [`Type`] that the expression evaluates to.
     fn visit_expression(&mut self, expression: &Expression) -> Type {
+        let first_error = self.errors.len();
+        let expression_type = self.visit_expression_kind(&expression.kind);
+        self.attach_span(first_error, expression.span);
+        expression_type
+    }
+
+    /// Performs the checks specific to each kind of expression and returns its type.
+    fn visit_expression_kind(&mut self, expression: &ExpressionKind) -> Type {
         match expression {
-            Expression::Integer(_) => Type::Integer,
-            Expression::String(_) => Type::String,
-            Expression::Boolean(_) => Type::Boolean,
-            Expression::Variable(name) => {
+            ExpressionKind::Integer(_) => Type::Integer,
+            ExpressionKind::Float(_) => Type::Float,
+            ExpressionKind::String(_) => Type::String,
+            ExpressionKind::Boolean(_) => Type::Boolean,
+            ExpressionKind::Variable(name) => {
                 // Look up the variable type in the symbol table.
                 self.symbols.get(name).cloned().unwrap_or_else(|| {
                     // Report an error if the variable is undefined.
-                    self.errors.push(CompileError::undefined_variable(name));
+                    self.errors.push(self.undefined_variable(name));
                     Type::Unknown
                 })
             }
-            Expression::ArrayAccess { array, index } => {
-                // Ensure the index expression is checked.
-                self.visit_expression(index);
-                // Check if the array variable exists.
-                if !self.symbols.contains_key(array) {
-                    self.errors.push(CompileError::undefined_variable(array));
+            ExpressionKind::ArrayAccess { array, index } => self.visit_array_access(array, index),
+            ExpressionKind::Slice { string, start, end } => self.visit_slice(string, start, end),
+            ExpressionKind::CollectionAccess {
+                root,
+                by_name,
+                path,
+                index,
+            } => self.visit_collection_access(root, *by_name, path, index.as_deref()),
+            ExpressionKind::Length(operand) => match self.visit_expression(operand) {
+                Type::Array(_) | Type::String | Type::Unknown => Type::Integer,
+                other => {
+                    self.errors.push(CompileError::type_mismatch(format!(
+                        "Length ('#') expects an array or a string, found {:?}",
+                        other
+                    )));
+                    Type::Integer
                 }
-                // Arrays are assumed to hold Integers for now.
-                Type::Integer
+            },
+            ExpressionKind::Unary { operator, operand } => {
+                let operand_type = self.visit_expression(operand);
+                self.unary_type(operator, &operand_type)
             }
-            Expression::Binary {
+            ExpressionKind::Binary {
                 left,
                 operator,
                 right,
@@ -188,25 +1349,48 @@ impl Analyzer {
                 let left_type = self.visit_expression(left);
                 let right_type = self.visit_expression(right);
 
-                // Check for type compatibility between operands.
-                if !left_type.is_compatible_with(&right_type) {
-                    self.errors.push(CompileError::type_mismatch(&format!(
+                // Check for type compatibility between operands (logical operators check
+                // each operand on its own).
+                let logical = matches!(operator, BinaryOperator::And | BinaryOperator::Or);
+                if !logical && !left_type.is_compatible_with(&right_type) {
+                    let error = CompileError::type_mismatch(format!(
                         "{:?} {:?} {:?}",
                         left_type, operator, right_type
-                    )));
+                    ))
+                    .with_label(left.span, format!("this is {:?}", left_type))
+                    .with_label(right.span, format!("this is {:?}", right_type));
+                    let joins_number = matches!(operator, BinaryOperator::Add)
+                        && matches!(
+                            (&left_type, &right_type),
+                            (Type::String, Type::Integer) | (Type::Integer, Type::String)
+                        );
+                    self.errors.push(if joins_number {
+                        error.with_help("convert the number to a string with `str(...)` first")
+                    } else {
+                        error
+                    });
+                }
+
+                if matches!(operator, BinaryOperator::Power) && left_type == Type::Integer {
+                    self.check_integer_power(left.integer_literal(), right);
                 }
 
                 // Determine the resulting type of the binary operation.
-                match operator {
-                    BinaryOperator::Equal
-                    | BinaryOperator::NotEqual
-                    | BinaryOperator::Less
-                    | BinaryOperator::Greater
-                    | BinaryOperator::LessEqual
-                    | BinaryOperator::GreaterEqual
-                    | BinaryOperator::And
-                    | BinaryOperator::Or => Type::Boolean, // Comparison/Logical operations yield a boolean
-                    _ => left_type, // Arithmetic operations yield the operand type
+                self.binary_type(&left_type, operator, &right_type, right.span)
+            }
+            ExpressionKind::Call {
+                function,
+                arguments,
+            } => {
+                let return_type = self.visit_call(function, arguments);
+                if return_type == Type::Void {
+                    self.errors.push(CompileError::type_mismatch(format!(
+                        "Function '{}' does not return a value",
+                        function
+                    )));
+                    Type::Unknown
+                } else {
+                    return_type
                 }
             }
         }
@@ -219,7 +1403,387 @@ impl Default for Analyzer {
     }
 }
 
-/// Convenience function to call the analyzer.
-pub fn analyze(ast: &Program) -> Result<(), CompileError> {
-    Analyzer::analyze(ast)
+/// Convenience function to call the analyzer, with integer overflow trapping.
+pub fn analyze(ast: &Program) -> Result<ProgramTypes, CompileError> {
+    Analyzer::analyze(ast, OverflowMode::Trap)
+}
+
+/// Convenience function to call the analyzer for a given overflow mode.
+///
+/// # Arguments
+///
+/// * `ast` - The root [`Program`] AST node.
+/// * `overflow` - What integer overflow does at runtime.
+pub fn analyze_with_overflow(
+    ast: &Program,
+    overflow: OverflowMode,
+) -> Result<ProgramTypes, CompileError> {
+    Analyzer::analyze(ast, overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::lexer::tokenize;
+    use crate::compiler::parser::parse;
+    use crate::compiler::span::SourceMap;
+
+    /// Parses `source` into a program; the source must be syntactically valid.
+    fn program(source: &str) -> Program {
+        parse(tokenize(source).expect("source should tokenize")).expect("source should parse")
+    }
+
+    /// Analyzes `source` and returns the messages of every error found.
+    fn errors(source: &str) -> Vec<String> {
+        match analyze(&program(source)) {
+            Ok(_) => Vec::new(),
+            Err(error) => error
+                .into_errors()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
+    /// Asserts that analyzing `source` reports exactly one error containing `expected`.
+    fn assert_error(source: &str, expected: &str) {
+        let errors = errors(source);
+        assert_eq!(errors.len(), 1, "expected one error, found {:?}", errors);
+        assert!(
+            errors[0].contains(expected),
+            "expected an error containing {:?}, found {:?}",
+            expected,
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn recursive_function_is_valid() {
+        let source =
+            "# fact(n)\n    ? (n <= 1)\n        => 1\n    => n * fact(n - 1)\n\nx fact(5)\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+        let types = analyze(&program(source)).unwrap();
+        assert_eq!(types.functions["fact"].parameters, [Type::Integer]);
+        assert_eq!(types.functions["fact"].return_type, Type::Integer);
+    }
+
+    #[test]
+    fn call_checks() {
+        assert_error(
+            "# add(a, b)\n    => a + b\n\nx add(1)\n",
+            "Function 'add' expects 2 argument(s), found 1",
+        );
+        assert_error("x nothing(1)\n", "Undefined function: nothing");
+        assert_error("=> 1\n", "only allowed inside a function");
+    }
+
+    #[test]
+    fn array_checks() {
+        assert_eq!(errors("a [1, 2]\nx a{1}\ny #a\n"), Vec::<String>::new());
+        assert_error(
+            "a [1, 2]\nx a{TRUE}\n",
+            "Array index must be integer, found Boolean",
+        );
+        assert_error("n 5\nx n{0}\n", "'n' is Integer, not an array");
+    }
+
+    #[test]
+    fn collection_paths_resolve_aliases_and_names() {
+        const CARS: &str = "[] cars as c\n    [] brands as b\n        \"Toyota\", \"Honda\"\n    [] prices as p\n        21000, 19500\n";
+        let source =
+            format!("{CARS}first c:b:0\nall c:b\ncount #c:p\n! \"{{[]cars:p:1}} {{all}}\"\n");
+        assert_eq!(errors(&source), Vec::<String>::new());
+        let types = analyze(&program(&source)).unwrap();
+        assert_eq!(types.variables["first"], Type::String);
+        assert_eq!(types.variables["all"], Type::Array(Box::new(Type::String)));
+        assert_eq!(types.variables["count"], Type::Integer);
+        assert_error(
+            &format!("{source}x all{{TRUE}}\n"),
+            "Array index must be integer",
+        );
+
+        assert_error(&format!("{CARS}x c:z\n"), "Unknown collection path 'c:z'");
+        assert_error(
+            &format!("{CARS}! \"{{[]cars}}\"\n"),
+            "'cars' groups sub-collections; select one (e.g., 'cars:b')",
+        );
+        assert_error(
+            "[] cars as c\n    [] brands as b\n        1\n    [] bikes as b\n        2\n",
+            "'b' is used by more than one sub-collection of 'cars'",
+        );
+    }
+
+    #[test]
+    fn unknown_collection_roots_and_segments() {
+        const CARS: &str = "[] cars as c\n    [] brands as b\n        \"Toyota\"\n";
+        assert_error(&format!("{CARS}x q:b:0\n"), "Unknown collection path 'q:b'");
+        assert_error(
+            &format!("{CARS}! \"{{[]c:b}}\"\n"),
+            "Unknown collection path 'c:b'",
+        );
+        assert_error(
+            &format!("{CARS}x c:b:z\n"),
+            "Unknown collection path 'c:b:z'",
+        );
+    }
+
+    #[test]
+    fn copying_a_collection_into_a_variable() {
+        let source = "[] cars as c\n    [] brands as b\n        \"Toyota\", \"Honda\"\nall c:b\nx all{1}\n@ brand in all\n    ! \"{brand}\"\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+        assert_error(&format!("{source}n all + 1\n"), "Type mismatch");
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_expression() {
+        let source = "x 1\ny x + missing\n";
+        let error = analyze(&program(source)).unwrap_err();
+        let span = error.span().expect("the error should have a span");
+        assert_eq!(&source[span.start..span.end], "missing");
+        assert_eq!(
+            SourceMap::new(source).location(span.start).to_string(),
+            "2:7"
+        );
+    }
+
+    #[test]
+    fn every_error_is_reported_in_one_pass() {
+        assert_eq!(
+            errors("x a\ny TRUE + 1\nz b\n"),
+            [
+                "Undefined variable: a",
+                "Type mismatch: Boolean Add Integer",
+                "Undefined variable: b"
+            ]
+        );
+    }
+
+    #[test]
+    fn integers_promote_to_floats() {
+        let source = "# area(r)\n    => r * r * 3.5\n\nx 2\nf x + 0.5\na area(2)\nb area(1.5)\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+        let types = analyze(&program(source)).unwrap();
+        assert_eq!(types.variables["f"], Type::Float);
+        assert_eq!(types.variables["a"], Type::Float);
+        assert_eq!(types.functions["area"].parameters, [Type::Float]);
+        assert_eq!(types.functions["area"].return_type, Type::Float);
+    }
+
+    #[test]
+    fn precision_is_only_for_numbers() {
+        assert_eq!(
+            errors("r 0.25\n! \"{r:.2} {3:.0}\"\n"),
+            Vec::<String>::new()
+        );
+        assert_error(
+            "s \"a\"\n! \"{s:.2}\"\n",
+            "Only numbers can be printed with a precision, found String",
+        );
+        assert_error("r 0.25\n! \"{r:.40}\"\n", "Precision '.40' is too large");
+    }
+
+    #[test]
+    fn variable_types_are_inferred_from_their_values() {
+        let types = analyze(&program("s \"hi\"\nb 3 > 2\nt FALSE\na [1]\nn #a\n")).unwrap();
+        assert_eq!(types.variables["s"], Type::String);
+        assert_eq!(types.variables["b"], Type::Boolean);
+        assert_eq!(types.variables["t"], Type::Boolean);
+        assert_eq!(types.variables["n"], Type::Integer);
+    }
+
+    #[test]
+    fn reassignment_keeps_the_declared_type() {
+        assert_eq!(
+            errors("x 5\nx 6\nf 1.5\nf 3\nf += 1\n"),
+            Vec::<String>::new()
+        );
+        assert_error(
+            "x 5\nx 2.5\n",
+            "Cannot assign Float to 'x', which holds Integer",
+        );
+        assert_error(
+            "s \"a\"\ns *= 2\n",
+            "'*=' does not apply to String variable 's'",
+        );
+        assert_error(
+            "x 5\nx += \"a\"\n",
+            "Cannot update Integer variable 'x' with String",
+        );
+
+        // The error points at the new value and labels the original declaration.
+        let source = "x 5\nx \"hi\"\n";
+        let error = analyze(&program(source)).unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!(&source[span.start..span.end], "\"hi\"");
+        let CompileError::Annotated { labels, .. } = &error else {
+            panic!("expected a labelled error, found {:?}", error);
+        };
+        assert_eq!(&source[labels[0].span.start..labels[0].span.end], "x 5");
+        assert_eq!(labels[0].message, "'x' declared as Integer here");
+    }
+
+    #[test]
+    fn range_loop_counters_keep_the_type_of_an_existing_variable() {
+        assert_eq!(
+            errors("i 10\n@ i, 1..3\n    ! \"{i}\"\n"),
+            Vec::<String>::new()
+        );
+        assert_error(
+            "s \"a\"\n@ s, 1..2\n    ! \"{s}\"\n",
+            "Loop counter 's' would change String variable 's' to Integer",
+        );
+        assert_error(
+            "f 1.5\n@ f, 1..2\n    ! \"{f}\"\n",
+            "Loop counter 'f' would change Float variable 'f' to Integer",
+        );
+    }
+
+    #[test]
+    fn loop_variables_leave_outer_variables_in_scope() {
+        assert_eq!(
+            errors("i 10\n@ i, 1..3\n    ! \"{i}\"\ni 4\n! \"{i}\"\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors(
+                "arr [1, 2]\ntotal 0\n@ total in arr\n    ! \"{total}\"\ntotal 5\n! \"{total}\"\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn string_operations() {
+        let source = "a \"ab\"\nb a + \"c\"\nl a < b\nn #b\nt b{1}\nu b{0..1}\nv str(42)\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+        let types = analyze(&program(source)).unwrap();
+        for (name, expected) in [
+            ("b", Type::String),
+            ("l", Type::Boolean),
+            ("n", Type::Integer),
+            ("t", Type::String),
+            ("u", Type::String),
+            ("v", Type::String),
+        ] {
+            assert_eq!(types.variables[name], expected, "type of {}", name);
+        }
+
+        assert_error(
+            "a \"x\" - \"y\"\n",
+            "Operator '-' cannot combine String and String",
+        );
+        // A mix of a string and a number is reported once, with a conversion hint.
+        assert_error("a 5 + \"x\"\n", "Integer Add String");
+        assert_error("b \"x\" * 2\n", "String Multiply Integer");
+        assert_error("a \"x\" < 1\n", "String Less Integer");
+        assert_error(
+            "n 5\nb n{0..1}\n",
+            "'n' is Integer; only strings can be sliced",
+        );
+    }
+
+    #[test]
+    fn uncalled_functions_concatenate_string_parameters() {
+        // Without a call, the parameter's type stays unknown.
+        assert_eq!(
+            errors("# greet(s)\n    => s + \"!\"\n"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors("# greet(s)\n    => \"hi \" + s\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn break_and_continue_need_a_loop() {
+        assert_eq!(
+            errors("i 0\n@ (i < 3)\n    i += 1\n    ? (i == 2)\n        continue\n    break\n"),
+            Vec::<String>::new()
+        );
+        assert_error("break\n", "'break' is only allowed inside a loop");
+        assert_error(
+            "# f()\n    continue\n",
+            "'continue' is only allowed inside a loop",
+        );
+        assert_error("@ (1)\n    x 1\n", "Loop condition must be boolean");
+    }
+
+    #[test]
+    fn loop_steps_are_nonzero_integers() {
+        assert_eq!(
+            errors("n 3\n@ i, 10..<0 step -2\n    x i\n@ k, 0..n step n - 3\n    y k\n"),
+            Vec::<String>::new()
+        );
+        assert_error("@ i, 0..3 step 0\n    x 1\n", "Loop step cannot be zero");
+        assert_error("@ i, 0..3 step 0.5\n    x 1\n", "Loop step must be integer");
+    }
+
+    #[test]
+    fn literal_powers_are_checked_by_the_overflow_mode() {
+        assert_error(
+            "x 2 ^ 63\n",
+            "Integer overflow: 2 ^ 63 does not fit in 64 bits",
+        );
+        for mode in [OverflowMode::Wrap, OverflowMode::Saturate] {
+            assert!(analyze_with_overflow(&program("x 2 ^ 63\n"), mode).is_ok());
+        }
+
+        // Negative integer exponents are an error in every mode; floats take the reciprocal.
+        assert_error(
+            "y 2\nx y ^ -1\n",
+            "Negative exponent -1 in an integer power",
+        );
+        assert!(analyze_with_overflow(&program("x 2 ^ -1\n"), OverflowMode::Wrap).is_err());
+        assert_eq!(errors("x 2.0 ^ -1\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn logical_operators_need_booleans() {
+        assert_eq!(
+            errors("x 0\nok x != 0 AND 10 / x > 1\n"),
+            Vec::<String>::new()
+        );
+        assert_error("a 1 AND TRUE\n", "AND expects booleans, found Integer");
+        assert_error("a TRUE OR \"x\"\n", "OR expects booleans, found String");
+        assert_error("? (1)\n    x 1\n", "If condition must be boolean");
+    }
+
+    #[test]
+    fn input_builtins_are_typed() {
+        let types = analyze(&program("n input_int(-1)\nl input()\ne eof()\n")).unwrap();
+        assert_eq!(types.variables["n"], Type::Integer);
+        assert_eq!(types.variables["l"], Type::String);
+        assert_eq!(types.variables["e"], Type::Boolean);
+
+        assert_error(
+            "x input_int(\"a\")\n",
+            "Argument 1 of 'input_int' expects Integer, found String",
+        );
+        assert_error(
+            "x input(1)\n",
+            "Function 'input' expects 0 argument(s), found 1",
+        );
+        assert_error(
+            "# input()\n    => 1\n",
+            "'input' is a built-in function and cannot be redefined",
+        );
+    }
+
+    #[test]
+    fn process_builtins_are_typed() {
+        let types = analyze(&program("c argc()\na arg(c - 1)\nh env(\"HOME\")\n")).unwrap();
+        assert_eq!(types.variables["c"], Type::Integer);
+        assert_eq!(types.variables["a"], Type::String);
+        assert_eq!(types.variables["h"], Type::String);
+
+        assert_error(
+            "a arg(\"x\")\n",
+            "Argument 1 of 'arg' expects Integer, found String",
+        );
+        assert_error(
+            "h env(1)\n",
+            "Argument 1 of 'env' expects String, found Integer",
+        );
+    }
 }
@@ -0,0 +1,134 @@
+//! # Built-in Functions
+//!
+//! This module lists the functions provided by the Nebulang runtime itself (e.g., `str(42)`).
+//! Built-ins are called like user-defined functions, but their names are reserved and their
+//! code is an assembly routine of the runtime rather than a compiled function body.
+
+use crate::ast::types::Type;
+
+/// A function provided by the runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// `str(n)`: converts an integer into its decimal string.
+    Str,
+    /// `input()`: reads a line from stdin, without its line terminator.
+    Input,
+    /// `input_int(fallback)`: reads a line from stdin as a decimal integer, or returns
+    /// `fallback` if the line is not one or the input has ended.
+    InputInt,
+    /// `eof()`: whether the last `input()` or `input_int(...)` found the input ended.
+    Eof,
+    /// `argc()`: the number of command-line arguments, including the program path.
+    Argc,
+    /// `arg(n)`: the command-line argument at index `n` (`arg(0)` is the program path).
+    Arg,
+    /// `env(name)`: the value of an environment variable, or `""` if it is not set.
+    Env,
+}
+
+impl Builtin {
+    /// Every built-in function.
+    pub const ALL: [Builtin; 7] = [
+        Builtin::Str,
+        Builtin::Input,
+        Builtin::InputInt,
+        Builtin::Eof,
+        Builtin::Argc,
+        Builtin::Arg,
+        Builtin::Env,
+    ];
+
+    /// Looks up a built-in function by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The called name.
+    ///
+    /// # Returns
+    ///
+    /// The matching [`Builtin`], or `None` for any other name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|builtin| builtin.name() == name)
+    }
+
+    /// The name under which the built-in is called.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Str => "str",
+            Self::Input => "input",
+            Self::InputInt => "input_int",
+            Self::Eof => "eof",
+            Self::Argc => "argc",
+            Self::Arg => "arg",
+            Self::Env => "env",
+        }
+    }
+
+    /// The parameter types, in order.
+    pub fn parameters(self) -> Vec<Type> {
+        match self {
+            Self::Str | Self::InputInt | Self::Arg => vec![Type::Integer],
+            Self::Env => vec![Type::String],
+            Self::Input | Self::Eof | Self::Argc => Vec::new(),
+        }
+    }
+
+    /// The type of the returned value.
+    pub fn return_type(self) -> Type {
+        match self {
+            Self::Str | Self::Input | Self::Arg | Self::Env => Type::String,
+            Self::InputInt | Self::Argc => Type::Integer,
+            Self::Eof => Type::Boolean,
+        }
+    }
+
+    /// The runtime routine implementing the built-in.
+    ///
+    /// Routines take their arguments in the System V argument registers (`rdi`, `rsi`, ...)
+    /// and return their result in RAX.
+    pub fn routine(self) -> &'static str {
+        match self {
+            Self::Str => "_nebula_int_to_str",
+            Self::Input => "_nebula_read_line",
+            Self::InputInt => "_nebula_read_int",
+            Self::Eof => "_nebula_stdin_eof",
+            Self::Argc => "_nebula_argc",
+            Self::Arg => "_nebula_arg",
+            Self::Env => "_nebula_env",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for builtin in Builtin::ALL {
+            assert_eq!(Builtin::from_name(builtin.name()), Some(builtin));
+            assert!(builtin.routine().starts_with("_nebula_"));
+        }
+        assert_eq!(Builtin::from_name("print"), None);
+        assert_eq!(Builtin::from_name("Input"), None);
+    }
+
+    #[test]
+    fn input_signatures() {
+        assert_eq!(Builtin::Input.parameters(), []);
+        assert_eq!(Builtin::Input.return_type(), Type::String);
+        assert_eq!(Builtin::InputInt.parameters(), [Type::Integer]);
+        assert_eq!(Builtin::InputInt.return_type(), Type::Integer);
+        assert_eq!(Builtin::Eof.return_type(), Type::Boolean);
+    }
+
+    #[test]
+    fn process_signatures() {
+        assert_eq!(Builtin::Argc.parameters(), []);
+        assert_eq!(Builtin::Argc.return_type(), Type::Integer);
+        assert_eq!(Builtin::Arg.parameters(), [Type::Integer]);
+        assert_eq!(Builtin::Arg.return_type(), Type::String);
+        assert_eq!(Builtin::Env.parameters(), [Type::String]);
+        assert_eq!(Builtin::Env.return_type(), Type::String);
+    }
+}
@@ -0,0 +1,144 @@
+//! # Project Configuration (`.nebfmt`)
+//!
+//! This module reads the optional `.nebfmt` file that fixes formatting settings for
+//! the Nebulang sources below it. The file is looked up in the source file's directory
+//! and then in each parent directory; the closest one wins.
+//!
+//! The format is one `key = value` setting per line, with `#` starting a comment:
+//!
+//! ```text
+//! # Indent blocks with two spaces.
+//! indent_width = 2
+//! ```
+
+use super::error::CompileError;
+use super::lexer::LexerOptions;
+use std::path::{Path, PathBuf};
+
+/// The settings read from a `.nebfmt` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// The number of spaces per indentation level (`indent_width`).
+    pub indent_width: Option<usize>,
+}
+
+impl FormatConfig {
+    /// The name of the configuration file.
+    pub const FILE_NAME: &'static str = ".nebfmt";
+
+    /// Finds and reads the `.nebfmt` that applies to a source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_path` - The Nebulang source file being compiled.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or the defaults if no `.nebfmt` file exists.
+    pub fn discover(source_path: &Path) -> Result<Self, CompileError> {
+        match Self::find(source_path) {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)?;
+                Self::parse(&text).map_err(|message| CompileError::InvalidConfig {
+                    path: path.display().to_string(),
+                    message,
+                })
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parses the contents of a `.nebfmt` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The file contents.
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or a message describing the first invalid line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`", index + 1));
+            };
+
+            match key.trim() {
+                "indent_width" => match value.trim().parse() {
+                    Ok(width @ 1..=16) => config.indent_width = Some(width),
+                    _ => {
+                        return Err(format!(
+                            "line {}: `indent_width` must be a number from 1 to 16",
+                            index + 1
+                        ));
+                    }
+                },
+                other => return Err(format!("line {}: unknown setting `{}`", index + 1, other)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the lexer settings derived from this configuration.
+    pub fn lexer_options(&self) -> LexerOptions {
+        LexerOptions {
+            indent_width: self.indent_width,
+        }
+    }
+
+    /// Looks for a `.nebfmt` file next to the source file or in any parent directory.
+    fn find(source_path: &Path) -> Option<PathBuf> {
+        let absolute = std::path::absolute(source_path).ok()?;
+        absolute
+            .ancestors()
+            .skip(1)
+            .map(|directory| directory.join(Self::FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_and_comments() {
+        let config = FormatConfig::parse("# Two spaces.\n\nindent_width = 2 # per level\n");
+        assert_eq!(
+            config,
+            Ok(FormatConfig {
+                indent_width: Some(2)
+            })
+        );
+        assert_eq!(config.unwrap().lexer_options().indent_width, Some(2));
+        assert_eq!(FormatConfig::parse(""), Ok(FormatConfig::default()));
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert_eq!(
+            FormatConfig::parse("indent_width 2"),
+            Err("line 1: expected `key = value`".to_string())
+        );
+        assert_eq!(
+            FormatConfig::parse("\nindent_width = 0"),
+            Err("line 2: `indent_width` must be a number from 1 to 16".to_string())
+        );
+        assert_eq!(
+            FormatConfig::parse("indent_width = two"),
+            Err("line 1: `indent_width` must be a number from 1 to 16".to_string())
+        );
+        assert_eq!(
+            FormatConfig::parse("tab_width = 4"),
+            Err("line 1: unknown setting `tab_width`".to_string())
+        );
+    }
+}
@@ -0,0 +1,762 @@
+//! # Diagnostics
+//!
+//! This module turns a [`CompileError`] into a [`Diagnostic`] and renders it in the
+//! style of `rustc`: a headline with the error code, the `file:line:column` location, the offending source
+//! line with the problem underlined, any secondary labels, and an optional `help:` line.
+//!
+//! ```text
+//! error[NEB0003]: undefined variable `totl`
+//!  --> shop.neb:3:10
+//!   |
+//! 3 | ! "Sum: {totl}"
+//!   |          ^^^^ not found in this scope
+//!   |
+//!   = help: a variable with a similar name exists: `total`
+//! ```
+
+use super::error::CompileError;
+use super::span::{SourceMap, Span};
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+/// A source span together with a short explanation shown next to its underline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The underlined source span.
+    pub span: Span,
+    /// The explanation (may be empty).
+    pub message: String,
+}
+
+impl Label {
+    /// Creates a label for the given span.
+    ///
+    /// # Arguments
+    ///
+    /// * `span` - The underlined source span.
+    /// * `message` - The explanation shown after the underline.
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The program is rejected.
+    Error,
+    /// The program compiles, but probably does not do what was intended.
+    Warning,
+}
+
+impl Severity {
+    /// The name shown in front of the headline (e.g., `error[NEB0003]`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// A user-facing description of a compile error or warning, ready to be rendered.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Whether the diagnostic is an error or a warning.
+    pub severity: Severity,
+    /// The stable error code (e.g., `"NEB0003"`), if the error has one.
+    pub code: Option<&'static str>,
+    /// The headline (e.g., "undefined variable `y`").
+    pub message: String,
+    /// The main location of the problem, underlined with `^`.
+    pub primary: Option<Label>,
+    /// Related locations, underlined with `-`.
+    pub secondary: Vec<Label>,
+    /// An optional suggestion for fixing the problem.
+    pub help: Option<String>,
+    /// An optional machine-applicable fix (e.g., the correctly spelled name).
+    pub suggestion: Option<Suggestion>,
+}
+
+/// A replacement for a span of source text that fixes a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The source span to replace.
+    pub span: Span,
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    /// Builds the diagnostic for a compile error.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error to describe, including any labels and help it carries.
+    pub fn from_error(error: &CompileError) -> Self {
+        let (secondary, help, replacement) = match error {
+            CompileError::Annotated {
+                labels,
+                help,
+                replacement,
+                ..
+            } => (labels.clone(), help.clone(), replacement.clone()),
+            _ => (Vec::new(), None, None),
+        };
+
+        let inner = error.inner();
+        let (message, label) = match inner {
+            CompileError::SyntaxError { message, .. } | CompileError::TypeError { message, .. } => {
+                (message.clone(), String::new())
+            }
+            CompileError::IndentationError { message, .. } => (message.clone(), String::new()),
+            CompileError::UndefinedVariable { name, .. } => (
+                format!("undefined variable `{}`", name),
+                "not found in this scope".to_string(),
+            ),
+            CompileError::UndefinedFunction { name, .. } => (
+                format!("undefined function `{}`", name),
+                "no function with this name".to_string(),
+            ),
+            CompileError::TypeMismatch { details, .. } => {
+                (format!("type mismatch: {}", details), String::new())
+            }
+            CompileError::UnexpectedCharacter { character, .. } => (
+                format!(
+                    "unexpected character `{}` (U+{:04X})",
+                    character, *character as u32
+                ),
+                "not valid here".to_string(),
+            ),
+            CompileError::ArityMismatch { expected, .. } => (
+                inner.to_string(),
+                format!("expected {} argument(s)", expected),
+            ),
+            other => (other.to_string(), String::new()),
+        };
+
+        // Undefined names are reported at a span that starts with the name itself.
+        let suggestion = match (inner, inner.span(), replacement) {
+            (
+                CompileError::UndefinedVariable { name, .. }
+                | CompileError::UndefinedFunction { name, .. },
+                Some(span),
+                Some(replacement),
+            ) => Some(Suggestion {
+                span: Span::new(span.start, span.start + name.len()),
+                replacement,
+            }),
+            _ => None,
+        };
+
+        Self {
+            severity: Severity::Error,
+            code: inner.code().map(|entry| entry.code),
+            message,
+            primary: inner.span().map(|span| Label::new(span, label)),
+            secondary,
+            help,
+            suggestion,
+        }
+    }
+}
+
+/// Finds the candidate closest to a misspelled name, for "did you mean" suggestions.
+///
+/// # Arguments
+///
+/// * `name` - The unknown name as written.
+/// * `candidates` - The names that are defined at that point.
+///
+/// # Returns
+///
+/// The candidate with the smallest edit distance, if it is close enough to be a likely typo.
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Computes the Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Controls whether diagnostics are colored (`--color`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color only when stderr is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolves the choice to a yes/no decision for stderr.
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => Err(format!("invalid color choice '{}'", other)),
+        }
+    }
+}
+
+/// Selects how diagnostics are printed (`--message-format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Rendered, human-readable text on stderr.
+    #[default]
+    Human,
+    /// One JSON object per line on stdout, for editors and CI tools.
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            other => Err(format!("invalid message format '{}'", other)),
+        }
+    }
+}
+
+// ANSI styles used by the renderer.
+const STYLE_ERROR: &str = "1;31";
+const STYLE_WARNING: &str = "1;33";
+const STYLE_SECONDARY: &str = "1;34";
+const STYLE_BOLD: &str = "1";
+
+/// Renders diagnostics for one source file.
+pub struct Renderer<'a> {
+    /// The file name shown in the `-->` line.
+    file: &'a str,
+    /// The line index of the file.
+    source_map: &'a SourceMap,
+    /// Whether to emit ANSI colors.
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a renderer for a source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file name, as shown to the user.
+    /// * `source_map` - The line index of the file.
+    /// * `color` - Whether to emit ANSI colors.
+    pub fn new(file: &'a str, source_map: &'a SourceMap, color: bool) -> Self {
+        Self {
+            file,
+            source_map,
+            color,
+        }
+    }
+
+    /// Renders a diagnostic as multi-line text, ending with a newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostic` - The diagnostic to render.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity = diagnostic.severity.name();
+        let level = match diagnostic.code {
+            Some(code) => format!("{}[{}]", severity, code),
+            None => severity.to_string(),
+        };
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(&level, Self::primary_style(diagnostic.severity)),
+            self.paint(&format!(": {}", diagnostic.message), STYLE_BOLD)
+        );
+
+        // Primary label first, so that it wins when labels share a line.
+        let labels: Vec<(&Label, bool)> = diagnostic
+            .primary
+            .iter()
+            .map(|label| (label, true))
+            .chain(diagnostic.secondary.iter().map(|label| (label, false)))
+            .collect();
+
+        let Some(&(first, _)) = labels.first() else {
+            out.push_str(&format!(
+                "{} {}\n",
+                self.paint("-->", STYLE_SECONDARY),
+                self.file
+            ));
+            self.render_help(&mut out, diagnostic, 0);
+            return out;
+        };
+
+        let mut lines: Vec<usize> = labels
+            .iter()
+            .map(|(label, _)| self.source_map.location(label.span.start).line)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        let width = lines.last().map_or(1, |line| line.to_string().len());
+        let gutter = " ".repeat(width);
+        let bar = self.paint("|", STYLE_SECONDARY);
+
+        out.push_str(&format!(
+            "{}{} {}:{}\n",
+            gutter,
+            self.paint("-->", STYLE_SECONDARY),
+            self.file,
+            self.source_map.location(first.span.start)
+        ));
+        out.push_str(&format!("{} {}\n", gutter, bar));
+
+        let mut previous_line = None;
+        for line in lines {
+            if previous_line.is_some_and(|previous| line > previous + 1) {
+                out.push_str(&format!("{}\n", self.paint("...", STYLE_SECONDARY)));
+            }
+            previous_line = Some(line);
+
+            let text = self.source_map.line_text(line);
+            out.push_str(&format!(
+                "{} {} {}\n",
+                self.paint(&format!("{:>width$}", line, width = width), STYLE_SECONDARY),
+                bar,
+                text
+            ));
+
+            for (label, is_primary) in &labels {
+                if self.source_map.location(label.span.start).line != line {
+                    continue;
+                }
+                out.push_str(&format!(
+                    "{} {} {}\n",
+                    gutter,
+                    bar,
+                    self.underline(text, label, is_primary.then_some(diagnostic.severity))
+                ));
+            }
+        }
+
+        self.render_help(&mut out, diagnostic, width);
+        out
+    }
+
+    /// Builds the underline row for a label on a source line.
+    ///
+    /// Tabs in front of the label are copied so that the underline stays aligned. Primary
+    /// labels pass the diagnostic's severity, which selects their color.
+    fn underline(&self, text: &str, label: &Label, primary: Option<Severity>) -> String {
+        let start = self.source_map.location(label.span.start);
+        let end = self.source_map.location(label.span.end);
+
+        let padding: String = text
+            .chars()
+            .take(start.column - 1)
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        // Spans that continue onto later lines are underlined to the end of the first line.
+        let line_length = text.chars().count();
+        let end_column = if end.line == start.line {
+            end.column
+        } else {
+            line_length + 1
+        };
+        let length = end_column.saturating_sub(start.column).max(1);
+
+        let (marker, style) = match primary {
+            Some(severity) => ('^', Self::primary_style(severity)),
+            None => ('-', STYLE_SECONDARY),
+        };
+        let mut mark = marker.to_string().repeat(length);
+        if !label.message.is_empty() {
+            mark.push(' ');
+            mark.push_str(&label.message);
+        }
+
+        format!("{}{}", padding, self.paint(&mark, style))
+    }
+
+    /// Appends the `= help:` line, if the diagnostic has one.
+    fn render_help(&self, out: &mut String, diagnostic: &Diagnostic, width: usize) {
+        if let Some(help) = &diagnostic.help {
+            let gutter = " ".repeat(width);
+            out.push_str(&format!(
+                "{} {}\n",
+                gutter,
+                self.paint("|", STYLE_SECONDARY)
+            ));
+            out.push_str(&format!(
+                "{} {} {}: {}\n",
+                gutter,
+                self.paint("=", STYLE_SECONDARY),
+                self.paint("help", STYLE_BOLD),
+                help
+            ));
+        }
+    }
+
+    /// The style of the headline and the primary underline.
+    fn primary_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => STYLE_ERROR,
+            Severity::Warning => STYLE_WARNING,
+        }
+    }
+
+    /// Wraps text in an ANSI style when color is enabled.
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// A diagnostic as printed by `--message-format=json`.
+#[derive(Debug, Serialize)]
+struct JsonDiagnostic<'a> {
+    /// Always `"diagnostic"`; distinguishes the object from the build summary.
+    r#type: &'static str,
+    code: Option<&'static str>,
+    severity: &'static str,
+    message: &'a str,
+    file: &'a str,
+    /// The primary span first, followed by the secondary ones.
+    spans: Vec<JsonSpan<'a>>,
+    /// Extra explanations, such as the `help:` line of the rendered diagnostic.
+    notes: Vec<String>,
+    suggestion: Option<JsonSuggestion<'a>>,
+}
+
+/// A source span with both byte offsets and 1-based line/column positions.
+#[derive(Debug, Serialize)]
+struct JsonSpan<'a> {
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    column_start: usize,
+    line_end: usize,
+    column_end: usize,
+    is_primary: bool,
+    label: Option<&'a str>,
+}
+
+/// A machine-applicable replacement.
+#[derive(Debug, Serialize)]
+struct JsonSuggestion<'a> {
+    span: JsonSpan<'a>,
+    replacement: &'a str,
+}
+
+/// The final object printed by `--message-format=json` once a build ends.
+#[derive(Debug, Serialize)]
+pub struct BuildSummary<'a> {
+    /// Always `"build-finished"`.
+    r#type: &'static str,
+    file: &'a str,
+    success: bool,
+    error_count: usize,
+    /// The failure, for builds that did not fail because of source diagnostics.
+    message: Option<String>,
+}
+
+impl<'a> BuildSummary<'a> {
+    /// Creates the summary of a finished build.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The source file that was built.
+    /// * `result` - The outcome of the build.
+    pub fn new(file: &'a str, result: &Result<(), CompileError>) -> Self {
+        let (error_count, message) = match result {
+            Ok(()) => (0, None),
+            Err(CompileError::Reported { count, .. }) => (*count, None),
+            Err(error) => (1, Some(error.to_string())),
+        };
+
+        Self {
+            r#type: "build-finished",
+            file,
+            success: result.is_ok(),
+            error_count,
+            message,
+        }
+    }
+
+    /// Serializes the summary as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("build summaries always serialize")
+    }
+}
+
+/// Serializes diagnostics for one source file as JSON lines.
+pub struct JsonEmitter<'a> {
+    /// The file name stored in every diagnostic.
+    file: &'a str,
+    /// The line index used to compute line/column positions.
+    source_map: &'a SourceMap,
+}
+
+impl<'a> JsonEmitter<'a> {
+    /// Creates an emitter for a source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file name, as shown to the user.
+    /// * `source_map` - The line index of the file.
+    pub fn new(file: &'a str, source_map: &'a SourceMap) -> Self {
+        Self { file, source_map }
+    }
+
+    /// Serializes a diagnostic as a single line of JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostic` - The diagnostic to serialize.
+    pub fn emit(&self, diagnostic: &Diagnostic) -> String {
+        let spans = diagnostic
+            .primary
+            .iter()
+            .map(|label| self.span(label.span, true, Some(&label.message)))
+            .chain(
+                diagnostic
+                    .secondary
+                    .iter()
+                    .map(|label| self.span(label.span, false, Some(&label.message))),
+            )
+            .collect();
+
+        let json = JsonDiagnostic {
+            r#type: "diagnostic",
+            code: diagnostic.code,
+            severity: diagnostic.severity.name(),
+            message: &diagnostic.message,
+            file: self.file,
+            spans,
+            notes: diagnostic
+                .help
+                .iter()
+                .map(|help| format!("help: {}", help))
+                .collect(),
+            suggestion: diagnostic
+                .suggestion
+                .as_ref()
+                .map(|suggestion| JsonSuggestion {
+                    span: self.span(suggestion.span, true, None),
+                    replacement: &suggestion.replacement,
+                }),
+        };
+
+        serde_json::to_string(&json).expect("diagnostics always serialize")
+    }
+
+    /// Converts a span into its JSON form; empty labels are omitted.
+    fn span(&self, span: Span, is_primary: bool, label: Option<&'a str>) -> JsonSpan<'a> {
+        let start = self.source_map.location(span.start);
+        let end = self.source_map.location(span.end);
+        JsonSpan {
+            byte_start: span.start,
+            byte_end: span.end,
+            line_start: start.line,
+            column_start: start.column,
+            line_end: end.line,
+            column_end: end.column,
+            is_primary,
+            label: label.filter(|label| !label.is_empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::analyzer::analyze;
+    use crate::compiler::lexer::tokenize;
+    use crate::compiler::parser::parse;
+
+    /// Returns the diagnostic for the first analysis error of `source`.
+    fn diagnostic(source: &str) -> Diagnostic {
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let error = analyze(&program).unwrap_err().into_errors().remove(0);
+        Diagnostic::from_error(&error)
+    }
+
+    /// Renders the first analysis error of `source`, as if it came from `test.neb`.
+    fn render(source: &str, color: bool) -> String {
+        let source_map = SourceMap::new(source);
+        Renderer::new("test.neb", &source_map, color).render(&diagnostic(source))
+    }
+
+    /// Emits the first analysis error of `source` as JSON, as if it came from `test.neb`.
+    fn emit(source: &str) -> serde_json::Value {
+        let source_map = SourceMap::new(source);
+        let line = JsonEmitter::new("test.neb", &source_map).emit(&diagnostic(source));
+        assert!(
+            !line.contains('\n'),
+            "diagnostics are single lines: {}",
+            line
+        );
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn renders_the_line_caret_and_help() {
+        assert_eq!(
+            render("total 3\ni 1\n! \"Sum: {totl}\"\n", false),
+            concat!(
+                "error[NEB0003]: undefined variable `totl`\n",
+                " --> test.neb:3:10\n",
+                "  |\n",
+                "3 | ! \"Sum: {totl}\"\n",
+                "  |          ^^^^ not found in this scope\n",
+                "  |\n",
+                "  = help: a variable with a similar name exists: `total`\n",
+            )
+        );
+    }
+
+    #[test]
+    fn renders_secondary_labels_below_the_primary_one() {
+        let rendered = render("x 1\ny x + \"a\"\n", false);
+        assert!(
+            rendered.contains(concat!(
+                "2 | y x + \"a\"\n",
+                "  |   ^^^^^^^\n",
+                "  |   - this is Integer\n",
+                "  |       --- this is String\n",
+            )),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn color_is_only_emitted_when_enabled() {
+        let source = "! \"{missing}\"\n";
+        assert!(render(source, true).starts_with("\x1b[1;31merror[NEB0003]\x1b[0m"));
+        assert!(!render(source, false).contains('\x1b'));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn closest_match_only_suggests_likely_typos() {
+        let names = ["total", "count", "totals"];
+        assert_eq!(closest_match("totl", names), Some("total"));
+        assert_eq!(closest_match("cont", names), Some("count"));
+        assert_eq!(closest_match("price", names), None);
+        assert_eq!(closest_match("total", ["total"]), None);
+    }
+
+    #[test]
+    fn json_diagnostics_carry_code_spans_notes_and_suggestion() {
+        let json = emit("total 3\n! \"Sum: {totl}\"\n");
+        assert_eq!(json["type"], "diagnostic");
+        assert_eq!(json["code"], "NEB0003");
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["file"], "test.neb");
+        assert_eq!(
+            json["spans"],
+            serde_json::json!([{
+                "byte_start": 17,
+                "byte_end": 21,
+                "line_start": 2,
+                "column_start": 10,
+                "line_end": 2,
+                "column_end": 14,
+                "is_primary": true,
+                "label": "not found in this scope",
+            }])
+        );
+        assert_eq!(
+            json["notes"],
+            serde_json::json!(["help: a variable with a similar name exists: `total`"])
+        );
+        assert_eq!(json["suggestion"]["replacement"], "total");
+        assert_eq!(json["suggestion"]["span"]["byte_start"], 17);
+    }
+
+    #[test]
+    fn json_secondary_spans_follow_the_primary_one() {
+        let json = emit("x 1\ny x + \"a\"\n");
+        let spans = json["spans"].as_array().unwrap();
+        let primary: Vec<bool> = spans
+            .iter()
+            .map(|span| span["is_primary"] == true)
+            .collect();
+        assert_eq!(primary, [true, false, false]);
+        assert_eq!(spans[0]["label"], serde_json::Value::Null);
+        assert_eq!(spans[2]["label"], "this is String");
+    }
+
+    #[test]
+    fn build_summary_counts_reported_errors() {
+        let summary = |result| -> serde_json::Value {
+            serde_json::from_str(&BuildSummary::new("a.neb", &result).to_json()).unwrap()
+        };
+        assert_eq!(
+            summary(Ok(())),
+            serde_json::json!({
+                "type": "build-finished",
+                "file": "a.neb",
+                "success": true,
+                "error_count": 0,
+                "message": null,
+            })
+        );
+        let failed = summary(Err(CompileError::Reported {
+            file: "a.neb".to_string(),
+            count: 2,
+        }));
+        assert_eq!(failed["success"], false);
+        assert_eq!(failed["error_count"], 2);
+        assert_eq!(
+            summary(Err(CompileError::NoSourceFiles))["message"],
+            "No .neb source files found in current directory"
+        );
+    }
+}
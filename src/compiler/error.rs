@@ -6,6 +6,9 @@
 //! It leverages the `thiserror` crate to simplify error reporting and provide
 //! clear, descriptive messages to the user.
 
+use super::diagnostic::Label;
+use super::error_codes::{self, ErrorCode};
+use super::span::Span;
 use std::process::ExitStatus;
 use thiserror::Error;
 
@@ -16,13 +19,17 @@ pub enum CompileError {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// Error raised when a `.nebfmt` configuration file cannot be read.
+    #[error("Invalid configuration in {path}: {message}")]
+    InvalidConfig { path: String, message: String },
+
     /// Error raised when the compiler cannot find any source files to process.
     #[error("No .neb source files found in current directory")]
     NoSourceFiles,
 
     /// General type-related errors caught during semantic analysis.
     #[error("Type error: {message}")]
-    TypeError { message: String },
+    TypeError { message: String, span: Option<Span> },
 
     /// Error raised if the compiled executable file is missing after the compilation stage.
     #[error("Binary not found after compilation")]
@@ -41,33 +48,71 @@ pub enum CompileError {
     TestFailed,
 
     /// Syntax errors caught during the lexical analysis or parsing stages.
-    /// Includes positional information for user feedback.
-    #[error("Syntax error at position {position}: {message}")]
-    SyntaxError { position: usize, message: String },
+    #[error("Syntax error: {message}")]
+    SyntaxError { message: String, span: Option<Span> },
+
+    /// Lexical error for leading whitespace that does not form a valid block structure.
+    #[error("Indentation error: {message}")]
+    IndentationError { message: String, span: Option<Span> },
+
+    /// Lexical error for a character that cannot start any token.
+    #[error("Unexpected character '{character}' (U+{:04X})", *.character as u32)]
+    UnexpectedCharacter { character: char, span: Option<Span> },
 
     /// Semantic error indicating a variable was used before it was declared.
     #[error("Undefined variable: {name}")]
-    UndefinedVariable { name: String },
+    UndefinedVariable { name: String, span: Option<Span> },
 
     /// Semantic error indicating operations between incompatible types.
     #[error("Type mismatch: {details}")]
-    TypeMismatch { details: String },
+    TypeMismatch { details: String, span: Option<Span> },
+
+    /// Semantic error indicating a call to a function that was never defined.
+    #[error("Undefined function: {name}")]
+    UndefinedFunction { name: String, span: Option<Span> },
+
+    /// Semantic error indicating a call with the wrong number of arguments.
+    #[error("Function '{name}' expects {expected} argument(s), found {found}")]
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Option<Span>,
+    },
+
+    /// An error enriched with secondary labels, a help message and a suggested
+    /// replacement for the misspelled name it reports.
+    #[error("{error}")]
+    Annotated {
+        error: Box<CompileError>,
+        labels: Vec<Label>,
+        help: Option<String>,
+        replacement: Option<String>,
+    },
+
+    /// Several errors found in one pass (e.g., by parser recovery or the analyzer).
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Multiple(Vec<CompileError>),
+
+    /// Error raised after the diagnostics of a failed compilation have been printed.
+    #[error("could not compile `{file}` due to {}", describe_count(*count))]
+    Reported { file: String, count: usize },
 }
 
 impl CompileError {
     /// Constructs a `SyntaxError` specific to the **Lexer** phase.
     pub fn lexer(message: impl Into<String>) -> Self {
         Self::SyntaxError {
-            position: 0, // Positional data is often imprecise at the lexer level
             message: message.into(),
+            span: None,
         }
     }
 
     /// Constructs a `SyntaxError` specific to the **Parser** phase.
     pub fn parser(message: impl Into<String>) -> Self {
         Self::SyntaxError {
-            position: 0, // Positional data is often gathered and set here in full compilers
             message: message.into(),
+            span: None,
         }
     }
 
@@ -75,40 +120,296 @@ impl CompileError {
     pub fn analysis(message: impl Into<String>) -> Self {
         Self::TypeError {
             message: message.into(),
+            span: None,
         }
     }
 
     /// Alias for creating a general `TypeError` (e.g., "Loop start must be integer").
     pub fn r#type(message: impl Into<String>) -> Self {
-        Self::TypeError {
-            message: message.into(),
-        }
+        Self::analysis(message)
     }
 
     /// Another alias for creating a general `TypeError`.
     pub fn type_error(message: impl Into<String>) -> Self {
-        Self::TypeError {
+        Self::analysis(message)
+    }
+
+    /// Constructs a `SyntaxError` covering an explicit source span.
+    pub fn syntax(span: Span, message: impl Into<String>) -> Self {
+        Self::SyntaxError {
             message: message.into(),
+            span: Some(span),
         }
     }
 
-    /// Constructs a `SyntaxError` with explicit positional information.
-    pub fn syntax(position: usize, message: impl Into<String>) -> Self {
-        Self::SyntaxError {
-            position,
+    /// Constructs an `IndentationError` pointing at a line's leading whitespace.
+    pub fn indentation(span: Span, message: impl Into<String>) -> Self {
+        Self::IndentationError {
             message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    /// Constructs an `UnexpectedCharacter` error for a character the lexer cannot use.
+    pub fn unexpected_character(character: char, span: Span) -> Self {
+        Self::UnexpectedCharacter {
+            character,
+            span: Some(span),
         }
     }
 
     /// Constructs an `UndefinedVariable` error, typically used by the analyzer.
     pub fn undefined_variable(name: impl Into<String>) -> Self {
-        Self::UndefinedVariable { name: name.into() }
+        Self::UndefinedVariable {
+            name: name.into(),
+            span: None,
+        }
     }
 
     /// Constructs a `TypeMismatch` error, providing specific details about the mismatched types/operation.
     pub fn type_mismatch(details: impl Into<String>) -> Self {
         Self::TypeMismatch {
             details: details.into(),
+            span: None,
+        }
+    }
+
+    /// Constructs an `UndefinedFunction` error for a call to an unknown function.
+    pub fn undefined_function(name: impl Into<String>) -> Self {
+        Self::UndefinedFunction {
+            name: name.into(),
+            span: None,
+        }
+    }
+
+    /// Constructs an `ArityMismatch` error for a call with the wrong number of arguments.
+    pub fn arity_mismatch(name: impl Into<String>, expected: usize, found: usize) -> Self {
+        Self::ArityMismatch {
+            name: name.into(),
+            expected,
+            found,
+            span: None,
+        }
+    }
+
+    /// Combines the errors collected by a phase into a single error.
+    ///
+    /// A single error is returned as is, so that callers see its own variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `errors` - The collected errors; must not be empty.
+    pub fn from_errors(mut errors: Vec<CompileError>) -> Self {
+        if errors.len() == 1 {
+            errors.remove(0)
+        } else {
+            Self::Multiple(errors)
+        }
+    }
+
+    /// Splits the error into the individual errors it contains.
+    pub fn into_errors(self) -> Vec<CompileError> {
+        match self {
+            Self::Multiple(errors) => errors.into_iter().flat_map(Self::into_errors).collect(),
+            error => vec![error],
+        }
+    }
+
+    /// Returns the source span the error points at, if it has one.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::SyntaxError { span, .. }
+            | Self::UnexpectedCharacter { span, .. }
+            | Self::IndentationError { span, .. }
+            | Self::TypeError { span, .. }
+            | Self::UndefinedVariable { span, .. }
+            | Self::TypeMismatch { span, .. }
+            | Self::UndefinedFunction { span, .. }
+            | Self::ArityMismatch { span, .. } => *span,
+            Self::Annotated { error, .. } => error.span(),
+            _ => None,
         }
     }
+
+    /// Attaches a source span to the error.
+    ///
+    /// A span that is already set is kept, so the most precise location (usually the
+    /// one attached closest to where the error was raised) wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_span` - The span of the offending token or AST node.
+    pub fn with_span(mut self, new_span: Span) -> Self {
+        if let Some(span) = self.span_mut() {
+            span.get_or_insert(new_span);
+        }
+        self
+    }
+
+    /// Adds a secondary label pointing at a related place in the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `span` - The related source span (e.g., a previous definition).
+    /// * `message` - A short explanation shown next to the underline.
+    pub fn with_label(self, span: Span, message: impl Into<String>) -> Self {
+        let mut error = self.annotated();
+        if let Self::Annotated { labels, .. } = &mut error {
+            labels.push(Label::new(span, message));
+        }
+        error
+    }
+
+    /// Adds a `help:` suggestion to the error's diagnostic.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The suggestion (e.g., the closest defined name).
+    pub fn with_help(self, message: impl Into<String>) -> Self {
+        let mut error = self.annotated();
+        if let Self::Annotated { help, .. } = &mut error {
+            *help = Some(message.into());
+        }
+        error
+    }
+
+    /// Suggests a name to replace the undefined one reported by the error.
+    ///
+    /// Tools consuming JSON diagnostics can apply the replacement directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The defined name closest to the one written.
+    pub fn with_replacement(self, name: impl Into<String>) -> Self {
+        let mut error = self.annotated();
+        if let Self::Annotated { replacement, .. } = &mut error {
+            *replacement = Some(name.into());
+        }
+        error
+    }
+
+    /// Wraps the error in [`CompileError::Annotated`], unless it already is.
+    fn annotated(self) -> Self {
+        match self {
+            error @ Self::Annotated { .. } => error,
+            error => Self::Annotated {
+                error: Box::new(error),
+                labels: Vec::new(),
+                help: None,
+                replacement: None,
+            },
+        }
+    }
+
+    /// Returns the stable diagnostic code of the error (see [`error_codes`]).
+    ///
+    /// Errors that are not reported as source diagnostics (e.g., I/O errors) have no code.
+    pub fn code(&self) -> Option<&'static ErrorCode> {
+        match self {
+            Self::SyntaxError { .. } => Some(&error_codes::SYNTAX_ERROR),
+            Self::TypeError { .. } => Some(&error_codes::SEMANTIC_ERROR),
+            Self::UndefinedVariable { .. } => Some(&error_codes::UNDEFINED_VARIABLE),
+            Self::TypeMismatch { .. } => Some(&error_codes::TYPE_MISMATCH),
+            Self::UndefinedFunction { .. } => Some(&error_codes::UNDEFINED_FUNCTION),
+            Self::ArityMismatch { .. } => Some(&error_codes::ARITY_MISMATCH),
+            Self::UnexpectedCharacter { .. } => Some(&error_codes::UNEXPECTED_CHARACTER),
+            Self::IndentationError { .. } => Some(&error_codes::INDENTATION),
+            Self::Annotated { error, .. } => error.code(),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its diagnostic annotations.
+    pub fn inner(&self) -> &CompileError {
+        match self {
+            Self::Annotated { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+
+    /// Returns a mutable reference to the error's span slot, if the variant has one.
+    fn span_mut(&mut self) -> Option<&mut Option<Span>> {
+        match self {
+            Self::SyntaxError { span, .. }
+            | Self::UnexpectedCharacter { span, .. }
+            | Self::IndentationError { span, .. }
+            | Self::TypeError { span, .. }
+            | Self::UndefinedVariable { span, .. }
+            | Self::TypeMismatch { span, .. }
+            | Self::UndefinedFunction { span, .. }
+            | Self::ArityMismatch { span, .. } => Some(span),
+            Self::Annotated { error, .. } => error.span_mut(),
+            _ => None,
+        }
+    }
+}
+
+/// Formats an error count for the final "could not compile" message.
+fn describe_count(count: usize) -> String {
+    match count {
+        1 => "previous error".to_string(),
+        count => format!("{} previous errors", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_errors_flatten_in_order() {
+        let error = CompileError::from_errors(vec![
+            CompileError::undefined_variable("a"),
+            CompileError::from_errors(vec![
+                CompileError::undefined_variable("b"),
+                CompileError::undefined_variable("c"),
+            ]),
+        ]);
+        let names: Vec<String> = error
+            .into_errors()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Undefined variable: a",
+                "Undefined variable: b",
+                "Undefined variable: c"
+            ]
+        );
+        assert!(matches!(
+            CompileError::from_errors(vec![CompileError::undefined_variable("a")]),
+            CompileError::UndefinedVariable { .. }
+        ));
+    }
+
+    #[test]
+    fn reported_errors_are_counted() {
+        let reported = |count| CompileError::Reported {
+            file: "a.neb".to_string(),
+            count,
+        };
+        assert_eq!(
+            reported(1).to_string(),
+            "could not compile `a.neb` due to previous error"
+        );
+        assert_eq!(
+            reported(3).to_string(),
+            "could not compile `a.neb` due to 3 previous errors"
+        );
+    }
+
+    #[test]
+    fn every_diagnostic_error_has_a_code() {
+        let code = |error: CompileError| error.code().map(|entry| entry.code);
+        assert_eq!(code(CompileError::parser("bad")), Some("NEB0001"));
+        assert_eq!(code(CompileError::analysis("bad")), Some("NEB0002"));
+        assert_eq!(
+            code(CompileError::undefined_variable("x").with_help("did you mean y")),
+            Some("NEB0003")
+        );
+        assert_eq!(code(CompileError::type_mismatch("bad")), Some("NEB0004"));
+        assert_eq!(code(CompileError::NoSourceFiles), None);
+    }
 }
@@ -0,0 +1,280 @@
+//! # Error Codes
+//!
+//! This module is the registry of stable diagnostic codes (`NEB0001`, `NEB0002`, ...).
+//! Every diagnostic printed by the compiler, warnings included, carries one of these
+//! codes, and `nebc explain <CODE>` prints the long-form explanation stored here.
+//!
+//! Codes are never reused or renumbered: new kinds of errors get new codes.
+
+/// A registered error code with its long-form explanation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// The stable identifier (e.g., `"NEB0003"`).
+    pub code: &'static str,
+    /// A one-line summary of the error.
+    pub title: &'static str,
+    /// The explanation, with an erroneous example and its fix.
+    pub explanation: &'static str,
+}
+
+/// A syntax error found by the lexer or the parser.
+pub const SYNTAX_ERROR: ErrorCode = ErrorCode {
+    code: "NEB0001",
+    title: "Syntax error",
+    explanation: r#"The source could not be read as Nebulang: a token appears where the
+grammar does not allow it, or a literal is malformed (e.g., a string
+without its closing quote).
+
+Erroneous example:
+
+    x 5
+    ? x >
+        ! "big"
+
+The comparison `x >` is missing its right-hand side. Complete it:
+
+    x 5
+    ? x > 3
+        ! "big"
+"#,
+};
+
+/// A construct used where the language does not allow it.
+pub const SEMANTIC_ERROR: ErrorCode = ErrorCode {
+    code: "NEB0002",
+    title: "Invalid use of a language construct",
+    explanation: r#"The program is well-formed, but a construct is used where it is not
+allowed: for example a return outside a function, a function defined
+twice, or an array index that is not an integer.
+
+Erroneous example:
+
+    => 3
+
+`=>` returns from a function, so it must appear inside one:
+
+    # three()
+        => 3
+"#,
+};
+
+/// A variable used before it is declared.
+pub const UNDEFINED_VARIABLE: ErrorCode = ErrorCode {
+    code: "NEB0003",
+    title: "Undefined variable",
+    explanation: r#"A variable is read or assigned, but no variable with that name has been
+declared at that point. Variables are declared by their first assignment
+(`name value`) and are visible below it; function bodies only see their
+parameters and their own variables.
+
+Erroneous example:
+
+    total 5
+    ! "Total: {totl}"
+
+Fix the spelling, or declare the variable before using it:
+
+    total 5
+    ! "Total: {total}"
+"#,
+};
+
+/// Operands or values of incompatible types.
+pub const TYPE_MISMATCH: ErrorCode = ErrorCode {
+    code: "NEB0004",
+    title: "Type mismatch",
+    explanation: r#"Two values that must have the same type do not: the operands of a binary
+operator, the elements of an array, or an argument and the type its
+parameter was given by an earlier call. The operands of `AND` and `OR`
+must be booleans; compare a number explicitly (`x != 0`) to test it.
+
+Erroneous example:
+
+    count 5
+    label count + " items"
+
+Use values of the same type on both sides:
+
+    count 5
+    total count + 2
+"#,
+};
+
+/// A call to a function that is never defined.
+pub const UNDEFINED_FUNCTION: ErrorCode = ErrorCode {
+    code: "NEB0005",
+    title: "Undefined function",
+    explanation: r#"A function is called, but no function with that name is defined anywhere
+in the file. Functions are defined at the top level with `# name(...)` and
+may be called before their definition.
+
+Erroneous example:
+
+    # square(n)
+        => n * n
+    area sqare(4)
+
+Fix the spelling, or define the function:
+
+    # square(n)
+        => n * n
+    area square(4)
+"#,
+};
+
+/// A call with the wrong number of arguments.
+pub const ARITY_MISMATCH: ErrorCode = ErrorCode {
+    code: "NEB0006",
+    title: "Wrong number of arguments",
+    explanation: r#"A function is called with more or fewer arguments than it has parameters.
+
+Erroneous example:
+
+    # add(a, b)
+        => a + b
+    total add(1)
+
+Pass exactly one argument per parameter:
+
+    # add(a, b)
+        => a + b
+    total add(1, 2)
+"#,
+};
+
+/// A character that cannot start any token.
+pub const UNEXPECTED_CHARACTER: ErrorCode = ErrorCode {
+    code: "NEB0007",
+    title: "Unexpected character",
+    explanation: r#"The source contains a character that is not part of Nebulang's syntax
+outside of a string literal or comment. Every such character is reported,
+together with its Unicode code point, which helps with invisible or
+look-alike characters (e.g., a non-breaking space or curly quotes).
+
+Erroneous example:
+
+    x 5 $ 3
+
+Remove the character, or replace it with the intended operator:
+
+    x 5 + 3
+
+Common mix-ups: assignment is written with a space (`x 5`), not `=`;
+ranges use `..`, not a single `.`; logic uses `AND` and `OR`, not `&&` and `||`
+(a single `&` or `|` is a bitwise operator).
+"#,
+};
+
+/// Leading whitespace that does not form a valid block structure.
+pub const INDENTATION: ErrorCode = ErrorCode {
+    code: "NEB0008",
+    title: "Inconsistent indentation",
+    explanation: r#"Blocks (function bodies, loop and conditional bodies, collections) are
+marked by indentation. The first indented line fixes the width of one
+level (or `indent_width` in a `.nebfmt` file does); every block must be
+indented by exactly one more level than its header, and a line that ends
+a block must return to the indentation of an enclosing block. A line may
+not mix tabs and spaces, and a file indents either with tabs or with
+spaces.
+
+Erroneous example:
+
+    # double(n)
+        d n * 2
+      => d
+
+The last line is indented by 2 columns, which matches neither the body
+(4) nor the top level (0). Line it up with the rest of the body:
+
+    # double(n)
+        d n * 2
+        => d
+"#,
+};
+
+/// Sibling conditionals that look like an else-if chain (a warning).
+pub const SIBLING_CONDITIONS: ErrorCode = ErrorCode {
+    code: "NEB0009",
+    title: "Sibling conditions that look like an else-if chain",
+    explanation: r#"This is a warning: the program compiles, but probably does not do what
+was intended. Several `?` statements in a row test the same variable and
+only the last one has a `!?` branch. Each `?` is a separate statement, so
+the `!?` belongs to the last `?` alone: an earlier branch and the `!?`
+branch can both run.
+
+Erroneous example:
+
+    ? (score > 15)
+        ! "You won!"
+    ? (score > 10)
+        ! "Good game!"
+    !?
+        ! "You can do better!"
+
+With a score of 20, both "You won!" and "You can do better!" are
+printed. Continue the chain with `!? (condition)` so that at most one
+branch runs:
+
+    ? (score > 15)
+        ! "You won!"
+    !? (score > 10)
+        ! "Good game!"
+    !?
+        ! "You can do better!"
+"#,
+};
+
+/// All registered error codes, in numeric order.
+pub const REGISTRY: &[ErrorCode] = &[
+    SYNTAX_ERROR,
+    SEMANTIC_ERROR,
+    UNDEFINED_VARIABLE,
+    TYPE_MISMATCH,
+    UNDEFINED_FUNCTION,
+    ARITY_MISMATCH,
+    UNEXPECTED_CHARACTER,
+    INDENTATION,
+    SIBLING_CONDITIONS,
+];
+
+/// Looks up an error code in the registry.
+///
+/// The lookup ignores case, so `neb0003` finds `NEB0003`.
+///
+/// # Arguments
+///
+/// * `code` - The code as typed by the user.
+///
+/// # Returns
+///
+/// The registered [`ErrorCode`], or `None` if the code is unknown.
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    REGISTRY
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_ignores_case_and_whitespace() {
+        assert_eq!(lookup("NEB0003"), Some(&UNDEFINED_VARIABLE));
+        assert_eq!(lookup(" neb0003\n"), Some(&UNDEFINED_VARIABLE));
+        assert_eq!(lookup("NEB9999"), None);
+        assert_eq!(lookup(""), None);
+    }
+
+    #[test]
+    fn registry_codes_are_numbered_in_order() {
+        for (index, entry) in REGISTRY.iter().enumerate() {
+            assert_eq!(entry.code, format!("NEB{:04}", index + 1));
+            assert!(
+                entry.explanation.contains("Erroneous example:"),
+                "{} has no example",
+                entry.code
+            );
+        }
+    }
+}
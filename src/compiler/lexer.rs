@@ -1,4 +1,7 @@
 use crate::compiler::error::CompileError;
+use crate::compiler::span::Span;
+use std::iter::Peekable;
+use std::str::Chars;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
@@ -6,6 +9,7 @@ pub enum Token {
     Identifier(String),
     StringLiteral(String),
     Integer(i64),
+    Float(f64),
     Boolean(bool),
 
     // Keywords
@@ -13,6 +17,11 @@ pub enum Token {
     Loop,
     If,
     Else,
+    Hash,
+    Return,
+    Break,
+    Continue,
+    Step,
     True,
     False,
 
@@ -31,10 +40,20 @@ pub enum Token {
     GreaterEqual,
     And,
     Or,
+    Not,
+    BitAnd,
+    BitOr,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
 
     // Assignment
     MultiplyAssign,
     PlusAssign,
+    MinusAssign,
+    DivideAssign,
+    ModuloAssign,
+    PowerAssign,
 
     // Punctuation
     BraceOpen,
@@ -46,6 +65,7 @@ pub enum Token {
     Colon,
     Comma,
     Range,
+    RangeExclusive,
     Newline,
 
     // Indentation
@@ -53,14 +73,115 @@ pub enum Token {
     Dedent,
 }
 
-pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, CompileError> {
+/// A token together with the source span it covers and its raw text (lexeme).
+pub type SpannedToken = (Token, Span, String);
+
+/// A comment found while tokenizing.
+///
+/// Comments never reach the parser; they are kept as trivia so that tools such
+/// as a formatter can put them back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The source span, including the `//` or `/* */` delimiters.
+    pub span: Span,
+    /// The comment text, including its delimiters.
+    pub text: String,
+    /// `true` for a `/* ... */` block comment, `false` for a `//` line comment.
+    pub is_block: bool,
+}
+
+/// Settings that change how the source is tokenized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LexerOptions {
+    /// The number of spaces per indentation level (from `.nebfmt`).
+    ///
+    /// When `None`, the first indented line of the file fixes the width.
+    pub indent_width: Option<usize>,
+}
+
+/// Splits Nebulang source code into tokens, discarding comments.
+///
+/// # Arguments
+///
+/// * `source` - The raw Nebulang source code.
+pub fn tokenize(source: &str) -> Result<Vec<SpannedToken>, CompileError> {
+    tokenize_with_comments(source, LexerOptions::default()).map(|(tokens, _)| tokens)
+}
+
+/// Splits Nebulang source code into tokens and the comments between them.
+///
+/// Line comments run from `//` to the end of the line. Block comments run from
+/// `/*` to the matching `*/` and may be nested. Lines holding only whitespace and
+/// comments do not affect indentation.
+///
+/// # Arguments
+///
+/// * `source` - The raw Nebulang source code.
+/// * `options` - Settings such as the indentation width.
+///
+/// # Returns
+///
+/// The tokens and the comments, each in source order.
+pub fn tokenize_with_comments(
+    source: &str,
+    options: LexerOptions,
+) -> Result<(Vec<SpannedToken>, Vec<Comment>), CompileError> {
     let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    // Lexing continues after an error, so that every bad character is reported at once.
+    let mut errors = Vec::new();
     let mut chars = source.chars().peekable();
     let mut position = 0;
-    let mut indentation_stack: Vec<usize> = vec![0];
+    let mut indentation = Indentation::new(options.indent_width);
+    // The indentation of the current line, applied once its first token is found.
+    // The first line is measured like any other, so that it cannot start indented.
+    let mut pending_indent = Some(read_indentation(source, &mut chars, &mut position));
 
     while let Some(&ch) = chars.peek() {
         let start = position;
+        let rest = &source[position..];
+        let is_comment = rest.starts_with("//") || rest.starts_with("/*");
+
+        if !is_comment
+            && !matches!(ch, ' ' | '\t' | '\r' | '\n')
+            && let Some((whitespace, indent_span)) = pending_indent.take()
+        {
+            indentation.apply(whitespace, indent_span, &mut tokens, &mut errors);
+        }
+
+        if rest.starts_with("//") {
+            let length = rest.find('\n').unwrap_or(rest.len());
+            for _ in rest[..length].chars() {
+                chars.next();
+            }
+            position += length;
+            comments.push(Comment {
+                span: Span::new(start, position),
+                text: rest[..length].to_string(),
+                is_block: false,
+            });
+            continue;
+        }
+
+        if rest.starts_with("/*") {
+            let Some(length) = block_comment_length(rest) else {
+                errors.push(CompileError::syntax(
+                    Span::new(start, start + 2),
+                    "Unterminated block comment",
+                ));
+                break;
+            };
+            for _ in rest[..length].chars() {
+                chars.next();
+            }
+            position += length;
+            comments.push(Comment {
+                span: Span::new(start, position),
+                text: rest[..length].to_string(),
+                is_block: true,
+            });
+            continue;
+        }
 
         match ch {
             ' ' => {
@@ -79,51 +200,11 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
             '\n' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Newline, start, position, "\n".to_string()));
-
-                // Yeni satır - girinti hesapla
-                let mut indent_level = 0;
-                let line_start = position;
-
-                while let Some(&next_ch) = chars.peek() {
-                    match next_ch {
-                        ' ' => {
-                            chars.next();
-                            position += 1;
-                            // Her 4 space = 1 girinti seviyesi
-                            if (position - line_start) % 4 == 0 {
-                                indent_level += 1;
-                            }
-                        }
-                        '\t' => {
-                            chars.next();
-                            position += 1;
-                            indent_level += 1;
-                        }
-                        _ => break,
-                    }
-                }
+                tokens.push((Token::Newline, Span::new(start, position), "\n".to_string()));
 
-                let current_indent = *indentation_stack.last().unwrap();
-
-                if indent_level > current_indent {
-                    tokens.push((Token::Indent, line_start, position, "indent".to_string()));
-                    indentation_stack.push(indent_level);
-                } else if indent_level < current_indent {
-                    while let Some(&stack_indent) = indentation_stack.last() {
-                        if stack_indent > indent_level {
-                            tokens.push((
-                                Token::Dedent,
-                                line_start,
-                                position,
-                                "dedent".to_string(),
-                            ));
-                            indentation_stack.pop();
-                        } else {
-                            break;
-                        }
-                    }
-                }
+                // Blank and comment-only lines never reach their first token, so the
+                // next line's indentation replaces theirs.
+                pending_indent = Some(read_indentation(source, &mut chars, &mut position));
             }
             '\r' => {
                 chars.next();
@@ -134,21 +215,32 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 position += 1;
 
                 let mut string_content = String::new();
-                while let Some(ch) = chars.next() {
+                let mut terminated = false;
+                while let Some(&ch) = chars.peek() {
+                    // The line break is left for the next token, so indentation still works.
+                    if ch == '\n' {
+                        break;
+                    }
+                    chars.next();
                     position += ch.len_utf8();
                     if ch == '"' {
+                        terminated = true;
                         break;
                     }
-                    if ch == '\n' {
-                        return Err(CompileError::lexer("Unterminated string"));
-                    }
                     string_content.push(ch);
                 }
 
+                if !terminated {
+                    errors.push(CompileError::syntax(
+                        Span::new(start, position),
+                        "Unterminated string",
+                    ));
+                    continue;
+                }
+
                 tokens.push((
                     Token::StringLiteral(string_content),
-                    start,
-                    position,
+                    Span::new(start, position),
                     source[start..position].to_string(),
                 ));
             }
@@ -167,32 +259,59 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 let token = match ident.as_str() {
                     "OR" => Token::Or,
                     "AND" => Token::And,
+                    "NOT" => Token::Not,
                     "TRUE" => Token::Boolean(true),
                     "FALSE" => Token::Boolean(false),
+                    "break" => Token::Break,
+                    "continue" => Token::Continue,
+                    "step" => Token::Step,
                     _ => Token::Identifier(ident),
                 };
-                tokens.push((token, start, position, source[start..position].to_string()));
+                tokens.push((
+                    token,
+                    Span::new(start, position),
+                    source[start..position].to_string(),
+                ));
             }
             '0'..='9' => {
                 let mut num_str = String::new();
-                let is_negative = if position > 0 {
-                    let prev_token = if tokens.is_empty() {
-                        None
-                    } else {
-                        tokens.last()
-                    };
-                    matches!(prev_token, Some((Token::Minus, _, _, _)))
-                } else {
-                    false
+                // A '-' directly before the digits is folded into the literal, unless it
+                // follows an operand, where it is a binary minus (e.g., `n - 1`, `n -1`).
+                let is_negative = match tokens.as_slice() {
+                    [.., before, (Token::Minus, minus, _)] if minus.end == start => !matches!(
+                        before.0,
+                        Token::Identifier(_)
+                            | Token::Integer(_)
+                            | Token::Float(_)
+                            | Token::StringLiteral(_)
+                            | Token::Boolean(_)
+                            | Token::ParenClose
+                            | Token::BraceClose
+                            | Token::BracketClose
+                    ),
+                    [(Token::Minus, minus, _)] => minus.end == start,
+                    _ => false,
                 };
 
+                let mut start = start;
                 if is_negative {
-                    tokens.pop();
+                    if let Some((_, minus, _)) = tokens.pop() {
+                        start = minus.start;
+                    }
                     num_str.push('-');
                 }
 
+                let mut is_float = false;
                 while let Some(&ch) = chars.peek() {
-                    if ch.is_ascii_digit() {
+                    // A '.' followed by a digit makes a float (`3.14`); `1..5` stays a range.
+                    let starts_fraction = ch == '.'
+                        && !is_float
+                        && chars
+                            .clone()
+                            .nth(1)
+                            .is_some_and(|next| next.is_ascii_digit());
+                    if ch.is_ascii_digit() || starts_fraction {
+                        is_float |= starts_fraction;
                         num_str.push(ch);
                         chars.next();
                         position += ch.len_utf8();
@@ -201,30 +320,47 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                     }
                 }
 
-                match num_str.parse() {
-                    Ok(n) => tokens.push((Token::Integer(n), start, position, num_str)),
-                    Err(_) => {
-                        return Err(CompileError::lexer(&format!(
-                            "Invalid integer: {}",
-                            num_str
-                        )));
+                let span = Span::new(start, position);
+                if is_float {
+                    match num_str.parse::<f64>() {
+                        Ok(x) if x.is_finite() => tokens.push((Token::Float(x), span, num_str)),
+                        _ => errors.push(CompileError::syntax(
+                            span,
+                            format!("Invalid float: {}", num_str),
+                        )),
+                    }
+                } else {
+                    match num_str.parse() {
+                        Ok(n) => tokens.push((Token::Integer(n), span, num_str)),
+                        Err(_) => errors.push(CompileError::syntax(
+                            span,
+                            format!("Invalid integer: {}", num_str),
+                        )),
                     }
                 }
             }
             '[' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::BracketOpen, start, position, "[".to_string()));
+                tokens.push((
+                    Token::BracketOpen,
+                    Span::new(start, position),
+                    "[".to_string(),
+                ));
             }
             ']' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::BracketClose, start, position, "]".to_string()));
+                tokens.push((
+                    Token::BracketClose,
+                    Span::new(start, position),
+                    "]".to_string(),
+                ));
             }
             ':' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Colon, start, position, ":".to_string()));
+                tokens.push((Token::Colon, Span::new(start, position), ":".to_string()));
             }
             '!' => {
                 chars.next();
@@ -232,20 +368,33 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'?') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::Else, start, position, "!?".to_string()));
+                    tokens.push((Token::Else, Span::new(start, position), "!?".to_string()));
+                } else if chars.peek() == Some(&'=') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::NotEqual,
+                        Span::new(start, position),
+                        "!=".to_string(),
+                    ));
                 } else {
-                    tokens.push((Token::Print, start, position, "!".to_string()));
+                    tokens.push((Token::Print, Span::new(start, position), "!".to_string()));
                 }
             }
             '?' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::If, start, position, "?".to_string()));
+                tokens.push((Token::If, Span::new(start, position), "?".to_string()));
             }
             '@' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Loop, start, position, "@".to_string()));
+                tokens.push((Token::Loop, Span::new(start, position), "@".to_string()));
+            }
+            '#' => {
+                chars.next();
+                position += 1;
+                tokens.push((Token::Hash, Span::new(start, position), "#".to_string()));
             }
             '>' => {
                 chars.next();
@@ -253,13 +402,25 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'|') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::Newline, start, position, ">|".to_string()));
+                    tokens.push((Token::Newline, Span::new(start, position), ">|".to_string()));
                 } else if chars.peek() == Some(&'=') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::GreaterEqual, start, position, ">=".to_string()));
+                    tokens.push((
+                        Token::GreaterEqual,
+                        Span::new(start, position),
+                        ">=".to_string(),
+                    ));
+                } else if chars.peek() == Some(&'>') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::ShiftRight,
+                        Span::new(start, position),
+                        ">>".to_string(),
+                    ));
                 } else {
-                    tokens.push((Token::Greater, start, position, ">".to_string()));
+                    tokens.push((Token::Greater, Span::new(start, position), ">".to_string()));
                 }
             }
             '<' => {
@@ -268,9 +429,21 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'=') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::LessEqual, start, position, "<=".to_string()));
+                    tokens.push((
+                        Token::LessEqual,
+                        Span::new(start, position),
+                        "<=".to_string(),
+                    ));
+                } else if chars.peek() == Some(&'<') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::ShiftLeft,
+                        Span::new(start, position),
+                        "<<".to_string(),
+                    ));
                 } else {
-                    tokens.push((Token::Less, start, position, "<".to_string()));
+                    tokens.push((Token::Less, Span::new(start, position), "<".to_string()));
                 }
             }
             '=' => {
@@ -279,14 +452,18 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'=') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::Equal, start, position, "==".to_string()));
+                    tokens.push((Token::Equal, Span::new(start, position), "==".to_string()));
+                } else if chars.peek() == Some(&'>') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((Token::Return, Span::new(start, position), "=>".to_string()));
                 } else {
-                    tokens.push((
-                        Token::Identifier("=".to_string()),
-                        start,
-                        position,
-                        "=".to_string(),
-                    ));
+                    errors.push(
+                        CompileError::unexpected_character('=', Span::new(start, position))
+                            .with_help(
+                                "values are assigned with a space (`x 5`); use `==` to compare",
+                            ),
+                    );
                 }
             }
             '+' => {
@@ -295,15 +472,29 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'=') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::PlusAssign, start, position, "+=".to_string()));
+                    tokens.push((
+                        Token::PlusAssign,
+                        Span::new(start, position),
+                        "+=".to_string(),
+                    ));
                 } else {
-                    tokens.push((Token::Plus, start, position, "+".to_string()));
+                    tokens.push((Token::Plus, Span::new(start, position), "+".to_string()));
                 }
             }
             '-' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Minus, start, position, "-".to_string()));
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::MinusAssign,
+                        Span::new(start, position),
+                        "-=".to_string(),
+                    ));
+                } else {
+                    tokens.push((Token::Minus, Span::new(start, position), "-".to_string()));
+                }
             }
             '*' => {
                 chars.next();
@@ -311,20 +502,44 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'=') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::MultiplyAssign, start, position, "*=".to_string()));
+                    tokens.push((
+                        Token::MultiplyAssign,
+                        Span::new(start, position),
+                        "*=".to_string(),
+                    ));
                 } else {
-                    tokens.push((Token::Multiply, start, position, "*".to_string()));
+                    tokens.push((Token::Multiply, Span::new(start, position), "*".to_string()));
                 }
             }
             '/' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Divide, start, position, "/".to_string()));
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::DivideAssign,
+                        Span::new(start, position),
+                        "/=".to_string(),
+                    ));
+                } else {
+                    tokens.push((Token::Divide, Span::new(start, position), "/".to_string()));
+                }
             }
             '^' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Caret, start, position, "^".to_string()));
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::PowerAssign,
+                        Span::new(start, position),
+                        "^=".to_string(),
+                    ));
+                } else {
+                    tokens.push((Token::Caret, Span::new(start, position), "^".to_string()));
+                }
             }
             '.' => {
                 chars.next();
@@ -332,51 +547,690 @@ pub fn tokenize(source: &str) -> Result<Vec<(Token, usize, usize, String)>, Comp
                 if chars.peek() == Some(&'.') {
                     chars.next();
                     position += 1;
-                    tokens.push((Token::Range, start, position, "..".to_string()));
+                    if chars.peek() == Some(&'<') {
+                        chars.next();
+                        position += 1;
+                        tokens.push((
+                            Token::RangeExclusive,
+                            Span::new(start, position),
+                            "..<".to_string(),
+                        ));
+                    } else {
+                        tokens.push((Token::Range, Span::new(start, position), "..".to_string()));
+                    }
+                } else {
+                    let help = if chars.peek().is_some_and(|next| next.is_ascii_digit()) {
+                        "floats need a digit before the decimal point (`0.5`)"
+                    } else {
+                        "ranges are written with two dots (`1..10`)"
+                    };
+                    errors.push(
+                        CompileError::unexpected_character('.', Span::new(start, position))
+                            .with_help(help),
+                    );
                 }
             }
             '{' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::BraceOpen, start, position, "{".to_string()));
+                tokens.push((
+                    Token::BraceOpen,
+                    Span::new(start, position),
+                    "{".to_string(),
+                ));
             }
             '}' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::BraceClose, start, position, "}".to_string()));
+                tokens.push((
+                    Token::BraceClose,
+                    Span::new(start, position),
+                    "}".to_string(),
+                ));
             }
             '(' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::ParenOpen, start, position, "(".to_string()));
+                tokens.push((
+                    Token::ParenOpen,
+                    Span::new(start, position),
+                    "(".to_string(),
+                ));
             }
             ')' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::ParenClose, start, position, ")".to_string()));
+                tokens.push((
+                    Token::ParenClose,
+                    Span::new(start, position),
+                    ")".to_string(),
+                ));
             }
             ',' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Comma, start, position, ",".to_string()));
+                tokens.push((Token::Comma, Span::new(start, position), ",".to_string()));
             }
             '%' => {
                 chars.next();
                 position += 1;
-                tokens.push((Token::Modulo, start, position, "%".to_string()));
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    position += 1;
+                    tokens.push((
+                        Token::ModuloAssign,
+                        Span::new(start, position),
+                        "%=".to_string(),
+                    ));
+                } else {
+                    tokens.push((Token::Modulo, Span::new(start, position), "%".to_string()));
+                }
             }
-            _ => {
+            '&' | '|' => {
                 chars.next();
                 position += 1;
+                if chars.peek() == Some(&ch) {
+                    // `&&` and `||` from other languages: the logical operators are keywords.
+                    chars.next();
+                    position += 1;
+                    let error = CompileError::unexpected_character(ch, Span::new(start, position));
+                    errors.push(match unexpected_character_help(ch) {
+                        Some(help) => error.with_help(help),
+                        None => error,
+                    });
+                } else if ch == '&' {
+                    tokens.push((Token::BitAnd, Span::new(start, position), "&".to_string()));
+                } else {
+                    tokens.push((Token::BitOr, Span::new(start, position), "|".to_string()));
+                }
+            }
+            '~' => {
+                chars.next();
+                position += 1;
+                tokens.push((Token::Tilde, Span::new(start, position), "~".to_string()));
+            }
+            _ => {
+                chars.next();
+                position += ch.len_utf8();
+
+                let error = CompileError::unexpected_character(ch, Span::new(start, position));
+                errors.push(match unexpected_character_help(ch) {
+                    Some(help) => error.with_help(help),
+                    None => error,
+                });
             }
         }
     }
 
     // Dosya sonunda kalan girintileri kapat
-    while indentation_stack.len() > 1 {
-        tokens.push((Token::Dedent, position, position, "dedent".to_string()));
-        indentation_stack.pop();
+    indentation.close(Span::new(position, position), &mut tokens);
+
+    if !errors.is_empty() {
+        return Err(CompileError::from_errors(errors));
+    }
+
+    Ok((tokens, comments))
+}
+
+/// Suggests a fix for a character that commonly slips in from other languages.
+///
+/// # Arguments
+///
+/// * `character` - The unexpected character.
+fn unexpected_character_help(character: char) -> Option<&'static str> {
+    match character {
+        '&' => Some("use `AND` for logical and; a single `&` is bitwise and"),
+        '|' => Some("use `OR` for logical or; a single `|` is bitwise or"),
+        ';' => Some("statements end at the end of the line; remove the `;`"),
+        '\u{201C}' | '\u{201D}' => Some("strings use straight double quotes (`\"`)"),
+        '\u{2018}' | '\u{2019}' | '\'' => Some("strings are written in double quotes (`\"`)"),
+        '\u{00A0}' | '\u{200B}' | '\u{FEFF}' => {
+            Some("this invisible character looks like a space; replace it with a regular space")
+        }
+        _ => None,
+    }
+}
+
+/// Reads the leading whitespace of a line.
+///
+/// # Returns
+///
+/// The whitespace and its span.
+fn read_indentation<'a>(
+    source: &'a str,
+    chars: &mut Peekable<Chars>,
+    position: &mut usize,
+) -> (&'a str, Span) {
+    let line_start = *position;
+    while let Some(&ch) = chars.peek() {
+        if ch != ' ' && ch != '\t' {
+            break;
+        }
+        chars.next();
+        *position += 1;
+    }
+    (
+        &source[line_start..*position],
+        Span::new(line_start, *position),
+    )
+}
+
+/// Tracks the indentation of nested blocks, Python style.
+///
+/// Each line that starts a statement is compared with the open blocks: a deeper
+/// line opens a block (`Indent`), a shallower one must return exactly to an
+/// enclosing block (`Dedent`s).
+struct Indentation {
+    /// The widths of the open blocks, outermost (`0`) first.
+    stack: Vec<usize>,
+    /// The number of spaces per level, fixed by `.nebfmt` or the first indented line.
+    unit: Option<usize>,
+    /// The indentation character (`' '` or `'\t'`), fixed by the first indented line.
+    style: Option<char>,
+    /// Whether a line with a token has been seen yet.
+    started: bool,
+}
+
+impl Indentation {
+    /// Creates the tracker, optionally with a configured indentation width.
+    fn new(unit: Option<usize>) -> Self {
+        Self {
+            stack: vec![0],
+            unit,
+            style: None,
+            started: false,
+        }
     }
 
-    Ok(tokens)
+    /// Emits the `Indent`/`Dedent` tokens for a line that starts with a token.
+    ///
+    /// Problems are reported in `errors`; the line is still given the closest
+    /// sensible level, so that one mistake does not cascade.
+    ///
+    /// # Arguments
+    ///
+    /// * `whitespace` - The line's leading whitespace.
+    /// * `span` - The span of that whitespace.
+    /// * `tokens` - The token stream being built.
+    /// * `errors` - The lexer errors found so far.
+    fn apply(
+        &mut self,
+        whitespace: &str,
+        span: Span,
+        tokens: &mut Vec<SpannedToken>,
+        errors: &mut Vec<CompileError>,
+    ) {
+        if !std::mem::replace(&mut self.started, true) && !whitespace.is_empty() {
+            errors.push(CompileError::indentation(
+                span,
+                "Unexpected indentation: the first statement must not be indented",
+            ));
+            return;
+        }
+
+        // A line in the wrong style is reported once and otherwise ignored.
+        if whitespace.contains(' ') && whitespace.contains('\t') {
+            errors.push(
+                CompileError::indentation(span, "Indentation mixes tabs and spaces")
+                    .with_help("indent with either tabs or spaces, not both"),
+            );
+            return;
+        }
+        if let Some(first) = whitespace.chars().next() {
+            match self.style {
+                None => self.style = Some(first),
+                Some(style) if style != first => {
+                    errors.push(CompileError::indentation(
+                        span,
+                        format!(
+                            "Indentation uses {}, but this file is indented with {}",
+                            describe_style(first),
+                            describe_style(style)
+                        ),
+                    ));
+                    return;
+                }
+                Some(_) => {}
+            }
+        }
+
+        // A tab is one level; spaces are counted and checked against the unit.
+        let width = whitespace.chars().count();
+        let current = *self.stack.last().unwrap();
+
+        if width > current {
+            let unit = if self.style == Some('\t') {
+                1
+            } else {
+                *self.unit.get_or_insert(width - current)
+            };
+            if width != current + unit {
+                errors.push(CompileError::indentation(
+                    span,
+                    format!(
+                        "Unexpected indentation: expected {} column(s), found {}",
+                        current + unit,
+                        width
+                    ),
+                ));
+            }
+            tokens.push((Token::Indent, span, "indent".to_string()));
+            self.stack.push(width);
+        } else if width < current {
+            let levels = self
+                .stack
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            while self.stack.last().is_some_and(|&level| level > width) {
+                tokens.push((Token::Dedent, span, "dedent".to_string()));
+                self.stack.pop();
+            }
+            if self.stack.last() != Some(&width) {
+                errors.push(
+                    CompileError::indentation(
+                        span,
+                        "Inconsistent dedent: this line does not match any outer indentation level",
+                    )
+                    .with_help(format!(
+                        "the enclosing blocks are indented by {} column(s)",
+                        levels
+                    )),
+                );
+            }
+        }
+    }
+
+    /// Closes all blocks still open at the end of the file.
+    fn close(&mut self, span: Span, tokens: &mut Vec<SpannedToken>) {
+        while self.stack.len() > 1 {
+            tokens.push((Token::Dedent, span, "dedent".to_string()));
+            self.stack.pop();
+        }
+    }
+}
+
+/// Names an indentation character for error messages.
+fn describe_style(style: char) -> &'static str {
+    if style == '\t' { "tabs" } else { "spaces" }
+}
+
+/// Measures a (possibly nested) block comment at the start of `text`.
+///
+/// # Returns
+///
+/// The length in bytes up to and including the matching `*/`, or `None` if the
+/// comment is never closed.
+fn block_comment_length(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return Some(index);
+            }
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::span::SourceMap;
+
+    /// Tokenizes `source` and keeps only the tokens, without spans and lexemes.
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .expect("source should tokenize")
+            .into_iter()
+            .map(|(token, _, _)| token)
+            .collect()
+    }
+
+    #[test]
+    fn minus_after_an_operand_stays_binary() {
+        assert_eq!(
+            tokens("n - 1"),
+            [
+                Token::Identifier("n".into()),
+                Token::Minus,
+                Token::Integer(1)
+            ]
+        );
+        assert_eq!(
+            tokens("n -1"),
+            [
+                Token::Identifier("n".into()),
+                Token::Minus,
+                Token::Integer(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn minus_before_digits_folds_into_the_literal() {
+        assert_eq!(tokens("-7"), [Token::Integer(-7)]);
+        assert_eq!(
+            tokens("(-7)"),
+            [Token::ParenOpen, Token::Integer(-7), Token::ParenClose]
+        );
+        let (_, span, lexeme) = tokenize("-7").unwrap().remove(0);
+        assert_eq!(span, Span::new(0, 2));
+        assert_eq!(lexeme, "-7");
+    }
+
+    #[test]
+    fn function_tokens() {
+        assert_eq!(
+            tokens("# f(a) => a"),
+            [
+                Token::Hash,
+                Token::Identifier("f".into()),
+                Token::ParenOpen,
+                Token::Identifier("a".into()),
+                Token::ParenClose,
+                Token::Return,
+                Token::Identifier("a".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_carry_their_byte_spans() {
+        let spans: Vec<_> = tokenize("total 10\n! \"{total}\"")
+            .unwrap()
+            .into_iter()
+            .map(|(_, span, lexeme)| (span, lexeme))
+            .collect();
+        assert_eq!(spans[0], (Span::new(0, 5), "total".to_string()));
+        assert_eq!(spans[1], (Span::new(6, 8), "10".to_string()));
+        assert_eq!(spans.last().unwrap().0.end, 20);
+    }
+
+    #[test]
+    fn comments_are_skipped_but_kept_as_trivia() {
+        let source = "x 1 // one\n/* a /* nested */ block */ y 2\n";
+        let (tokens, comments) = tokenize_with_comments(source, LexerOptions::default()).unwrap();
+        let tokens: Vec<Token> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::Identifier("x".into()),
+                Token::Integer(1),
+                Token::Newline,
+                Token::Identifier("y".into()),
+                Token::Integer(2),
+                Token::Newline,
+            ]
+        );
+        let texts: Vec<(&str, bool)> = comments
+            .iter()
+            .map(|comment| (comment.text.as_str(), comment.is_block))
+            .collect();
+        assert_eq!(
+            texts,
+            [("// one", false), ("/* a /* nested */ block */", true)]
+        );
+        assert_eq!(comments[0].span, Span::new(4, 10));
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_text() {
+        assert_eq!(
+            tokens("! \"a // b /* c\""),
+            [Token::Print, Token::StringLiteral("a // b /* c".into())]
+        );
+    }
+
+    #[test]
+    fn comment_only_lines_do_not_change_indentation() {
+        let source = "? (TRUE)\n        // deeper comment\n    x 1\n// outer comment\n    y 2\n";
+        let indents = tokens(source)
+            .iter()
+            .filter(|token| matches!(token, Token::Indent | Token::Dedent))
+            .count();
+        // A single Indent before `x` and its Dedent at the end of the file.
+        assert_eq!(indents, 2);
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_an_error() {
+        let error = tokenize("x 1\n/* /* */ never closed").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Syntax error: Unterminated block comment"
+        );
+        assert_eq!(error.span(), Some(Span::new(4, 6)));
+    }
+
+    /// Tokenizes `source`, which must be invalid, and returns each error's span and help.
+    fn lexer_errors(source: &str) -> Vec<(String, Option<Span>, Option<String>)> {
+        tokenize(source)
+            .unwrap_err()
+            .into_errors()
+            .into_iter()
+            .map(|error| {
+                let help = match &error {
+                    CompileError::Annotated { help, .. } => help.clone(),
+                    _ => None,
+                };
+                (error.to_string(), error.span(), help)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_unexpected_character_is_reported() {
+        let errors = lexer_errors("x 5 $ 3\ny 2 ` 1\n");
+        assert_eq!(
+            errors,
+            [
+                (
+                    "Unexpected character '$' (U+0024)".to_string(),
+                    Some(Span::new(4, 5)),
+                    None
+                ),
+                (
+                    "Unexpected character '`' (U+0060)".to_string(),
+                    Some(Span::new(12, 13)),
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unexpected_characters_on_several_lines_keep_their_positions() {
+        let source = "a 1 $\nb 2\nc ` 3 \\\nd 4 $\n";
+        let source_map = SourceMap::new(source);
+        let positions: Vec<_> = lexer_errors(source)
+            .into_iter()
+            .map(|(message, span, _)| {
+                let span = span.expect("every error should have a span");
+                (
+                    message,
+                    &source[span.start..span.end],
+                    source_map.location(span.start).to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (
+                    "Unexpected character '$' (U+0024)".to_string(),
+                    "$",
+                    "1:5".to_string()
+                ),
+                (
+                    "Unexpected character '`' (U+0060)".to_string(),
+                    "`",
+                    "3:3".to_string()
+                ),
+                (
+                    "Unexpected character '\\' (U+005C)".to_string(),
+                    "\\",
+                    "3:7".to_string()
+                ),
+                (
+                    "Unexpected character '$' (U+0024)".to_string(),
+                    "$",
+                    "4:5".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn common_slips_get_a_suggestion() {
+        let help = |source| lexer_errors(source).remove(0).2.unwrap();
+        assert_eq!(
+            help("x = 5"),
+            "values are assigned with a space (`x 5`); use `==` to compare"
+        );
+        assert_eq!(
+            help("x .5"),
+            "floats need a digit before the decimal point (`0.5`)"
+        );
+        assert_eq!(help("x a.b"), "ranges are written with two dots (`1..10`)");
+        assert_eq!(
+            help("x 1;"),
+            "statements end at the end of the line; remove the `;`"
+        );
+        let (message, _, _) = lexer_errors("! \u{201C}hi\u{201D}").remove(0);
+        assert_eq!(message, "Unexpected character '\u{201C}' (U+201C)");
+    }
+
+    /// Returns the messages of the errors found while tokenizing `source`.
+    fn error_messages(source: &str, options: LexerOptions) -> Vec<String> {
+        match tokenize_with_comments(source, options) {
+            Ok(_) => Vec::new(),
+            Err(error) => error
+                .into_errors()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn the_first_indented_block_fixes_the_unit() {
+        let source = "? (TRUE)\n  ? (TRUE)\n    x 1\n  y 2\nz 3\n";
+        assert_eq!(
+            error_messages(source, LexerOptions::default()),
+            Vec::<String>::new()
+        );
+        let blocks: Vec<Token> = tokens(source)
+            .into_iter()
+            .filter(|token| matches!(token, Token::Indent | Token::Dedent))
+            .collect();
+        assert_eq!(
+            blocks,
+            [Token::Indent, Token::Indent, Token::Dedent, Token::Dedent]
+        );
+        assert_eq!(
+            error_messages(
+                "? (TRUE)\n  x 1\n? (TRUE)\n     y 2\n",
+                LexerOptions::default()
+            ),
+            ["Indentation error: Unexpected indentation: expected 2 column(s), found 5"]
+        );
+    }
+
+    #[test]
+    fn tabs_and_spaces_do_not_mix() {
+        let options = LexerOptions::default();
+        assert_eq!(
+            error_messages("? (TRUE)\n\tx 1\n", options),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            error_messages("? (TRUE)\n \tx 1\n", options),
+            ["Indentation error: Indentation mixes tabs and spaces"]
+        );
+        assert_eq!(
+            error_messages("? (TRUE)\n\tx 1\n? (TRUE)\n    y 2\n", options),
+            ["Indentation error: Indentation uses spaces, but this file is indented with tabs"]
+        );
+    }
+
+    #[test]
+    fn dedents_must_return_to_an_open_block() {
+        let source = "? (TRUE)\n    ? (TRUE)\n        x 1\n  y 2\n";
+        let error = tokenize(source).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Indentation error: Inconsistent dedent: this line does not match any outer indentation level"
+        );
+        assert_eq!(error.span(), Some(Span::new(34, 36)));
+        assert_eq!(
+            error_messages("  x 1\n", LexerOptions::default()),
+            ["Indentation error: Unexpected indentation: the first statement must not be indented"]
+        );
+    }
+
+    #[test]
+    fn a_configured_width_overrides_the_first_block() {
+        let options = LexerOptions {
+            indent_width: Some(2),
+        };
+        assert_eq!(
+            error_messages("? (TRUE)\n  x 1\n", options),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            error_messages("? (TRUE)\n    x 1\n", options),
+            ["Indentation error: Unexpected indentation: expected 2 column(s), found 4"]
+        );
+    }
+
+    #[test]
+    fn float_literals_and_ranges() {
+        assert_eq!(tokens("3.25"), [Token::Float(3.25)]);
+        assert_eq!(tokens("-0.5"), [Token::Float(-0.5)]);
+        assert_eq!(
+            tokens("1..10"),
+            [Token::Integer(1), Token::Range, Token::Integer(10)]
+        );
+    }
+
+    #[test]
+    fn compound_assignment_tokens() {
+        assert_eq!(
+            tokens("+= -= *= /= %= ^="),
+            [
+                Token::PlusAssign,
+                Token::MinusAssign,
+                Token::MultiplyAssign,
+                Token::DivideAssign,
+                Token::ModuloAssign,
+                Token::PowerAssign,
+            ]
+        );
+    }
+
+    #[test]
+    fn not_equal_does_not_clash_with_print_or_else() {
+        assert_eq!(
+            tokens("! a != b"),
+            [
+                Token::Print,
+                Token::Identifier("a".into()),
+                Token::NotEqual,
+                Token::Identifier("b".into()),
+            ]
+        );
+        assert_eq!(tokens("!?"), [Token::Else]);
+        assert_eq!(tokens("NOT ~"), [Token::Not, Token::Tilde]);
+    }
 }
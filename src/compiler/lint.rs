@@ -0,0 +1,219 @@
+//! # Lints
+//!
+//! This module looks for code that compiles but probably does not do what was intended,
+//! and describes it as warnings. Lints run once semantic analysis has succeeded; warnings
+//! are printed like errors but never stop a build.
+
+use super::diagnostic::{Diagnostic, Label, Severity, Suggestion};
+use super::error_codes;
+use super::span::Span;
+use crate::ast::nodes::*;
+use std::collections::HashSet;
+
+/// Runs every lint over a program.
+///
+/// # Arguments
+///
+/// * `program` - The analyzed program.
+///
+/// # Returns
+///
+/// The warnings, in source order.
+pub fn lint(program: &Program) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+    lint_block(&program.statements, &mut warnings);
+    warnings
+}
+
+/// Lints a list of sibling statements and, recursively, the blocks nested in them.
+fn lint_block(statements: &[Statement], warnings: &mut Vec<Diagnostic>) {
+    sibling_conditions(statements, warnings);
+
+    for statement in statements {
+        match &statement.kind {
+            StatementKind::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                lint_block(then_branch, warnings);
+                if let Some(else_branch) = else_branch {
+                    lint_block(else_branch, warnings);
+                }
+            }
+            StatementKind::Loop { body, .. }
+            | StatementKind::ForEach { body, .. }
+            | StatementKind::While { body, .. }
+            | StatementKind::Function { body, .. } => lint_block(body, warnings),
+            _ => {}
+        }
+    }
+}
+
+/// Warns about runs of `?` statements that look like an else-if chain written without `!?`.
+///
+/// A run is a sequence of adjacent conditionals where each condition shares a variable with
+/// the previous one, only the last conditional has a `!?` branch, and so that branch can run
+/// together with an earlier one. Every conditional after the first gets a warning.
+fn sibling_conditions(statements: &[Statement], warnings: &mut Vec<Diagnostic>) {
+    let mut index = 0;
+    while index < statements.len() {
+        let mut run = vec![&statements[index]];
+        while let Some(next) = statements.get(index + run.len()) {
+            let previous = run[run.len() - 1];
+            if has_else(previous) || !shares_variable(previous, next) {
+                break;
+            }
+            run.push(next);
+        }
+
+        if run.len() > 1 && has_else(run[run.len() - 1]) {
+            let first = run[0];
+            for sibling in &run[1..] {
+                warnings.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: Some(error_codes::SIBLING_CONDITIONS.code),
+                    message: "this `?` starts a separate conditional, not an else-if".to_string(),
+                    primary: Some(Label::new(sibling.span, "tested even if an earlier branch ran")),
+                    secondary: vec![Label::new(first.span, "the chain seems to start here")],
+                    help: Some(
+                        "write `!? (condition)` to continue the chain, so that at most one branch runs"
+                            .to_string(),
+                    ),
+                    suggestion: Some(Suggestion {
+                        span: Span::new(sibling.span.start, sibling.span.start + 1),
+                        replacement: "!?".to_string(),
+                    }),
+                });
+            }
+        }
+
+        index += run.len();
+    }
+}
+
+/// Checks whether a statement is a conditional with a `!?` branch.
+fn has_else(statement: &Statement) -> bool {
+    matches!(
+        statement.kind,
+        StatementKind::If {
+            else_branch: Some(_),
+            ..
+        }
+    )
+}
+
+/// Checks whether two statements are conditionals whose conditions read a common variable.
+fn shares_variable(first: &Statement, second: &Statement) -> bool {
+    let (
+        StatementKind::If {
+            condition: first, ..
+        },
+        StatementKind::If {
+            condition: second, ..
+        },
+    ) = (&first.kind, &second.kind)
+    else {
+        return false;
+    };
+
+    let mut first_variables = HashSet::new();
+    collect_variables(first, &mut first_variables);
+    let mut second_variables = HashSet::new();
+    collect_variables(second, &mut second_variables);
+    !first_variables.is_disjoint(&second_variables)
+}
+
+/// Collects the names of the variables read by an expression.
+fn collect_variables<'a>(expression: &'a Expression, variables: &mut HashSet<&'a str>) {
+    match &expression.kind {
+        ExpressionKind::Variable(name) => {
+            variables.insert(name);
+        }
+        ExpressionKind::ArrayAccess { array, index } => {
+            variables.insert(array);
+            collect_variables(index, variables);
+        }
+        ExpressionKind::Slice { string, start, end } => {
+            variables.insert(string);
+            collect_variables(start, variables);
+            collect_variables(end, variables);
+        }
+        ExpressionKind::Binary { left, right, .. } => {
+            collect_variables(left, variables);
+            collect_variables(right, variables);
+        }
+        ExpressionKind::CollectionAccess { index, .. } => {
+            if let Some(index) = index {
+                collect_variables(index, variables);
+            }
+        }
+        ExpressionKind::Unary { operand, .. } | ExpressionKind::Length(operand) => {
+            collect_variables(operand, variables)
+        }
+        ExpressionKind::Call { arguments, .. } => {
+            for argument in arguments {
+                collect_variables(argument, variables);
+            }
+        }
+        ExpressionKind::Integer(_)
+        | ExpressionKind::Float(_)
+        | ExpressionKind::String(_)
+        | ExpressionKind::Boolean(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::lexer::tokenize;
+    use crate::compiler::parser::parse;
+
+    /// Lints `source` and returns the source text each warning points at.
+    fn warned(source: &str) -> Vec<&str> {
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        lint(&program)
+            .iter()
+            .map(|warning| {
+                let span = warning.primary.as_ref().unwrap().span;
+                &source[span.start..span.start + source[span.start..].find('\n').unwrap()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn warns_about_siblings_that_look_like_a_chain() {
+        let source = "? (s > 15)\n    ! \"won\"\n? (s > 10)\n    ! \"good\"\n!?\n    ! \"more\"\n";
+        assert_eq!(warned(source), ["? (s > 10)"]);
+
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let warning = lint(&program).remove(0);
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.code, Some("NEB0009"));
+        assert_eq!(
+            warning.suggestion.map(|suggestion| suggestion.replacement),
+            Some("!?".to_string())
+        );
+    }
+
+    #[test]
+    fn real_chains_and_unrelated_conditions_are_fine() {
+        // An else-if chain.
+        assert!(
+            warned("? (s > 15)\n    ! \"won\"\n!? (s > 10)\n    ! \"good\"\n!?\n    ! \"more\"\n")
+                .is_empty()
+        );
+        // Conditions on different variables.
+        assert!(
+            warned("? (a > 1)\n    ! \"a\"\n? (b > 1)\n    ! \"b\"\n!?\n    ! \"c\"\n").is_empty()
+        );
+        // No `!?` branch: every condition is meant to be tested.
+        assert!(warned("? (s > 15)\n    ! \"a\"\n? (s > 10)\n    ! \"b\"\n").is_empty());
+    }
+
+    #[test]
+    fn nested_blocks_are_linted() {
+        let source = "@ (TRUE)\n    ? (s > 1)\n        ! \"a\"\n    ? (s > 0)\n        ! \"b\"\n    !?\n        ! \"c\"\n";
+        assert_eq!(warned(source), ["? (s > 0)"]);
+    }
+}
@@ -7,21 +7,31 @@
 
 // Publicly exposes the compiler phases.
 pub mod analyzer;
+pub mod builtins;
 pub mod codegen;
+pub mod config;
+pub mod diagnostic;
 pub mod error;
+pub mod error_codes;
 pub mod lexer;
+pub mod lint;
 pub mod parser;
+pub mod span;
 
 // Re-exports essential functions for external use.
-pub use analyzer::analyze;
+pub use analyzer::{analyze, analyze_with_overflow};
 pub use lexer::tokenize;
 pub use parser::parse;
 
 // Internal dependencies for the compilation process.
+use crate::codegen::common::OverflowMode;
 use crate::codegen::quantum_asm::QuantumAssemblyGenerator;
+use config::FormatConfig;
+use diagnostic::{BuildSummary, ColorChoice, Diagnostic, JsonEmitter, MessageFormat, Renderer};
 use error::CompileError;
-use std::path::PathBuf;
-use std::process::Command;
+use span::SourceMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 /// The central structure that manages the compilation, assembly, and execution
 /// of a Nebulang program.
@@ -38,6 +48,21 @@ pub struct Compiler {
     pub no_protection: bool,
     /// Flag for detailed output messages during the build process.
     pub verbose: bool,
+    /// Flag to disable runtime bounds checks on array accesses.
+    pub no_bounds_check: bool,
+    /// What integer arithmetic does when it overflows.
+    pub overflow: OverflowMode,
+    /// Whether compile errors are rendered with ANSI colors.
+    pub color: ColorChoice,
+    /// The maximum number of diagnostics printed per file (`None` prints all of them).
+    pub error_limit: Option<usize>,
+    /// Whether diagnostics are rendered for humans or printed as JSON lines.
+    pub message_format: MessageFormat,
+    /// A file fed to the standard input of a program started by `run` or `test`. Without
+    /// it, `run` passes on the compiler's own standard input and `test` gives an empty one.
+    pub stdin_path: Option<PathBuf>,
+    /// The command-line arguments passed to a program started by `run`.
+    pub program_args: Vec<String>,
 }
 
 /*
@@ -61,24 +86,39 @@ impl Compiler {
             show_asm: false,
             no_protection: false,
             verbose: false,
+            no_bounds_check: false,
+            overflow: OverflowMode::Trap,
+            color: ColorChoice::Auto,
+            error_limit: None,
+            message_format: MessageFormat::Human,
+            stdin_path: None,
+            program_args: Vec::new(),
         }
     }
 
     /// Initiates the build process.
     ///
     /// It first checks if the source path points to a single `.neb` file or
-    /// delegates to directory compilation logic (if implemented).
+    /// delegates to directory compilation logic (if implemented). With JSON
+    /// messages, a final build-summary object is printed either way.
     ///
     /// # Arguments
     /// * `target` - The target platform for the resulting binary.
     pub fn build(&mut self, target: &str) -> Result<(), CompileError> {
         self.target = target.to_string();
 
-        if self.source_path.is_file() && self.is_neb_file(&self.source_path) {
-            return self.build_single_file(target);
+        let result = if self.source_path.is_file() && self.is_neb_file(&self.source_path) {
+            self.build_single_file(target)
+        } else {
+            Err(CompileError::NoSourceFiles)
+        };
+
+        if self.message_format == MessageFormat::Json {
+            let file = self.source_path.display().to_string();
+            println!("{}", BuildSummary::new(&file, &result).to_json());
         }
 
-        Err(CompileError::NoSourceFiles)
+        result
     }
 
     /// Executes the full compilation pipeline for a single Nebulang source file.
@@ -93,21 +133,33 @@ impl Compiler {
 
         self.log_verbose(&format!("Processing: {:?}", self.source_path));
 
-        // 1. Read Source
+        // 1. Read Source (and the `.nebfmt` settings that apply to it)
         let content = std::fs::read_to_string(&self.source_path)?;
+        let config = FormatConfig::discover(&self.source_path)?;
+
+        // Errors carry byte spans; they are rendered against the source before returning.
+        let file_name = self.source_path.display().to_string();
+        let source_map = SourceMap::new(&content);
+        let report = |error: CompileError| self.report(error, &file_name, &source_map);
 
         // 2. Lexing (Tokenize)
-        let tokens = tokenize(&content)?;
+        let (tokens, _) =
+            lexer::tokenize_with_comments(&content, config.lexer_options()).map_err(report)?;
 
         // 3. Parsing (Build AST)
-        let ast = parse(tokens)?;
+        let ast = parse(tokens).map_err(report)?;
 
         // 4. Semantic Analysis (Type/Symbol Check)
-        analyze(&ast)?;
+        let types = analyze_with_overflow(&ast, self.overflow).map_err(report)?;
+        self.warn(&lint::lint(&ast), &file_name, &source_map);
 
         // 5. Code Generation (Generate ASM)
         let mut quantum_gen = QuantumAssemblyGenerator::new();
-        let asm_code = quantum_gen.generate(&ast)?;
+        quantum_gen.set_bounds_checks(!self.no_bounds_check);
+        quantum_gen.set_overflow(self.overflow);
+        quantum_gen.set_source(&file_name, source_map.clone());
+        quantum_gen.set_types(types);
+        let asm_code = quantum_gen.generate(&ast).map_err(report)?;
 
         // Output ASM if requested
         if self.show_asm {
@@ -116,6 +168,7 @@ impl Compiler {
         }
 
         // 6. Write Assembly to File
+        std::fs::create_dir_all(&self.build_path)?;
         let asm_file_path = self.build_path.join("quantum_output.asm");
         std::fs::write(&asm_file_path, &asm_code)?;
 
@@ -127,10 +180,10 @@ impl Compiler {
         // 7. Assemble and Link to Binary
         self.compile_assembly_to_binary(&asm_file_path)?;
 
-        println!(
+        self.log_status(&format!(
             "✅ {:?} - Quantum compilation successful!",
             self.source_path
-        );
+        ));
 
         Ok(())
     }
@@ -138,14 +191,17 @@ impl Compiler {
     /// Compiles and then executes a single Nebulang file.
     pub fn run_single_file(&mut self) -> Result<(), CompileError> {
         self.build_single_file("current")?;
-        self.execute_binary()
+        self.execute_binary(Stdio::inherit)
     }
 
     /// Discovers and executes tests on Nebulang files.
     ///
+    /// Every file is built and run (with `stdin_path` as its input, if set, or an empty
+    /// input so that reads see end of file), and passes if the program exits successfully.
+    ///
     /// # Arguments
     /// * `specific_file` - An optional path to run only a single test file.
-    pub fn test(&self, specific_file: Option<PathBuf>) -> Result<(), CompileError> {
+    pub fn test(&mut self, specific_file: Option<PathBuf>) -> Result<(), CompileError> {
         let files_to_test = if let Some(file) = specific_file {
             vec![file]
         } else {
@@ -156,8 +212,10 @@ impl Compiler {
 
         let mut all_passed = true;
         for file in files_to_test {
+            // Diagnostics are printed while testing, so the result line follows them.
+            let result = self.test_file(&file);
             print!("Testing {:?}... ", file);
-            match self.test_file(&file) {
+            match result {
                 Ok(_) => println!("✓ PASSED"),
                 Err(e) => {
                     println!("✗ FAILED");
@@ -207,7 +265,7 @@ impl Compiler {
 
         match link_result {
             Ok(_) => {
-                println!("📦 Quantum binary generated: {:?}", output_path);
+                self.log_status(&format!("📦 Quantum binary generated: {:?}", output_path));
                 self.make_executable(&output_path)?;
                 Ok(())
             }
@@ -264,7 +322,10 @@ impl Compiler {
             .map_err(CompileError::ExecutionError)?;
 
         if status.success() {
-            println!("✅ Binary linked successfully with {}", alternative_linker);
+            self.log_status(&format!(
+                "✅ Binary linked successfully with {}",
+                alternative_linker
+            ));
             Ok(())
         } else {
             Err(CompileError::ExecutionFailed(status))
@@ -272,14 +333,24 @@ impl Compiler {
     }
 
     /// Executes the final compiled binary.
-    fn execute_binary(&self) -> Result<(), CompileError> {
+    ///
+    /// # Arguments
+    ///
+    /// * `default_stdin` - The standard input of the program when `stdin_path` is not set.
+    fn execute_binary(&self, default_stdin: fn() -> Stdio) -> Result<(), CompileError> {
         let binary_path = self.build_path.join(self.get_output_name());
 
         if !binary_path.exists() {
             return Err(CompileError::BinaryNotFound);
         }
 
+        let stdin = match &self.stdin_path {
+            Some(path) => Stdio::from(std::fs::File::open(path)?),
+            None => default_stdin(),
+        };
         let status = std::process::Command::new(&binary_path)
+            .args(&self.program_args)
+            .stdin(stdin)
             .status()
             .map_err(CompileError::ExecutionError)?;
 
@@ -294,13 +365,13 @@ impl Compiler {
     fn find_neb_files_in_directory(&self) -> Result<Vec<PathBuf>, CompileError> {
         let mut files = Vec::new();
 
-        if self.source_path.is_dir() {
-            if let Ok(entries) = std::fs::read_dir(&self.source_path) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_file() && self.is_neb_file(&path) {
-                        files.push(path);
-                    }
+        if self.source_path.is_dir()
+            && let Ok(entries) = std::fs::read_dir(&self.source_path)
+        {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file() && self.is_neb_file(&path) {
+                    files.push(path);
                 }
             }
         }
@@ -312,18 +383,128 @@ impl Compiler {
         }
     }
 
-    /// Stub function to run a specific file in test mode (currently only performs parse).
-    fn test_file(&self, file_path: &PathBuf) -> Result<(), CompileError> {
-        let content = std::fs::read_to_string(file_path)?;
-        let tokens = tokenize(&content)?;
-        let _ast = parse(tokens)?;
-        // NOTE: A complete test would also execute the binary and verify its output/exit code.
-        Ok(())
+    /// Tests a specific file: builds it and runs the binary, which must exit successfully.
+    fn test_file(&mut self, file_path: &Path) -> Result<(), CompileError> {
+        self.source_path = file_path.to_path_buf();
+        self.build_single_file("current")?;
+        // Tests never wait for someone to type; without `--stdin`, reads see end of file.
+        self.execute_binary(Stdio::null)
+    }
+
+    /// Prints the diagnostics for a compile error, in source order.
+    ///
+    /// Diagnostics are rendered to stderr, or printed to stdout as JSON lines with
+    /// `--message-format=json`. At most `error_limit` diagnostics are printed; the
+    /// rest are only counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error (or errors) raised by one of the compilation phases.
+    /// * `file` - The name of the source file, as shown to the user.
+    /// * `source_map` - The line index of the source file.
+    ///
+    /// # Returns
+    ///
+    /// A [`CompileError::Reported`] summarizing the failed compilation.
+    fn report(&self, error: CompileError, file: &str, source_map: &SourceMap) -> CompileError {
+        let mut errors = error.into_errors();
+        errors.sort_by_key(|error| error.span().map_or(usize::MAX, |span| span.start));
+
+        let shown = self.error_limit.unwrap_or(usize::MAX).min(errors.len());
+        let diagnostics: Vec<Diagnostic> =
+            errors[..shown].iter().map(Diagnostic::from_error).collect();
+
+        match self.message_format {
+            MessageFormat::Human => {
+                Self::print_diagnostics(
+                    &diagnostics,
+                    errors.len() - shown,
+                    &Renderer::new(file, source_map, self.color.enabled()),
+                );
+            }
+            MessageFormat::Json => {
+                let emitter = JsonEmitter::new(file, source_map);
+                for diagnostic in &diagnostics {
+                    println!("{}", emitter.emit(diagnostic));
+                }
+            }
+        }
+
+        CompileError::Reported {
+            file: file.to_string(),
+            count: errors.len(),
+        }
+    }
+
+    /// Prints the warnings found in a file that compiled.
+    ///
+    /// Warnings are printed like error diagnostics, but do not fail the build.
+    ///
+    /// # Arguments
+    ///
+    /// * `warnings` - The warnings, in source order.
+    /// * `file` - The name of the source file, as shown to the user.
+    /// * `source_map` - The line index of the source file.
+    fn warn(&self, warnings: &[Diagnostic], file: &str, source_map: &SourceMap) {
+        match self.message_format {
+            MessageFormat::Human => {
+                let renderer = Renderer::new(file, source_map, self.color.enabled());
+                for warning in warnings {
+                    eprintln!("{}", renderer.render(warning));
+                }
+            }
+            MessageFormat::Json => {
+                let emitter = JsonEmitter::new(file, source_map);
+                for warning in warnings {
+                    println!("{}", emitter.emit(warning));
+                }
+            }
+        }
+    }
+
+    /// Renders diagnostics to stderr, followed by pointers to `nebc explain`.
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics` - The diagnostics to print.
+    /// * `hidden` - The number of further errors cut off by `--error-limit`.
+    /// * `renderer` - The renderer for the source file.
+    fn print_diagnostics(diagnostics: &[Diagnostic], hidden: usize, renderer: &Renderer) {
+        for diagnostic in diagnostics {
+            eprintln!("{}", renderer.render(diagnostic));
+        }
+
+        if hidden > 0 {
+            eprintln!(
+                "note: {} more error(s) not shown; raise --error-limit to see them\n",
+                hidden
+            );
+        }
+
+        let mut codes: Vec<&str> = diagnostics
+            .iter()
+            .filter_map(|diagnostic| diagnostic.code)
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        match codes.as_slice() {
+            [] => {}
+            [code] => eprintln!(
+                "For more information about this error, try `nebc explain {}`.",
+                code
+            ),
+            codes => eprintln!(
+                "Some errors have detailed explanations: {}.\n\
+                 For more information about an error, try `nebc explain {}`.",
+                codes.join(", "),
+                codes[0]
+            ),
+        }
     }
 
     /// Checks if a given path has the `.neb` extension.
-    fn is_neb_file(&self, path: &PathBuf) -> bool {
-        path.extension().map_or(false, |ext| ext == "neb")
+    fn is_neb_file(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "neb")
     }
 
     /// Determines the final executable name based on the target platform.
@@ -367,8 +548,17 @@ impl Compiler {
     }
 
     /// Prints a message only if verbose mode is enabled.
+    ///
+    /// Nothing is printed with JSON messages, which keep stdout machine-readable.
     fn log_verbose(&self, message: &str) {
-        if self.verbose {
+        if self.verbose && self.message_format == MessageFormat::Human {
+            println!("{}", message);
+        }
+    }
+
+    /// Prints a progress message, unless JSON messages were requested.
+    fn log_status(&self, message: &str) {
+        if self.message_format == MessageFormat::Human {
             println!("{}", message);
         }
     }
@@ -12,7 +12,7 @@ pub mod statement_parser;
 
 use crate::ast::nodes::Program;
 use crate::compiler::error::CompileError;
-use crate::compiler::lexer::Token;
+use crate::compiler::lexer::SpannedToken;
 
 /// The main entry point for the parsing phase.
 ///
@@ -27,7 +27,7 @@ use crate::compiler::lexer::Token;
 /// # Returns
 ///
 /// A `Result` containing the root [`Program`] AST node or a [`CompileError`].
-pub fn parse(tokens: Vec<(Token, usize, usize, String)>) -> Result<Program, CompileError> {
+pub fn parse(tokens: Vec<SpannedToken>) -> Result<Program, CompileError> {
     use common::Parser;
 
     // Create the parser instance with the token stream.
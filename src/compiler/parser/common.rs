@@ -9,7 +9,9 @@
 use super::statement_parser::StatementParser;
 use crate::ast::nodes::Program;
 use crate::compiler::error::CompileError;
-use crate::compiler::lexer::Token;
+use crate::compiler::lexer::{SpannedToken, Token};
+use crate::compiler::span::Span;
+use std::collections::HashSet;
 
 /// The main parser structure for the Nebulang compiler.
 ///
@@ -17,12 +19,16 @@ use crate::compiler::lexer::Token;
 pub struct Parser {
     /// The sequence of tokens produced by the lexer. Each entry includes:
     /// 1. The [`Token`] type.
-    /// 2. Start line index.
-    /// 3. End line index.
-    /// 4. The raw string value (lexeme).
-    pub tokens: Vec<(Token, usize, usize, String)>,
+    /// 2. The [`Span`] of source bytes it covers.
+    /// 3. The raw string value (lexeme).
+    pub tokens: Vec<SpannedToken>,
     /// The index of the next token to be consumed.
     pub position: usize,
+    /// The syntax errors recovered from so far; parsing resumes at the next statement.
+    pub errors: Vec<CompileError>,
+    /// The variables declared so far in the current scope (the top level or a function
+    /// body), so that `name value` on one of them is parsed as a reassignment.
+    pub declared: HashSet<String>,
 }
 
 impl Parser {
@@ -31,20 +37,23 @@ impl Parser {
     /// # Arguments
     ///
     /// * `tokens` - The token stream received from the lexer.
-    pub fn new(tokens: Vec<(Token, usize, usize, String)>) -> Self {
+    pub fn new(tokens: Vec<SpannedToken>) -> Self {
         Self {
             tokens,
             position: 0,
+            errors: Vec::new(),
+            declared: HashSet::new(),
         }
     }
 
     /// The main entry point for parsing, responsible for building the root [`Program`] AST node.
     ///
     /// It repeatedly attempts to parse statements until the end of the token stream is reached.
+    /// Statements with syntax errors are skipped, so that every error in the file is reported.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the fully constructed [`Program`] AST or a [`CompileError`].
+    /// A `Result` containing the fully constructed [`Program`] AST, or all syntax errors found.
     pub fn parse_program(&mut self) -> Result<Program, CompileError> {
         let mut statements = Vec::new();
 
@@ -61,7 +70,60 @@ impl Parser {
             }
         }
 
-        Ok(Program { statements })
+        if self.errors.is_empty() {
+            Ok(Program { statements })
+        } else {
+            Err(CompileError::from_errors(std::mem::take(&mut self.errors)))
+        }
+    }
+
+    /// Records a syntax error and skips to the start of the next statement (panic-mode recovery).
+    ///
+    /// Tokens are discarded up to the end of the current line. If the broken statement
+    /// opened an indented block, the whole block is skipped with it. A `Dedent` is left
+    /// in place, so that the enclosing block still ends where it should.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error that interrupted the statement.
+    pub fn recover(&mut self, error: CompileError) {
+        self.errors.push(error);
+
+        while !self.is_at_end() {
+            match self.peek().0 {
+                Token::Dedent => return,
+                Token::Indent => {
+                    self.skip_block();
+                    return;
+                }
+                Token::Newline => {
+                    while self.check(Token::Newline) {
+                        self.advance();
+                    }
+                    if self.check(Token::Indent) {
+                        self.skip_block();
+                    }
+                    return;
+                }
+                _ => self.advance(),
+            }
+        }
+    }
+
+    /// Skips an indented block, including any nested blocks and its closing `Dedent`.
+    fn skip_block(&mut self) {
+        let mut depth = 0;
+        while !self.is_at_end() {
+            match self.peek().0 {
+                Token::Indent => depth += 1,
+                Token::Dedent => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+            if depth == 0 {
+                return;
+            }
+        }
     }
 
     /// Provides a lookahead mechanism to inspect the current token without consuming it.
@@ -71,16 +133,27 @@ impl Parser {
     /// # Returns
     ///
     /// A reference to the current token tuple.
-    pub fn peek(&self) -> &(Token, usize, usize, String) {
+    pub fn peek(&self) -> &SpannedToken {
         if self.position < self.tokens.len() {
             &self.tokens[self.position]
         } else {
             // Static EOF placeholder
-            static EOF: (Token, usize, usize, String) = (Token::Newline, 0, 0, String::new());
+            static EOF: SpannedToken = (Token::Newline, Span::new(0, 0), String::new());
             &EOF
         }
     }
 
+    /// Looks one token past the current one without consuming anything.
+    ///
+    /// # Returns
+    ///
+    /// The token after the current one, or `None` at the end of the stream.
+    pub fn peek_next(&self) -> Option<&Token> {
+        self.tokens
+            .get(self.position + 1)
+            .map(|(token, _, _)| token)
+    }
+
     /// Consumes the current token and advances the parser to the next position.
     pub fn advance(&mut self) {
         if self.position < self.tokens.len() {
@@ -117,11 +190,61 @@ impl Parser {
             self.advance();
             Ok(())
         } else {
-            let (found, start, _, _) = self.peek();
+            let found = &self.peek().0;
             let message = format!("Expected {:?}, found {:?}", expected, found);
-            // In a real compiler, 'start' would be used to point to the error location.
-            Err(CompileError::parser(message))
+            Err(self.error(message))
+        }
+    }
+
+    /// Returns the span of the current token.
+    ///
+    /// At the end of the stream, this is an empty span just after the last token.
+    pub fn current_span(&self) -> Span {
+        match self.tokens.get(self.position) {
+            Some((_, span, _)) => *span,
+            None => {
+                let end = self.tokens.last().map_or(0, |(_, span, _)| span.end);
+                Span::new(end, end)
+            }
+        }
+    }
+
+    /// Returns the span from `start` to the end of the most recently consumed token.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The byte offset where the construct being parsed begins.
+    pub fn span_from(&self, start: usize) -> Span {
+        let end = self
+            .position
+            .checked_sub(1)
+            .and_then(|previous| self.tokens.get(previous))
+            .map_or(start, |(_, span, _)| span.end);
+        Span::new(start, end.max(start))
+    }
+
+    /// Constructs a parser error pointing at the current token.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The description of the problem.
+    pub fn error(&self, message: impl Into<String>) -> CompileError {
+        CompileError::syntax(self.current_span(), message)
+    }
+
+    /// Checks whether the current token starts exactly where the previous one ended.
+    ///
+    /// This distinguishes a call `add(1, 2)` from a declaration with a grouped value
+    /// such as `total (a + b)`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if there is no whitespace between the previous and the current token.
+    pub fn is_adjacent(&self) -> bool {
+        if self.position == 0 || self.is_at_end() {
+            return false;
         }
+        self.tokens[self.position - 1].1.end == self.tokens[self.position].1.start
     }
 
     /// Checks if the parser has consumed all available tokens.
@@ -5,7 +5,7 @@
 //! technique for handling binary operations and respecting operator precedence and associativity.
 
 use super::common::Parser;
-use crate::ast::nodes::{BinaryOperator, Expression};
+use crate::ast::nodes::{BinaryOperator, Expression, ExpressionKind, UnaryOperator};
 use crate::compiler::error::CompileError;
 use crate::compiler::lexer::Token;
 
@@ -65,11 +65,15 @@ impl ExpressionParser {
             let right = Self::parse_binary_expression(parser, op_precedence + 1)?;
 
             // Combine the current expression and the newly parsed right expression.
-            left = Expression::Binary {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
-            };
+            let span = left.span.to(right.span);
+            left = Expression::new(
+                ExpressionKind::Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                },
+                span,
+            );
         }
 
         Ok(left)
@@ -100,6 +104,11 @@ impl ExpressionParser {
             Token::GreaterEqual => Some(BinaryOperator::GreaterEqual),
             Token::And => Some(BinaryOperator::And),
             Token::Or => Some(BinaryOperator::Or),
+            Token::BitAnd => Some(BinaryOperator::BitAnd),
+            Token::BitOr => Some(BinaryOperator::BitOr),
+            Token::Tilde => Some(BinaryOperator::BitXor),
+            Token::ShiftLeft => Some(BinaryOperator::ShiftLeft),
+            Token::ShiftRight => Some(BinaryOperator::ShiftRight),
             _ => None,
         }
     }
@@ -107,6 +116,7 @@ impl ExpressionParser {
     /// Returns the precedence level for a given binary operator. Higher numbers mean tighter binding.
     ///
     /// The levels are designed to follow standard mathematical and logical precedence rules.
+    /// Bitwise operators bind tighter than comparisons, so `n & 1 == 0` tests `(n & 1) == 0`.
     ///
     /// # Arguments
     ///
@@ -117,9 +127,13 @@ impl ExpressionParser {
     /// A `u8` representing the operator's precedence level.
     fn get_precedence(operator: &BinaryOperator) -> u8 {
         match operator {
-            BinaryOperator::Power => 7,
-            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 6,
-            BinaryOperator::Add | BinaryOperator::Subtract => 5,
+            BinaryOperator::Power => 11,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 10,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::BitAnd => 7,
+            BinaryOperator::BitXor => 6,
+            BinaryOperator::BitOr => 5,
             BinaryOperator::Less
             | BinaryOperator::Greater
             | BinaryOperator::LessEqual
@@ -130,9 +144,103 @@ impl ExpressionParser {
         }
     }
 
+    /// Parses a parenthesized, comma-separated argument list (e.g., `(a, b + 1)`).
+    ///
+    /// The parser must be positioned on the opening parenthesis.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The mutable parser instance.
+    ///
+    /// # Returns
+    ///
+    /// The argument expressions, in call order.
+    pub fn parse_call_arguments(parser: &mut Parser) -> Result<Vec<Expression>, CompileError> {
+        parser.expect(Token::ParenOpen)?;
+
+        let mut arguments = Vec::new();
+        if !parser.check(Token::ParenClose) {
+            loop {
+                arguments.push(Self::parse_expression(parser)?);
+                if parser.check(Token::Comma) {
+                    parser.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        parser.expect(Token::ParenClose)?;
+        Ok(arguments)
+    }
+
+    /// Parses the remainder of a collection path after its root (e.g., `:col:0` or `:b{i}`).
+    ///
+    /// Each `:name` selects a sub-collection; a trailing `:N` integer or an adjacent
+    /// `{expr}` selects an element.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The mutable parser instance, positioned after the root.
+    /// * `root` - The root alias or name.
+    /// * `by_name` - Whether the root was written as `[]name`.
+    /// * `start` - The byte offset where the path begins.
+    fn parse_collection_path(
+        parser: &mut Parser,
+        root: String,
+        by_name: bool,
+        start: usize,
+    ) -> Result<Expression, CompileError> {
+        let mut path = Vec::new();
+        let mut index = None;
+
+        while parser.check(Token::Colon) {
+            parser.advance(); // Consume ':'
+            let (token, span, _) = parser.peek();
+            match token {
+                Token::Identifier(segment) => {
+                    path.push(segment.clone());
+                    parser.advance();
+                }
+                Token::Integer(n) => {
+                    index = Some(Box::new(Expression::new(
+                        ExpressionKind::Integer(*n),
+                        *span,
+                    )));
+                    parser.advance();
+                    break;
+                }
+                other => {
+                    return Err(parser.error(format!(
+                        "Expected a collection name or index after ':', found {:?}",
+                        other
+                    )));
+                }
+            }
+        }
+
+        // Computed index (e.g., c:b{i}); the '{' must touch the path.
+        if index.is_none() && parser.check(Token::BraceOpen) && parser.is_adjacent() {
+            parser.advance();
+            index = Some(Box::new(Self::parse_expression(parser)?));
+            parser.expect(Token::BraceClose)?;
+        }
+
+        Ok(Expression::new(
+            ExpressionKind::CollectionAccess {
+                root,
+                by_name,
+                path,
+                index,
+            },
+            parser.span_from(start),
+        ))
+    }
+
     /// Parses the most basic, non-binary components of an expression (literals, variables, groups).
     ///
-    /// This also handles implicit unary operations like negation (`-`).
+    /// This also handles the prefix operators (`-`, `NOT`, `~`), which bind tighter than any
+    /// binary operator.
     ///
     /// # Arguments
     ///
@@ -142,95 +250,250 @@ impl ExpressionParser {
     ///
     /// A simple [`Expression`] node.
     fn parse_primary(parser: &mut Parser) -> Result<Expression, CompileError> {
+        let start = parser.current_span().start;
         match &parser.peek().0 {
-            // Unary Minus: Treat as multiplication by -1.
-            Token::Minus => {
+            // Unary operators: negation (`-x`), logical not (`NOT x`), bitwise complement (`~x`)
+            Token::Minus | Token::Not | Token::Tilde => {
+                let operator = match parser.peek().0 {
+                    Token::Minus => UnaryOperator::Negate,
+                    Token::Not => UnaryOperator::Not,
+                    _ => UnaryOperator::BitNot,
+                };
                 parser.advance();
-                let expr = Self::parse_primary(parser)?;
-                // Rewrites `-X` as `(-1 * X)`
-                Ok(Expression::Binary {
-                    left: Box::new(Expression::Integer(-1)),
-                    operator: BinaryOperator::Multiply,
-                    right: Box::new(expr),
-                })
+                let operand = Self::parse_primary(parser)?;
+                Ok(Expression::new(
+                    ExpressionKind::Unary {
+                        operator,
+                        operand: Box::new(operand),
+                    },
+                    parser.span_from(start),
+                ))
             }
-            // Unary Caret (^): Placeholder logic (often used for unary negation/bitwise complement,
-            // but here it seems to be incorrectly used or a placeholder for a specific language feature).
-            // NOTE: The current implementation has a placeholder Left-hand side (Integer 0) for a BinaryOperator::Power.
-            Token::Caret => {
+            // `!x` from other languages: `!` starts a print statement.
+            Token::Print => Err(parser
+                .error("Expected expression, found Print")
+                .with_help("use `NOT` for logical not")),
+            // Length prefix (e.g., `#scores`)
+            Token::Hash => {
                 parser.advance();
                 let expr = Self::parse_primary(parser)?;
-                Ok(Expression::Binary {
-                    left: Box::new(Expression::Integer(0)), // Placeholder or error-prone logic
-                    operator: BinaryOperator::Power,
-                    right: Box::new(expr),
-                })
+                Ok(Expression::new(
+                    ExpressionKind::Length(Box::new(expr)),
+                    parser.span_from(start),
+                ))
             }
             // Literal Integers
             Token::Integer(n) => {
                 let value = *n;
                 parser.advance();
-                Ok(Expression::Integer(value))
+                Ok(Expression::new(
+                    ExpressionKind::Integer(value),
+                    parser.span_from(start),
+                ))
+            }
+            // Literal Floats
+            Token::Float(x) => {
+                let value = *x;
+                parser.advance();
+                Ok(Expression::new(
+                    ExpressionKind::Float(value),
+                    parser.span_from(start),
+                ))
             }
             // Literal Strings
             Token::StringLiteral(s) => {
                 let value = s.clone();
                 parser.advance();
-                Ok(Expression::String(value))
+                Ok(Expression::new(
+                    ExpressionKind::String(value),
+                    parser.span_from(start),
+                ))
             }
             // Literal Booleans
             Token::Boolean(b) => {
                 let value = *b;
                 parser.advance();
-                Ok(Expression::Boolean(value))
+                Ok(Expression::new(
+                    ExpressionKind::Boolean(value),
+                    parser.span_from(start),
+                ))
             }
             // Identifiers (Variables or Array Access)
             Token::Identifier(name) => {
                 let name = name.clone();
                 parser.advance();
 
-                // Check for array access syntax (e.g., array_name{index})
-                if parser.check(Token::BraceOpen) {
+                // Check for a collection path (e.g., c:col:0)
+                if parser.check(Token::Colon) {
+                    return Self::parse_collection_path(parser, name, false, start);
+                }
+
+                // Check for function call syntax (e.g., add(1, 2)); the '(' must touch the name.
+                if parser.check(Token::ParenOpen) && parser.is_adjacent() {
+                    let arguments = Self::parse_call_arguments(parser)?;
+                    return Ok(Expression::new(
+                        ExpressionKind::Call {
+                            function: name,
+                            arguments,
+                        },
+                        parser.span_from(start),
+                    ));
+                }
+
+                // Check for array access syntax (e.g., array_name{index}); the '{' must touch the name.
+                if parser.check(Token::BraceOpen) && parser.is_adjacent() {
                     parser.advance();
                     // Array access index is treated as an expression
                     let index_expr = Self::parse_expression(parser)?;
+
+                    // A range selects a substring (e.g., name{0..2})
+                    if parser.check(Token::Range) {
+                        parser.advance();
+                        let end_expr = Self::parse_expression(parser)?;
+                        parser.expect(Token::BraceClose)?;
+                        return Ok(Expression::new(
+                            ExpressionKind::Slice {
+                                string: name,
+                                start: Box::new(index_expr),
+                                end: Box::new(end_expr),
+                            },
+                            parser.span_from(start),
+                        ));
+                    }
                     parser.expect(Token::BraceClose)?;
 
-                    // NOTE: The current implementation rewrites array access `array{index}` as a binary expression `array + index`.
-                    // This is incorrect for typical array access which should return `Expression::ArrayAccess`.
-                    // It's assumed to be a temporary language design choice or a bug.
-                    Ok(Expression::Binary {
-                        left: Box::new(Expression::Variable(name)),
-                        operator: BinaryOperator::Add,
-                        right: Box::new(index_expr),
-                    })
+                    Ok(Expression::new(
+                        ExpressionKind::ArrayAccess {
+                            array: name,
+                            index: Box::new(index_expr),
+                        },
+                        parser.span_from(start),
+                    ))
                 } else {
                     // Simple variable access
-                    Ok(Expression::Variable(name))
+                    Ok(Expression::new(
+                        ExpressionKind::Variable(name),
+                        parser.span_from(start),
+                    ))
+                }
+            }
+            // Collection path with an explicit root name (e.g., []cars:b:2)
+            Token::BracketOpen if parser.peek_next() == Some(&Token::BracketClose) => {
+                parser.advance(); // Consume '['
+                parser.advance(); // Consume ']'
+                let root = parser.get_identifier();
+                if root.is_empty() {
+                    let found = parser.peek().0.clone();
+                    return Err(parser.error(format!(
+                        "Expected collection name after '[]', found {:?}",
+                        found
+                    )));
                 }
+                parser.advance();
+                Self::parse_collection_path(parser, root, true, start)
             }
             // Grouping with curly braces (BraceOpen/BraceClose)
             Token::BraceOpen => {
                 parser.advance();
-                let expr = Self::parse_expression(parser)?;
+                let mut expr = Self::parse_expression(parser)?;
                 parser.expect(Token::BraceClose)?;
+                // The group covers its delimiters, so that errors underline `(a + b)`.
+                expr.span = parser.span_from(start);
                 Ok(expr)
             }
             // Grouping with parentheses (ParenOpen/ParenClose)
             Token::ParenOpen => {
                 parser.advance();
-                let expr = Self::parse_expression(parser)?;
+                let mut expr = Self::parse_expression(parser)?;
                 parser.expect(Token::ParenClose)?;
+                // The group covers its delimiters, so that errors underline `(a + b)`.
+                expr.span = parser.span_from(start);
                 Ok(expr)
             }
             // Error case: Found a token that does not start an expression.
             _ => {
                 let token = parser.peek().0.clone();
-                Err(CompileError::parser(format!(
-                    "Expected expression, found {:?}",
-                    token
-                )))
+                Err(parser.error(format!("Expected expression, found {:?}", token)))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::lexer::tokenize;
+
+    /// Parses `source` as one expression and writes it back fully parenthesized.
+    fn grouped(source: &str) -> String {
+        let mut parser = Parser::new(tokenize(source).expect("source should tokenize"));
+        let expression = ExpressionParser::parse_expression(&mut parser).expect("should parse");
+        assert!(parser.is_at_end(), "`{}` was not parsed completely", source);
+        write_grouped(&expression)
+    }
+
+    /// Writes an expression with every unary and binary operation in parentheses.
+    fn write_grouped(expression: &Expression) -> String {
+        match &expression.kind {
+            ExpressionKind::Integer(value) => value.to_string(),
+            ExpressionKind::Boolean(value) => value.to_string(),
+            ExpressionKind::Variable(name) => name.clone(),
+            ExpressionKind::Unary { operator, operand } => {
+                format!("({:?} {})", operator, write_grouped(operand))
+            }
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => format!(
+                "({} {:?} {})",
+                write_grouped(left),
+                operator,
+                write_grouped(right)
+            ),
+            other => panic!("unexpected expression {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arithmetic_binds_tighter_than_comparison() {
+        assert_eq!(grouped("a + b * c"), "(a Add (b Multiply c))");
+        assert_eq!(grouped("a - b - c"), "((a Subtract b) Subtract c)");
+        assert_eq!(grouped("a * b ^ 2"), "(a Multiply (b Power 2))");
+        assert_eq!(grouped("a + 1 < b * 2"), "((a Add 1) Less (b Multiply 2))");
+        assert_eq!(
+            grouped("a < b == c > d"),
+            "((a Less b) Equal (c Greater d))"
+        );
+    }
+
+    #[test]
+    fn logical_operators_bind_loosest() {
+        assert_eq!(
+            grouped("a == 1 OR b == 2 AND c"),
+            "((a Equal 1) Or ((b Equal 2) And c))"
+        );
+        assert_eq!(grouped("a != b AND c"), "((a NotEqual b) And c)");
+    }
+
+    #[test]
+    fn bitwise_operators_sit_between_arithmetic_and_comparison() {
+        assert_eq!(grouped("n & 1 == 0"), "((n BitAnd 1) Equal 0)");
+        assert_eq!(
+            grouped("a | b ~ c & d"),
+            "(a BitOr (b BitXor (c BitAnd d)))"
+        );
+        assert_eq!(grouped("a << 1 + b"), "(a ShiftLeft (1 Add b))");
+        assert_eq!(grouped("a >> 2 & m"), "((a ShiftRight 2) BitAnd m)");
+    }
+
+    #[test]
+    fn unary_operators_apply_to_the_next_operand() {
+        assert_eq!(grouped("-x * 2"), "((Negate x) Multiply 2)");
+        assert_eq!(grouped("NOT a AND b"), "((Not a) And b)");
+        assert_eq!(grouped("~m & 7"), "((BitNot m) BitAnd 7)");
+        assert_eq!(grouped("- -x"), "(Negate (Negate x))");
+        // A negative literal is folded by the lexer rather than negated at run time.
+        assert_eq!(grouped("-3 + x"), "(-3 Add x)");
+    }
+}
@@ -11,6 +11,7 @@ use super::expression_parser::ExpressionParser;
 use crate::ast::nodes::*;
 use crate::compiler::error::CompileError;
 use crate::compiler::lexer::Token;
+use crate::compiler::span::Span;
 
 /// A static utility struct dedicated to parsing statements and building statement AST nodes.
 pub struct StatementParser;
@@ -41,11 +42,11 @@ impl StatementParser {
             }
 
             // Attempt to parse a single statement.
-            if let Some(statement) = Self::parse_statement(parser)? {
-                statements.push(statement);
-            } else {
+            match Self::parse_statement(parser) {
+                Ok(Some(statement)) => statements.push(statement),
                 // If a token was not recognized as a statement, consume it and continue.
-                parser.advance();
+                Ok(None) => parser.advance(),
+                Err(error) => parser.recover(error),
             }
 
             // Consume trailing newlines/whitespace equivalents
@@ -76,119 +77,390 @@ impl StatementParser {
             Token::Print => Self::parse_print_statement(parser),
             Token::Loop => Self::parse_loop_statement(parser),
             Token::If => Self::parse_if_statement(parser),
+            Token::Hash => Self::parse_function_definition(parser),
+            Token::BracketOpen => {
+                let collection = Self::parse_collection(parser)?;
+                let span = collection.span;
+                Ok(Some(Statement::new(
+                    StatementKind::CollectionDeclaration { collection },
+                    span,
+                )))
+            }
+            Token::Return => Self::parse_return_statement(parser),
+            Token::Break | Token::Continue => Self::parse_loop_control(parser),
             _ => Ok(None),
         }
     }
 
+    /// Parses an indented block of statements (`Indent ... Dedent`).
+    ///
+    /// Any newlines before the block are consumed. If no indented block follows,
+    /// an empty body is returned.
+    fn parse_block(parser: &mut Parser) -> Result<Vec<Statement>, CompileError> {
+        // Consume any newlines before the block
+        while parser.check(Token::Newline) {
+            parser.advance();
+        }
+
+        let mut body = Vec::new();
+        if parser.check(Token::Indent) {
+            parser.advance(); // Consume 'Indent'
+            while !parser.check(Token::Dedent) && !parser.is_at_end() {
+                match Self::parse_statement(parser) {
+                    Ok(Some(statement)) => body.push(statement),
+                    Ok(None) => parser.advance(),
+                    Err(error) => parser.recover(error),
+                }
+            }
+            if parser.check(Token::Dedent) {
+                parser.advance(); // Consume 'Dedent'
+            }
+        }
+        Ok(body)
+    }
+
+    /// Parses a function definition (e.g., `# add(a, b)` followed by an indented body).
+    fn parse_function_definition(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let start = parser.current_span().start;
+        parser.advance(); // Consume '#'
+
+        let name = parser.get_identifier();
+        if name.is_empty() {
+            let found = parser.peek().0.clone();
+            return Err(parser.error(format!(
+                "Expected function name after '#', found {:?}",
+                found
+            )));
+        }
+        parser.advance(); // Consume function name
+
+        // Parse the parameter list: `(a, b, ...)`
+        parser.expect(Token::ParenOpen)?;
+        let mut parameters = Vec::new();
+        while !parser.check(Token::ParenClose) && !parser.is_at_end() {
+            let parameter = parser.get_identifier();
+            if parameter.is_empty() {
+                let found = parser.peek().0.clone();
+                return Err(parser.error(format!("Expected parameter name, found {:?}", found)));
+            }
+            parser.advance();
+            parameters.push(parameter);
+
+            if parser.check(Token::Comma) {
+                parser.advance();
+            } else {
+                break;
+            }
+        }
+        parser.expect(Token::ParenClose)?;
+        let span = parser.span_from(start);
+
+        // The body has its own scope, starting with the parameters.
+        let scope = parameters.iter().cloned().collect();
+        let outer_scope = std::mem::replace(&mut parser.declared, scope);
+        let body = Self::parse_block(parser);
+        parser.declared = outer_scope;
+        let body = body?;
+
+        Ok(Some(Statement::new(
+            StatementKind::Function {
+                name,
+                parameters,
+                body,
+            },
+            span,
+        )))
+    }
+
+    /// Parses a return statement (`=> value` or a bare `=>`).
+    fn parse_return_statement(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let start = parser.current_span().start;
+        parser.advance(); // Consume '=>'
+
+        let value =
+            if parser.is_at_end() || matches!(parser.peek().0, Token::Newline | Token::Dedent) {
+                None
+            } else {
+                Some(Box::new(ExpressionParser::parse_expression(parser)?))
+            };
+
+        Ok(Some(Statement::new(
+            StatementKind::Return { value },
+            parser.span_from(start),
+        )))
+    }
+
+    /// Parses a `break` or `continue` statement.
+    fn parse_loop_control(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let kind = match parser.peek().0 {
+            Token::Break => StatementKind::Break,
+            _ => StatementKind::Continue,
+        };
+        let span = parser.current_span();
+        parser.advance(); // Consume 'break' or 'continue'
+        Ok(Some(Statement::new(kind, span)))
+    }
+
     /// Parses a statement starting with an `Identifier`, which could be a variable declaration
     /// (e.g., `x 10`) or a compound assignment (e.g., `x += 5`).
     fn parse_variable_or_assignment(
         parser: &mut Parser,
     ) -> Result<Option<Statement>, CompileError> {
+        let start = parser.current_span().start;
         let name = parser.get_identifier();
         parser.advance(); // Consume the identifier
 
+        // Check for a call statement (e.g., `greet("Joe")`); the '(' must touch the name.
+        if parser.check(Token::ParenOpen) && parser.is_adjacent() {
+            let arguments = ExpressionParser::parse_call_arguments(parser)?;
+            return Ok(Some(Statement::new(
+                StatementKind::Call {
+                    function: name,
+                    arguments,
+                },
+                parser.span_from(start),
+            )));
+        }
+
         // Check for array declaration syntax (e.g., `array_name [ ... ]`)
         if parser.check(Token::BracketOpen) {
-            return Self::parse_array_declaration(parser, name);
+            parser.declared.insert(name.clone());
+            return Self::parse_array_declaration(parser, name, start);
         }
 
-        // Check for compound assignment operators
-        if parser.check(Token::MultiplyAssign) {
-            parser.advance();
+        // Check for element assignment (e.g., `scores{2} 95`); the '{' must touch the name.
+        if parser.check(Token::BraceOpen) && parser.is_adjacent() {
+            parser.advance(); // Consume '{'
+            let index = ExpressionParser::parse_expression(parser)?;
+            parser.expect(Token::BraceClose)?;
+            if let Some(operator) = Self::compound_operator(&parser.peek().0) {
+                return Err(parser
+                    .error(format!(
+                        "Compound assignment '{}' requires a variable name",
+                        operator.symbol()
+                    ))
+                    .with_help(format!(
+                        "write the element out, e.g. `{0}{{i}} {0}{{i}} + 1`",
+                        name
+                    )));
+            }
             let value = ExpressionParser::parse_expression(parser)?;
-            Ok(Some(Statement::Assignment {
-                name,
-                value: Box::new(value),
-                operator: AssignmentOperator::Multiply,
-            }))
-        } else if parser.check(Token::PlusAssign) {
+            return Ok(Some(Statement::new(
+                StatementKind::ArrayAssignment {
+                    array: name,
+                    index: Box::new(index),
+                    value: Box::new(value),
+                },
+                parser.span_from(start),
+            )));
+        }
+
+        // Check for compound assignment operators
+        let kind = if let Some(operator) = Self::compound_operator(&parser.peek().0) {
             parser.advance();
             let value = ExpressionParser::parse_expression(parser)?;
-            Ok(Some(Statement::Assignment {
+            StatementKind::Assignment {
                 name,
                 value: Box::new(value),
-                operator: AssignmentOperator::Plus,
-            }))
+                operator,
+            }
         } else {
-            // Assume simple variable declaration/re-assignment if no operator is found.
+            // No operator: a declaration, or a reassignment if the name is already declared.
             // The value must be an expression immediately following the identifier.
             let value = ExpressionParser::parse_expression(parser)?;
-            Ok(Some(Statement::VariableDeclaration {
-                name,
-                value: Box::new(value),
-            }))
+            if parser.declared.contains(&name) {
+                StatementKind::Assignment {
+                    name,
+                    value: Box::new(value),
+                    operator: AssignmentOperator::Assign,
+                }
+            } else {
+                parser.declared.insert(name.clone());
+                StatementKind::VariableDeclaration {
+                    name,
+                    value: Box::new(value),
+                }
+            }
+        };
+        Ok(Some(Statement::new(kind, parser.span_from(start))))
+    }
+
+    /// Maps a compound assignment token (e.g., `+=`) to its operator.
+    ///
+    /// # Returns
+    ///
+    /// The operator, or `None` if `token` is not a compound assignment.
+    fn compound_operator(token: &Token) -> Option<AssignmentOperator> {
+        match token {
+            Token::PlusAssign => Some(AssignmentOperator::Plus),
+            Token::MinusAssign => Some(AssignmentOperator::Minus),
+            Token::MultiplyAssign => Some(AssignmentOperator::Multiply),
+            Token::DivideAssign => Some(AssignmentOperator::Divide),
+            Token::ModuloAssign => Some(AssignmentOperator::Modulo),
+            Token::PowerAssign => Some(AssignmentOperator::Power),
+            _ => None,
         }
     }
 
-    /// Parses an array declaration statement (e.g., `list [ 1, "a", 3 ]`).
+    /// Parses an array declaration statement (e.g., `list [ 1, 2 + x, 3 ]`).
+    ///
+    /// Elements are full expressions separated by commas; the list may span several lines.
     fn parse_array_declaration(
         parser: &mut Parser,
         name: String,
+        start: usize,
     ) -> Result<Option<Statement>, CompileError> {
         parser.advance(); // Consume BracketOpen '['
 
         let mut elements = Vec::new();
 
-        while !parser.check(Token::BracketClose) && !parser.is_at_end() {
-            // Simplified logic to parse elements, primarily looking for literals (string, integer).
-            match &parser.peek().0 {
-                Token::Identifier(ident) if ident == "as" => {
-                    // Handle 'as' keyword (for potential type aliasing, currently skipped)
-                    parser.advance();
-                    let _alias = parser.get_identifier();
-                    parser.advance();
-                }
-                Token::Identifier(ident) => {
-                    // Treat bare identifiers inside array as string literals (simplification)
-                    let value = ident.clone();
+        loop {
+            Self::skip_layout(parser);
+            if parser.check(Token::BracketClose) || parser.is_at_end() {
+                break;
+            }
+
+            elements.push(ExpressionParser::parse_expression(parser)?);
+
+            Self::skip_layout(parser);
+            if parser.check(Token::Comma) {
+                parser.advance();
+            } else {
+                break;
+            }
+        }
+
+        parser.expect(Token::BracketClose)?;
+
+        Ok(Some(Statement::new(
+            StatementKind::ArrayDeclaration { name, elements },
+            parser.span_from(start),
+        )))
+    }
+
+    /// Parses a named collection (e.g., `[] cars as c`) with its indented body.
+    ///
+    /// The body holds either element lines (`"White", "Black"`) or nested
+    /// `[] name as alias` sub-collections, but not both.
+    fn parse_collection(parser: &mut Parser) -> Result<Collection, CompileError> {
+        let start = parser.current_span().start;
+        parser.expect(Token::BracketOpen)?;
+        parser.expect(Token::BracketClose)?;
+
+        let name = parser.get_identifier();
+        if name.is_empty() {
+            let found = parser.peek().0.clone();
+            return Err(parser.error(format!(
+                "Expected collection name after '[]', found {:?}",
+                found
+            )));
+        }
+        parser.advance(); // Consume collection name
+
+        // Optional alias: `as c`
+        let mut alias = None;
+        if matches!(&parser.peek().0, Token::Identifier(word) if word == "as") {
+            parser.advance(); // Consume 'as'
+            let alias_name = parser.get_identifier();
+            if alias_name.is_empty() {
+                let found = parser.peek().0.clone();
+                return Err(parser.error(format!("Expected alias after 'as', found {:?}", found)));
+            }
+            parser.advance();
+            alias = Some(alias_name);
+        }
+        let span = parser.span_from(start);
+
+        let mut elements = Vec::new();
+        let mut children = Vec::new();
+
+        while parser.check(Token::Newline) {
+            parser.advance();
+        }
+        if parser.check(Token::Indent) {
+            parser.advance(); // Consume 'Indent'
+            loop {
+                while parser.check(Token::Newline) {
                     parser.advance();
-                    elements.push(Expression::String(value));
                 }
-                Token::StringLiteral(s) => {
-                    let value = s.clone();
-                    parser.advance();
-                    elements.push(Expression::String(value));
+                if parser.check(Token::Dedent) || parser.is_at_end() {
+                    break;
                 }
-                Token::Integer(n) => {
-                    let value = *n;
-                    parser.advance();
-                    elements.push(Expression::Integer(value));
+
+                if parser.check(Token::BracketOpen) {
+                    children.push(Self::parse_collection(parser)?);
+                    continue;
                 }
-                _ => {
-                    // Skip unrecognized tokens inside the array
+
+                // An element line; a trailing comma continues the list on the next line.
+                loop {
+                    elements.push(ExpressionParser::parse_expression(parser)?);
+                    if !parser.check(Token::Comma) {
+                        break;
+                    }
                     parser.advance();
+                    while parser.check(Token::Newline) {
+                        parser.advance();
+                    }
                 }
             }
-
-            if parser.check(Token::Comma) {
-                parser.advance();
+            if parser.check(Token::Dedent) {
+                parser.advance(); // Consume 'Dedent'
             }
         }
 
-        parser.expect(Token::BracketClose)?;
+        if !elements.is_empty() && !children.is_empty() {
+            return Err(CompileError::syntax(
+                span,
+                format!("Collection '{}' mixes elements and sub-collections", name),
+            ));
+        }
 
-        Ok(Some(Statement::ArrayDeclaration { name, elements }))
+        Ok(Collection {
+            name,
+            alias,
+            elements,
+            children,
+            span,
+        })
+    }
+
+    /// Skips line-structure tokens (newlines and indentation) inside a bracketed list.
+    fn skip_layout(parser: &mut Parser) {
+        while matches!(
+            parser.peek().0,
+            Token::Newline | Token::Indent | Token::Dedent
+        ) && !parser.is_at_end()
+        {
+            parser.advance();
+        }
     }
 
     /// Parses the `print` statement, which can contain string literals, booleans, and interpolated expressions.
     fn parse_print_statement(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let start = parser.current_span().start;
         parser.advance(); // Consume 'print' token
         let mut parts = Vec::new();
 
         while !parser.is_at_end() && !matches!(parser.peek().0, Token::Newline) {
-            match &parser.peek().0 {
+            let (token, span, _) = parser.peek();
+            match token {
                 Token::StringLiteral(s) => {
-                    // Handle string literals and check for interpolation (e.g., "Hello {name}!")
-                    let interpolation_parts = Self::parse_string_interpolation(s);
+                    // Handle string literals and check for interpolation (e.g., "Hello {name}!").
+                    // The content starts just after the opening quote.
+                    let interpolation_parts = Self::parse_string_interpolation(s, span.start + 1);
                     parts.extend(interpolation_parts);
                     parser.advance();
                 }
                 Token::Boolean(b) => {
                     // Direct boolean literal output
                     let value = *b;
+                    let span = *span;
                     parser.advance();
-                    parts.push(PrintPart::Expression(Box::new(Expression::Boolean(value))));
+                    parts.push(PrintPart::Expression(Box::new(Expression::new(
+                        ExpressionKind::Boolean(value),
+                        span,
+                    ))));
                 }
                 Token::BraceOpen => {
                     // Explicit expression to print (e.g., `print {a + b}`)
@@ -201,17 +473,23 @@ impl StatementParser {
             }
         }
 
-        Ok(Some(Statement::Print { parts }))
+        Ok(Some(Statement::new(
+            StatementKind::Print { parts },
+            parser.span_from(start),
+        )))
     }
 
     /// Splits a string literal based on interpolation markers (`{...}`) and recursively
     /// attempts to parse the content inside the markers as expressions.
-    fn parse_string_interpolation(s: &str) -> Vec<PrintPart> {
+    ///
+    /// `offset` is the byte offset of the string content in the source file, so that
+    /// the spans of interpolated expressions point into the original string.
+    fn parse_string_interpolation(s: &str, offset: usize) -> Vec<PrintPart> {
         let mut parts = Vec::new();
         let mut current_text = String::new();
-        let mut chars = s.chars().peekable();
+        let mut chars = s.char_indices().peekable();
 
-        while let Some(ch) = chars.next() {
+        while let Some((index, ch)) = chars.next() {
             if ch == '{' {
                 // End of the static string part
                 if !current_text.is_empty() {
@@ -223,29 +501,35 @@ impl StatementParser {
                 let mut brace_count = 1;
 
                 // Consume characters until the matching '}' is found
-                while let Some(&next_ch) = chars.peek() {
+                while let Some(&(_, next_ch)) = chars.peek() {
                     if next_ch == '{' {
                         brace_count += 1;
-                        expr_content.push(chars.next().unwrap());
+                        expr_content.push(next_ch);
+                        chars.next();
                     } else if next_ch == '}' {
                         brace_count -= 1;
+                        chars.next();
                         if brace_count == 0 {
-                            chars.next(); // Consume the final '}'
-                            break;
-                        } else {
-                            expr_content.push(chars.next().unwrap());
+                            break; // The final '}' has been consumed
                         }
+                        expr_content.push(next_ch);
                     } else {
-                        expr_content.push(chars.next().unwrap());
+                        expr_content.push(next_ch);
+                        chars.next();
                     }
                 }
 
                 // Attempt to parse the extracted content as an expression
                 if !expr_content.trim().is_empty() {
-                    match Self::parse_interpolation_expression(&expr_content) {
-                        Ok(expr) => {
-                            parts.push(PrintPart::Expression(Box::new(expr)));
-                        }
+                    let (source, precision) = Self::split_precision(&expr_content);
+                    match Self::parse_interpolation_expression(source, offset + index + 1) {
+                        Ok(expr) => match precision {
+                            Some(precision) => parts.push(PrintPart::Formatted {
+                                expression: Box::new(expr),
+                                precision,
+                            }),
+                            None => parts.push(PrintPart::Expression(Box::new(expr))),
+                        },
                         Err(_) => {
                             // On failure, treat the full original {content} as a literal string
                             parts.push(PrintPart::String(format!("{{{}}}", expr_content)));
@@ -264,25 +548,58 @@ impl StatementParser {
         parts
     }
 
+    /// Splits a precision suffix (e.g., `:.2` in `{rate:.2}`) off interpolation content.
+    ///
+    /// Collection paths also use `:`, but never followed by `.`, so the suffix is unambiguous.
+    ///
+    /// # Returns
+    ///
+    /// The expression source and the requested number of decimal places, if any.
+    fn split_precision(content: &str) -> (&str, Option<usize>) {
+        match content.rsplit_once(":.") {
+            Some((expression, digits))
+                if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                (expression, digits.parse().ok())
+            }
+            _ => (content, None),
+        }
+    }
+
     /// Utility function to tokenize and parse a string slice as a standalone expression.
     ///
     /// This is necessary because interpolation content must be re-lexed and re-parsed.
-    fn parse_interpolation_expression(expr_str: &str) -> Result<Expression, CompileError> {
+    /// Token spans are shifted by `offset` so that they point into the source file.
+    fn parse_interpolation_expression(
+        expr_str: &str,
+        offset: usize,
+    ) -> Result<Expression, CompileError> {
         use crate::compiler::lexer::tokenize;
         use crate::compiler::parser::common::Parser;
         use crate::compiler::parser::expression_parser::ExpressionParser;
 
         let tokens = match tokenize(expr_str) {
             Ok(tokens) => tokens,
-            Err(_) => return Err(CompileError::parser("Failed to tokenize expression in interpolation")),
+            Err(_) => {
+                return Err(CompileError::parser(
+                    "Failed to tokenize expression in interpolation",
+                ));
+            }
         };
 
         // Filter out structural tokens (like Newline, Indent, Dedent) which aren't valid inside an expression
         let filtered_tokens: Vec<_> = tokens
             .into_iter()
-            .filter(|(token, _, _, _)| {
+            .filter(|(token, _, _)| {
                 !matches!(token, Token::Newline | Token::Indent | Token::Dedent)
             })
+            .map(|(token, span, lexeme)| {
+                (
+                    token,
+                    Span::new(span.start + offset, span.end + offset),
+                    lexeme,
+                )
+            })
             .collect();
 
         if filtered_tokens.is_empty() {
@@ -293,106 +610,405 @@ impl StatementParser {
         ExpressionParser::parse_expression(&mut parser)
     }
 
-    /// Parses the `loop` statement (e.g., `loop i, 1..10: ...`).
+    /// Parses the `loop` statement (e.g., `@ i, 1..10`, `@ score in scores` or `@ (n > 0)`).
     ///
-    /// This assumes an inclusive range loop structure.
+    /// A range is inclusive (`1..10`) or exclusive (`0..<10`) and may be followed by a step
+    /// (`@ i, 0..100 step 5`). Anything other than a loop
+    /// variable followed by `,` or `in` is the condition of a condition loop.
     fn parse_loop_statement(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let loop_start = parser.current_span().start;
         parser.advance(); // Consume 'loop' token
+
+        // Condition loop: `@ (cond)`
+        let has_variable = matches!(parser.peek().0, Token::Identifier(_))
+            && matches!(
+                parser.peek_next(),
+                Some(Token::Comma) | Some(Token::Identifier(_))
+            );
+        if !has_variable {
+            let condition = ExpressionParser::parse_expression(parser)?;
+            let span = parser.span_from(loop_start);
+            let body = Self::parse_block(parser)?;
+            return Ok(Some(Statement::new(
+                StatementKind::While {
+                    condition: Box::new(condition),
+                    body,
+                },
+                span,
+            )));
+        }
+
         let variable = parser.get_identifier();
         parser.advance(); // Consume loop variable identifier
+
+        // Element loop: `@ x in array`
+        if matches!(&parser.peek().0, Token::Identifier(word) if word == "in") {
+            parser.advance(); // Consume 'in'
+            let iterable = ExpressionParser::parse_expression(parser)?;
+            let span = parser.span_from(loop_start);
+            let body = Self::parse_block(parser)?;
+            return Ok(Some(Statement::new(
+                StatementKind::ForEach {
+                    variable,
+                    iterable: Box::new(iterable),
+                    body,
+                },
+                span,
+            )));
+        }
+
         parser.expect(Token::Comma)?; // Expect ','
 
         let start = ExpressionParser::parse_expression(parser)?;
-        parser.expect(Token::Range)?; // Expect '..'
+        let inclusive = match parser.peek().0 {
+            Token::Range => true,
+            Token::RangeExclusive => false,
+            ref found => {
+                return Err(parser.error(format!("Expected '..' or '..<', found {:?}", found)));
+            }
+        };
+        parser.advance(); // Consume '..' or '..<'
         let end = ExpressionParser::parse_expression(parser)?;
 
-        // Consume any newlines before the block
-        while parser.check(Token::Newline) {
-            parser.advance();
-        }
+        // Optional step: `step 2`
+        let step = if parser.check(Token::Step) {
+            parser.advance(); // Consume 'step'
+            Some(Box::new(ExpressionParser::parse_expression(parser)?))
+        } else {
+            None
+        };
+        let span = parser.span_from(loop_start);
 
         // Parse indented loop body
-        let mut body = Vec::new();
-        if parser.check(Token::Indent) {
-            parser.advance(); // Consume 'Indent'
-            while !parser.check(Token::Dedent) && !parser.is_at_end() {
-                if let Some(statement) = Self::parse_statement(parser)? {
-                    body.push(statement);
-                } else {
-                    parser.advance();
-                }
-            }
-            if parser.check(Token::Dedent) {
-                parser.advance(); // Consume 'Dedent'
-            }
-        }
-
-        Ok(Some(Statement::Loop {
-            variable,
-            start: Box::new(start),
-            end: Box::new(end),
-            body,
-        }))
+        let body = Self::parse_block(parser)?;
+
+        Ok(Some(Statement::new(
+            StatementKind::Loop {
+                variable,
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+                step,
+                body,
+            },
+            span,
+        )))
     }
 
     /// Parses the `if` and `if-else` conditional statements, handling block structure via indentation.
+    ///
+    /// An `!? (cond)` continues the statement as an else-if chain; a bare `!?` ends it.
     fn parse_if_statement(parser: &mut Parser) -> Result<Option<Statement>, CompileError> {
+        let start = parser.current_span().start;
         parser.advance(); // Consume 'if' token
-        let condition = ExpressionParser::parse_expression(parser)?;
+        Self::parse_conditional(parser, start).map(Some)
+    }
 
-        // Consume any newlines before the 'then' block
-        while parser.check(Token::Newline) {
-            parser.advance();
-        }
+    /// Parses a condition, its block and the optional `!?` that follows.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The parser, positioned after the `?` or `!?` that introduced the condition.
+    /// * `start` - The byte offset of that `?` or `!?`, where the statement's span begins.
+    ///
+    /// # Returns
+    ///
+    /// The `If` statement. An `!? (cond)` becomes an else branch holding a single nested `If`.
+    fn parse_conditional(parser: &mut Parser, start: usize) -> Result<Statement, CompileError> {
+        let condition = ExpressionParser::parse_expression(parser)?;
+        let span = parser.span_from(start);
 
         // Parse 'Then' Branch (Indented Block)
-        let mut then_branch = Vec::new();
-        if parser.check(Token::Indent) {
-            parser.advance();
-            while !parser.check(Token::Dedent) && !parser.is_at_end() {
-                if let Some(statement) = Self::parse_statement(parser)? {
-                    then_branch.push(statement);
-                } else {
-                    parser.advance();
-                }
-            }
-            if parser.check(Token::Dedent) {
-                parser.advance();
-            }
-        }
+        let then_branch = Self::parse_block(parser)?;
 
         // Parse 'Else' Branch (Optional)
         let else_branch = if parser.check(Token::Else) {
+            let else_start = parser.current_span().start;
             parser.advance(); // Consume 'else' token
-
-            // Consume newlines before the 'else' block
-            while parser.check(Token::Newline) {
-                parser.advance();
-            }
-
-            let mut else_statements = Vec::new();
-            if parser.check(Token::Indent) {
-                parser.advance();
-                while !parser.check(Token::Dedent) && !parser.is_at_end() {
-                    if let Some(statement) = Self::parse_statement(parser)? {
-                        else_statements.push(statement);
-                    } else {
-                        parser.advance();
-                    }
-                }
-                if parser.check(Token::Dedent) {
-                    parser.advance();
-                }
+            if parser.is_at_end() || parser.check(Token::Newline) || parser.check(Token::Indent) {
+                Some(Self::parse_block(parser)?)
+            } else {
+                // 'else if': the rest of the chain is the only statement of the else branch.
+                Some(vec![Self::parse_conditional(parser, else_start)?])
             }
-            Some(else_statements)
         } else {
             None
         };
 
-        Ok(Some(Statement::If {
-            condition: Box::new(condition),
-            then_branch,
-            else_branch,
-        }))
+        Ok(Statement::new(
+            StatementKind::If {
+                condition: Box::new(condition),
+                then_branch,
+                else_branch,
+            },
+            span,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::nodes::*;
+    use crate::compiler::lexer::tokenize;
+    use crate::compiler::parser::parse;
+    use crate::compiler::span::SourceMap;
+
+    /// Parses `source` and returns the kinds of its top-level statements.
+    fn statements(source: &str) -> Vec<StatementKind> {
+        parse(tokenize(source).expect("source should tokenize"))
+            .expect("source should parse")
+            .statements
+            .into_iter()
+            .map(|statement| statement.kind)
+            .collect()
+    }
+
+    #[test]
+    fn call_needs_the_parenthesis_next_to_the_name() {
+        let kinds = statements("add(1, 2)\ntotal (a + b)\n");
+        assert!(matches!(
+            &kinds[0],
+            StatementKind::Call { function, arguments } if function == "add" && arguments.len() == 2
+        ));
+        assert!(matches!(
+            &kinds[1],
+            StatementKind::VariableDeclaration { name, .. } if name == "total"
+        ));
+    }
+
+    #[test]
+    fn function_definition_with_body() {
+        let kinds = statements("# add(a, b)\n    => a + b\n");
+        let StatementKind::Function {
+            name,
+            parameters,
+            body,
+        } = &kinds[0]
+        else {
+            panic!("expected a function, found {:?}", kinds[0]);
+        };
+        assert_eq!(name, "add");
+        assert_eq!(parameters, &["a", "b"]);
+        assert!(matches!(
+            body[..],
+            [Statement {
+                kind: StatementKind::Return { value: Some(_) },
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn array_statements() {
+        let kinds =
+            statements("scores [72, 95]\nscores{1} 90\n@ score in scores\n    ! \"{score}\"\n");
+        assert!(matches!(
+            &kinds[0],
+            StatementKind::ArrayDeclaration { name, elements } if name == "scores" && elements.len() == 2
+        ));
+        assert!(matches!(
+            &kinds[1],
+            StatementKind::ArrayAssignment { array, .. } if array == "scores"
+        ));
+        assert!(matches!(
+            &kinds[2],
+            StatementKind::ForEach { variable, body, .. } if variable == "score" && body.len() == 1
+        ));
+    }
+
+    #[test]
+    fn nested_collection_and_path_access() {
+        let kinds = statements(
+            "[] cars as c\n    [] brands as b\n        \"Toyota\", \"Honda\"\n    [] prices as p\n        21000, 19500\nfirst c:b:0\n! \"{[]cars:p{1}}\"\n",
+        );
+        let StatementKind::CollectionDeclaration { collection } = &kinds[0] else {
+            panic!("expected a collection, found {:?}", kinds[0]);
+        };
+        assert_eq!(
+            (collection.name.as_str(), collection.alias.as_deref()),
+            ("cars", Some("c"))
+        );
+        let children: Vec<_> = collection
+            .children
+            .iter()
+            .map(|child| {
+                (
+                    child.name.as_str(),
+                    child.alias.as_deref(),
+                    child.elements.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            children,
+            [("brands", Some("b"), 2), ("prices", Some("p"), 2)]
+        );
+
+        let StatementKind::VariableDeclaration { value, .. } = &kinds[1] else {
+            panic!("expected a declaration, found {:?}", kinds[1]);
+        };
+        assert!(matches!(
+            &value.kind,
+            ExpressionKind::CollectionAccess { root, by_name: false, path, index: Some(_) }
+                if root == "c" && path == &["b"]
+        ));
+        let StatementKind::Print { parts, .. } = &kinds[2] else {
+            panic!("expected a print, found {:?}", kinds[2]);
+        };
+        assert!(matches!(
+            &parts[..],
+            [PrintPart::Expression(expression)] if matches!(
+                &expression.kind,
+                ExpressionKind::CollectionAccess { root, by_name: true, path, index: Some(_) }
+                    if root == "cars" && path == &["p"]
+            )
+        ));
+    }
+
+    #[test]
+    fn statements_and_expressions_carry_spans() {
+        let source = "x 1\ntotal (x + 2) * 3\n";
+        let program = parse(tokenize(source).unwrap()).unwrap();
+        let statement = &program.statements[1];
+        assert_eq!(
+            &source[statement.span.start..statement.span.end],
+            "total (x + 2) * 3"
+        );
+        let StatementKind::VariableDeclaration { value, .. } = &statement.kind else {
+            panic!("expected a declaration, found {:?}", statement.kind);
+        };
+        assert_eq!(&source[value.span.start..value.span.end], "(x + 2) * 3");
+    }
+
+    #[test]
+    fn recovery_reports_every_broken_statement() {
+        let source = "x (1 +\n? (x >\n    y 2\n    z 3\nw (4\n! \"ok\"\n";
+        let errors = parse(tokenize(source).unwrap()).unwrap_err().into_errors();
+        let lines: Vec<usize> = errors
+            .iter()
+            .map(|error| {
+                SourceMap::new(source)
+                    .location(error.span().unwrap().start)
+                    .line
+            })
+            .collect();
+        // The block under the broken `?` is skipped with it, so its lines report nothing.
+        assert_eq!(lines, [1, 2, 5], "{:?}", errors);
+    }
+
+    #[test]
+    fn braces_after_a_name_index_or_slice() {
+        let kinds = statements("t s{1}\nu s{0..n}\n");
+        let values: Vec<&ExpressionKind> = kinds
+            .iter()
+            .map(|kind| match kind {
+                StatementKind::VariableDeclaration { value, .. } => &value.kind,
+                other => panic!("expected a declaration, found {:?}", other),
+            })
+            .collect();
+        assert!(matches!(values[0], ExpressionKind::ArrayAccess { array, .. } if array == "s"));
+        assert!(matches!(
+            values[1],
+            ExpressionKind::Slice { string, end, .. }
+                if string == "s" && matches!(&end.kind, ExpressionKind::Variable(name) if name == "n")
+        ));
+    }
+
+    #[test]
+    fn reassignment_and_compound_assignment() {
+        let kinds = statements("x 1\nx 2\nx += 1\nx -= 1\nx *= 1\nx /= 1\nx %= 1\nx ^= 1\n");
+        assert!(
+            matches!(&kinds[0], StatementKind::VariableDeclaration { name, .. } if name == "x")
+        );
+        let operators: Vec<String> = kinds[1..]
+            .iter()
+            .map(|kind| match kind {
+                StatementKind::Assignment { name, operator, .. } if name == "x" => {
+                    format!("{:?}", operator)
+                }
+                other => panic!("expected an assignment to x, found {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            operators,
+            [
+                "Assign", "Plus", "Minus", "Multiply", "Divide", "Modulo", "Power"
+            ]
+        );
+    }
+
+    #[test]
+    fn else_if_chains_nest_in_the_else_branch() {
+        let kinds = statements("? (x > 3)\n    y 1\n!? (x > 1)\n    y 2\n!?\n    y 3\n");
+        assert_eq!(kinds.len(), 1);
+        let StatementKind::If {
+            else_branch: Some(else_branch),
+            ..
+        } = &kinds[0]
+        else {
+            panic!("expected an if with an else branch, found {:?}", kinds[0]);
+        };
+        assert!(matches!(
+            &else_branch[..],
+            [Statement {
+                kind: StatementKind::If {
+                    else_branch: Some(last),
+                    ..
+                },
+                ..
+            }] if last.len() == 1
+        ));
+    }
+
+    #[test]
+    fn condition_loops_with_break_and_continue() {
+        let kinds = statements("@ (i < 10)\n    break\n    continue\n");
+        let StatementKind::While { condition, body } = &kinds[0] else {
+            panic!("expected a while loop, found {:?}", kinds[0]);
+        };
+        assert!(matches!(condition.kind, ExpressionKind::Binary { .. }));
+        assert!(matches!(
+            &body[..],
+            [
+                Statement {
+                    kind: StatementKind::Break,
+                    ..
+                },
+                Statement {
+                    kind: StatementKind::Continue,
+                    ..
+                }
+            ]
+        ));
+    }
+
+    #[test]
+    fn range_loops_with_exclusive_ends_and_steps() {
+        let kinds = statements("@ i, 1..10\n    x i\n@ i, 10..<0 step -2\n    x i\n");
+        assert!(matches!(
+            &kinds[0],
+            StatementKind::Loop { variable, inclusive: true, step: None, .. } if variable == "i"
+        ));
+        assert!(matches!(
+            &kinds[1],
+            StatementKind::Loop { inclusive: false, step: Some(step), .. }
+                if matches!(step.kind, ExpressionKind::Integer(-2))
+        ));
+    }
+
+    #[test]
+    fn compound_assignment_needs_a_variable_name() {
+        let source = "q [1, 2]\nq{0} += 5\n";
+        let errors = parse(tokenize(source).unwrap()).unwrap_err().into_errors();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(
+            errors[0]
+                .to_string()
+                .contains("Compound assignment '+=' requires a variable name"),
+            "{}",
+            errors[0]
+        );
+        let span = errors[0].span().unwrap();
+        assert_eq!(&source[span.start..span.end], "+=");
     }
-}
\ No newline at end of file
+}
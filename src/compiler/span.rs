@@ -0,0 +1,178 @@
+//! # Source Spans
+//!
+//! This module defines [`Span`], the byte range a token, AST node or error covers in
+//! the original source, and [`SourceMap`], which turns those byte offsets into the
+//! line/column positions shown to the user.
+//!
+//! Spans are created by the lexer, carried by every token and AST node, and attached
+//! to errors so that each diagnostic can point at the exact place in the `.neb` file.
+
+use std::fmt;
+
+/// A half-open byte range (`start..end`) in the source code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// The byte offset of the first character.
+    pub start: usize,
+    /// The byte offset just past the last character.
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a span covering `start..end`.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The byte offset of the first character.
+    /// * `end` - The byte offset just past the last character.
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the smallest span covering both `self` and `other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The span to merge with (e.g., the right operand of a binary expression).
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns the number of bytes covered by the span.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Checks whether the span covers no bytes (e.g., the end of the file).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A 1-based line and column position in the source code.
+///
+/// The column counts characters, not bytes, so that it matches what editors show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column number, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Maps byte offsets in a source file to line/column locations.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    /// The full source text.
+    source: String,
+    /// The byte offset at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    /// Builds the line index for a source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The raw Nebulang source code.
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self {
+            source: source.to_string(),
+            line_starts,
+        }
+    }
+
+    /// Converts a byte offset into a line/column location.
+    ///
+    /// Offsets past the end of the source map to the position just after the last character.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - A byte offset into the source.
+    pub fn location(&self, offset: usize) -> Location {
+        let offset = offset.min(self.source.len());
+        let line_index = self
+            .line_starts
+            .partition_point(|&line_start| line_start <= offset)
+            - 1;
+        let line_start = self.line_starts[line_index];
+
+        // Count characters rather than bytes; an offset inside a character rounds down.
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(index, _)| line_start + index < offset)
+            .count()
+            + 1;
+
+        Location {
+            line: line_index + 1,
+            column,
+        }
+    }
+
+    /// Returns the text of a line, without its line terminator.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The 1-based line number.
+    pub fn line_text(&self, line: usize) -> &str {
+        let Some(&start) = self.line_starts.get(line.wrapping_sub(1)) else {
+            return "";
+        };
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |&next| next);
+
+        self.source[start..end].trim_end_matches(['\n', '\r'])
+    }
+
+    /// Returns the number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_merge_and_measure() {
+        let span = Span::new(4, 6).to(Span::new(0, 2));
+        assert_eq!(span, Span::new(0, 6));
+        assert_eq!(span.len(), 6);
+        assert!(Span::new(3, 3).is_empty());
+    }
+
+    #[test]
+    fn locations_count_lines_and_characters() {
+        let map = SourceMap::new("x 1\n! \"é {y}\"\n");
+        assert_eq!(map.location(0), Location { line: 1, column: 1 });
+        assert_eq!(map.location(4), Location { line: 2, column: 1 });
+        // `é` takes two bytes but one column.
+        assert_eq!(map.location(10), Location { line: 2, column: 6 });
+        assert_eq!(map.location(100), Location { line: 3, column: 1 });
+        assert_eq!(map.location(4).to_string(), "2:1");
+    }
+
+    #[test]
+    fn line_text_strips_the_terminator() {
+        let map = SourceMap::new("first\r\nsecond");
+        assert_eq!(map.line_count(), 2);
+        assert_eq!(map.line_text(1), "first");
+        assert_eq!(map.line_text(2), "second");
+        assert_eq!(map.line_text(0), "");
+        assert_eq!(map.line_text(3), "");
+    }
+}
@@ -4,16 +4,13 @@
 //! interface (CLI). It uses the `clap` crate to parse user arguments and
 //! orchestrates the `compiler::Compiler` to perform build, run, and test actions.
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgMatches, Command};
 use std::path::PathBuf;
 
-// Import internal compiler components.
-mod ast;
-mod compiler;
-// mod vm; // Virtual machine module
-mod codegen;
-
-use compiler::Compiler;
+// Import the compiler API from the library crate.
+use nebc::Compiler;
+use nebc::codegen::common::OverflowMode;
+use nebc::compiler::error_codes;
 use std::process;
 
 fn main() {
@@ -22,6 +19,23 @@ fn main() {
         .version("0.1.0")
         .author("Nebula Project Systems")
         .about("🌌 Nebulang Quantum Compiler")
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .global(true)
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .help("When to color error messages"),
+        )
+        .arg(
+            Arg::new("error-limit")
+                .long("error-limit")
+                .value_name("N")
+                .global(true)
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .help("Print at most N errors per file"),
+        )
         // --- 'build' Subcommand ---
         .subcommand(
             Command::new("build")
@@ -55,7 +69,8 @@ fn main() {
                         .long("verbose")
                         .action(clap::ArgAction::SetTrue)
                         .help("Show step-by-step compilation process"),
-                ),
+                )
+                .args(code_generation_args()),
         )
         // --- 'run' Subcommand ---
         .subcommand(
@@ -66,6 +81,31 @@ fn main() {
                         .required(true)
                         .value_name("FILE")
                         .help("Nebulang source file to run"),
+                )
+                .arg(
+                    Arg::new("stdin")
+                        .long("stdin")
+                        .value_name("FILE")
+                        .help("Feed FILE to the program's standard input (default: inherit it)"),
+                )
+                .arg(
+                    Arg::new("args")
+                        .value_name("ARGS")
+                        .num_args(0..)
+                        .last(true)
+                        .help("Arguments passed to the program, after `--`"),
+                )
+                .args(code_generation_args()),
+        )
+        // --- 'explain' Subcommand ---
+        .subcommand(
+            Command::new("explain")
+                .about("Explain an error code (e.g., NEB0003)")
+                .arg(
+                    Arg::new("code")
+                        .required(true)
+                        .value_name("CODE")
+                        .help("Error code shown in a diagnostic"),
                 ),
         )
         // --- 'test' Subcommand ---
@@ -76,12 +116,26 @@ fn main() {
                     Arg::new("file")
                         .value_name("FILE")
                         .help("Specific file to test"),
-                ),
+                )
+                .arg(
+                    Arg::new("stdin")
+                        .long("stdin")
+                        .value_name("FILE")
+                        .help("Feed FILE to the standard input of every tested program"),
+                )
+                .args(code_generation_args()),
         )
         .get_matches();
 
     // Initialize the main compiler instance with default settings.
     let mut compiler = Compiler::new();
+    compiler.color = matches
+        .get_one::<String>("color")
+        .and_then(|choice| choice.parse().ok())
+        .unwrap_or_default();
+    compiler.error_limit = matches
+        .get_one::<u32>("error-limit")
+        .map(|&limit| limit as usize);
 
     // Dispatch the command based on the user's input.
     match matches.subcommand() {
@@ -100,6 +154,7 @@ fn main() {
             compiler.show_asm = sub_matches.get_flag("show-asm");
             compiler.no_protection = sub_matches.get_flag("no-protection");
             compiler.verbose = sub_matches.get_flag("verbose");
+            apply_code_generation_args(&mut compiler, sub_matches);
 
             // Execute the build command.
             if let Err(e) = compiler.build(target) {
@@ -111,6 +166,12 @@ fn main() {
             // Set source file path.
             let file = sub_matches.get_one::<String>("file").unwrap();
             compiler.source_path = PathBuf::from(file);
+            apply_code_generation_args(&mut compiler, sub_matches);
+            compiler.stdin_path = sub_matches.get_one::<String>("stdin").map(PathBuf::from);
+            compiler.program_args = sub_matches
+                .get_many::<String>("args")
+                .map(|args| args.cloned().collect())
+                .unwrap_or_default();
 
             // Execute the run command (which includes build and execute).
             if let Err(e) = compiler.run_single_file() {
@@ -121,6 +182,8 @@ fn main() {
         Some(("test", sub_matches)) => {
             // Get optional specific file to test.
             let file = sub_matches.get_one::<String>("file").map(PathBuf::from);
+            apply_code_generation_args(&mut compiler, sub_matches);
+            compiler.stdin_path = sub_matches.get_one::<String>("stdin").map(PathBuf::from);
 
             // Execute the test command.
             if let Err(e) = compiler.test(file) {
@@ -128,6 +191,21 @@ fn main() {
                 process::exit(1);
             }
         }
+        Some(("explain", sub_matches)) => {
+            let code = sub_matches.get_one::<String>("code").unwrap();
+
+            // Print the long-form explanation from the error code registry.
+            match error_codes::lookup(code) {
+                Some(entry) => {
+                    println!("{}: {}\n", entry.code, entry.title);
+                    print!("{}", entry.explanation);
+                }
+                None => {
+                    eprintln!("❌ Explain failed: '{}' is not a known error code", code);
+                    process::exit(1);
+                }
+            }
+        }
         // Default case: show help message.
         _ => {
             println!("🌌 Nebulang Quantum Compiler (NEBC)");
@@ -135,3 +213,56 @@ fn main() {
         }
     }
 }
+
+/// Defines the code generation flags shared by `build`, `run` and `test`.
+///
+/// # Returns
+///
+/// The `--no-bounds-check`, `--release`, `--overflow` and `--message-format` arguments.
+fn code_generation_args() -> [Arg; 4] {
+    [
+        Arg::new("no-bounds-check")
+            .long("no-bounds-check")
+            .action(clap::ArgAction::SetTrue)
+            .help("Disable runtime array bounds checks"),
+        Arg::new("release")
+            .long("release")
+            .action(clap::ArgAction::SetTrue)
+            .help("Build for release: integer overflow wraps unless --overflow is given"),
+        Arg::new("overflow")
+            .long("overflow")
+            .value_name("MODE")
+            .value_parser(["wrap", "trap", "saturate"])
+            .help("What integer overflow does (default: trap, or wrap with --release)"),
+        Arg::new("message-format")
+            .long("message-format")
+            .value_name("FORMAT")
+            .value_parser(["human", "json"])
+            .default_value("human")
+            .help("Print diagnostics as rendered text or as JSON lines"),
+    ]
+}
+
+/// Copies the code generation flags shared by `build`, `run` and `test` into the compiler.
+///
+/// # Arguments
+///
+/// * `compiler` - The compiler to configure.
+/// * `sub_matches` - The parsed arguments of the subcommand.
+fn apply_code_generation_args(compiler: &mut Compiler, sub_matches: &ArgMatches) {
+    compiler.no_bounds_check = sub_matches.get_flag("no-bounds-check");
+    // Debug builds trap on overflow; release builds wrap like the hardware.
+    let default_overflow = if sub_matches.get_flag("release") {
+        OverflowMode::Wrap
+    } else {
+        OverflowMode::Trap
+    };
+    compiler.overflow = sub_matches
+        .get_one::<String>("overflow")
+        .and_then(|mode| mode.parse().ok())
+        .unwrap_or(default_overflow);
+    compiler.message_format = sub_matches
+        .get_one::<String>("message-format")
+        .and_then(|format| format.parse().ok())
+        .unwrap_or_default();
+}